@@ -0,0 +1,86 @@
+// How long the sums for a frame take, without a canvas to draw on: `render::frame`, which is all of
+// `render::render` but the canvas calls, since those can't run outside a browser. These are ignored
+// by a plain `cargo test`; run them with
+//
+//     cargo test --release bench -- --ignored --nocapture --test-threads 1
+//
+// Each prints the average time per frame.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::hyper::{Polytope, Regular};
+use crate::mesh::Mesh;
+use crate::point::{Vec2, Vec3};
+use crate::render::{self, HiddenLines, Shading, Style};
+use crate::solid::Solids;
+
+// how long to keep drawing frames for each one
+const RUN_FOR: Duration = Duration::from_secs(2);
+
+fn time_frames(name: &str, style: Style, meshes: Vec<Mesh>) {
+    let dims = Vec2::new([1920.0, 1080.0]);
+    let camera = Camera::new(Vec3::new([0.0, 0.0, 10.0]));
+    let mut meshes = meshes;
+    let mut solids = Solids::default();
+
+    // warm up
+    for _ in 0..10 {
+        black_box(render::frame(&dims, &camera, &style, &meshes, &[], &mut solids));
+    }
+
+    let mut frames = 0;
+    let start = Instant::now();
+    while start.elapsed() < RUN_FOR {
+        // turn the meshes like `go` would so nothing gets to be the same twice
+        render::tic(&dims, &camera, &mut meshes);
+        black_box(render::frame(&dims, &camera, &style, black_box(&meshes), &[], &mut solids));
+        frames += 1;
+    }
+    let per_frame = start.elapsed() / frames;
+
+    eprintln!("{:<28} {:>10.1} us/frame ({} frames)", name, per_frame.as_secs_f64() * 1e6, frames);
+}
+
+#[test]
+#[ignore]
+fn bench_wireframe_cube() {
+    time_frames("wireframe cube", Style::default(), vec![Mesh::mk_cube()]);
+}
+
+#[test]
+#[ignore]
+fn bench_wireframe_120_cell() {
+    let mesh = Polytope::<4>::mk_regular(Regular::HundredTwentyCell).to_mesh();
+    time_frames("wireframe 120-cell", Style::default(), vec![mesh]);
+}
+
+#[test]
+#[ignore]
+fn bench_wireframe_torus() {
+    let mesh = Mesh::mk_torus(1.0, 0.4, 64, 32).expect("a fine torus");
+    time_frames("wireframe torus 64x32", Style::default(), vec![mesh]);
+}
+
+#[test]
+#[ignore]
+fn bench_hidden_lines_torus() {
+    let style = Style {
+        hidden_lines: HiddenLines::Dash,
+        ..Style::default()
+    };
+    let mesh = Mesh::mk_torus(1.0, 0.4, 24, 12).expect("a fine torus");
+    time_frames("hidden lines torus 24x12", style, vec![mesh]);
+}
+
+#[test]
+#[ignore]
+fn bench_solid_torus() {
+    let style = Style {
+        solid: Some(Shading::default()),
+        ..Style::default()
+    };
+    let mesh = Mesh::mk_torus(1.0, 0.4, 64, 32).expect("a fine torus");
+    time_frames("solid torus 64x32", style, vec![mesh]);
+}
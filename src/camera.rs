@@ -1,5 +1,7 @@
 use crate::point::Vec3;
 
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Camera {
     pub origin: Vec3,
     pub target: Vec3,
@@ -9,7 +11,7 @@ pub struct Camera {
 impl Camera {
     pub fn new(origin: Vec3) -> Self {
         Self {
-            origin: origin,
+            origin,
             target: Vec3::zeroes(),
             up: Vec3::new([0.0, 1.0, 0.0]),
         }
@@ -0,0 +1,457 @@
+// Conway polyhedron notation. A notation string is a seed polyhedron with operators applied right
+// to left, so "tkD" truncates the kis of the dodecahedron. Seeds are `T`, `C`, `O`, `D` and `I`
+// (the Platonic solids) and `P`, `A` and `B` followed by a number of sides (prisms, antiprisms and
+// bipyramids). Every operator boils down to some mix of dual, ambo, kis, gyro and reflect, which
+// are the only ones that touch the mesh; see `Operator::primitives`.
+//
+// The operators need a closed, consistently wound surface to work on (the seeds all are, and so is
+// everything the operators make from them). New vertices are put roughly where they'd go on a
+// sphere rather than canonicalized, so faces aren't always quite flat.
+
+use std::collections::HashMap;
+
+use crate::error::message_error;
+use crate::mesh::{canonical, Face, Line, Mesh};
+use crate::point::{Element, Vec3};
+use crate::polyhedra::Platonic;
+use crate::topology::HalfEdges;
+
+message_error!(ConwayError, "Conway notation");
+
+// Most lines a notation can make. Operators multiply the size (k, t and s about triple it) so a
+// handful more letters goes from busy to hanging the page.
+pub const MAX_LINES: usize = 10_000;
+// Most sides a prism, antiprism or bipyramid seed can have.
+pub const MAX_SIDES: usize = 1_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Dual,
+    Ambo,
+    Kis,
+    Gyro,
+    Reflect,
+    Truncate,
+    Join,
+    Expand,
+    Ortho,
+    Bevel,
+    Meta,
+    Needle,
+    Zip,
+    Snub,
+}
+
+impl Operator {
+    pub const ALL: [Operator; 14] = [
+        Operator::Dual,
+        Operator::Ambo,
+        Operator::Kis,
+        Operator::Gyro,
+        Operator::Reflect,
+        Operator::Truncate,
+        Operator::Join,
+        Operator::Expand,
+        Operator::Ortho,
+        Operator::Bevel,
+        Operator::Meta,
+        Operator::Needle,
+        Operator::Zip,
+        Operator::Snub,
+    ];
+
+    pub fn letter(self) -> char {
+        match self {
+            Operator::Dual => 'd',
+            Operator::Ambo => 'a',
+            Operator::Kis => 'k',
+            Operator::Gyro => 'g',
+            Operator::Reflect => 'r',
+            Operator::Truncate => 't',
+            Operator::Join => 'j',
+            Operator::Expand => 'e',
+            Operator::Ortho => 'o',
+            Operator::Bevel => 'b',
+            Operator::Meta => 'm',
+            Operator::Needle => 'n',
+            Operator::Zip => 'z',
+            Operator::Snub => 's',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|op| op.letter() == letter)
+    }
+
+    // The same operator in terms of the ones that do the work, in notation order (applied right to
+    // left), e.g. truncate is "dkd".
+    pub fn primitives(self) -> &'static str {
+        match self {
+            Operator::Dual => "d",
+            Operator::Ambo => "a",
+            Operator::Kis => "k",
+            Operator::Gyro => "g",
+            Operator::Reflect => "r",
+            Operator::Truncate => "dkd",
+            Operator::Join => "da",
+            Operator::Expand => "aa",
+            Operator::Ortho => "daa",
+            Operator::Bevel => "dkda",
+            Operator::Meta => "kda",
+            Operator::Needle => "kd",
+            Operator::Zip => "dk",
+            Operator::Snub => "dg",
+        }
+    }
+}
+
+impl Mesh {
+    // Build the polyhedron a notation string like "tkD" or "dA5" describes.
+    pub fn from_conway(notation: &str) -> Result<Mesh, ConwayError> {
+        let notation = notation.trim();
+        let seed_at = notation.find(|c: char| c.is_ascii_uppercase())
+            .ok_or_else(|| ConwayError { message: format!("{:?} has no seed (T, C, O, D, I, P, A or B)", notation) })?;
+        let (operators, seed) = notation.split_at(seed_at);
+
+        let operators = operators.chars()
+            .rev()
+            .map(|letter| Operator::from_letter(letter)
+                .ok_or_else(|| ConwayError { message: format!("unknown operator {:?} in {:?}", letter, notation) }))
+            .collect::<Result<Vec<Operator>, ConwayError>>()?;
+
+        let mut mesh = conway_seed(seed)?;
+        // How big it's going to get, before doing any of the work. No operator takes lines away, so
+        // we can stop counting as soon as there are too many (and long before the counts overflow).
+        let mut counts = (mesh.vertices.len(), mesh.lines.len(), mesh.faces.len());
+        for letter in operators.iter().flat_map(|op| op.primitives().chars().rev()) {
+            counts = grow(counts, letter);
+            if counts.1 > MAX_LINES {
+                return error(format!("{:?} would have at least {} lines, at most {} are allowed", notation, counts.1, MAX_LINES));
+            }
+        }
+
+        for op in operators {
+            mesh = mesh.apply_conway(op)?;
+        }
+
+        Ok(mesh)
+    }
+
+    pub fn apply_conway(&self, op: Operator) -> Result<Mesh, ConwayError> {
+        let mut mesh = self.clone();
+        for letter in op.primitives().chars().rev() {
+            mesh = match letter {
+                'd' => dual(&mesh)?,
+                'a' => ambo(&mesh)?,
+                'k' => kis(&mesh)?,
+                'g' => gyro(&mesh)?,
+                'r' => reflect(&mesh)?,
+                _ => unreachable!("primitives are only made of d, a, k, g and r"),
+            };
+        }
+
+        Ok(mesh)
+    }
+}
+
+// (vertices, lines, faces) of a closed polyhedron after the primitive operator `letter`.
+fn grow((v, e, f): (usize, usize, usize), letter: char) -> (usize, usize, usize) {
+    match letter {
+        'd' => (f, e, v),
+        'a' => (e, 2 * e, v + f),
+        'k' => (v + f, 3 * e, 2 * e),
+        'g' => (v + 2 * e + f, 5 * e, 2 * e),
+        // (reflect only mirrors it)
+        _ => (v, e, f),
+    }
+}
+
+fn conway_seed(seed: &str) -> Result<Mesh, ConwayError> {
+    let mut letters = seed.chars();
+    let letter = letters.next();
+    let rest = letters.as_str();
+
+    let platonic = match letter {
+        Some('T') => Some(Platonic::Tetrahedron),
+        Some('C') => Some(Platonic::Cube),
+        Some('O') => Some(Platonic::Octahedron),
+        Some('D') => Some(Platonic::Dodecahedron),
+        Some('I') => Some(Platonic::Icosahedron),
+        _ => None,
+    };
+    if let Some(platonic) = platonic {
+        if !rest.is_empty() {
+            return error(format!("unexpected {:?} after seed {:?}", rest, seed));
+        }
+        return Ok(Mesh::mk_platonic(platonic));
+    }
+
+    let sides = rest.parse::<usize>()
+        .map_err(|_| ConwayError { message: format!("seed {:?} needs a number of sides", seed) })?;
+    if sides > MAX_SIDES {
+        return error(format!("seed {:?} has too many sides, at most {} are allowed", seed, MAX_SIDES));
+    }
+    let mesh = match letter {
+        Some('P') => Mesh::mk_prism(sides),
+        Some('A') => Mesh::mk_antiprism(sides),
+        Some('B') => Mesh::mk_bipyramid(sides),
+        _ => return error(format!("unknown seed {:?}", seed)),
+    };
+    mesh.or_else(|e| error(format!("seed {:?}: {}", seed, e)))
+}
+
+// The half-edges of `mesh`, as long as it's something the operators can work on.
+fn surface(mesh: &Mesh) -> Result<HalfEdges, ConwayError> {
+    if mesh.faces.is_empty() {
+        return error("the mesh has no faces to work on".to_string());
+    }
+    let half_edges = mesh.half_edges();
+    if !half_edges.is_closed() || !half_edges.is_manifold() {
+        return error("the mesh has to be a closed, consistently wound surface".to_string());
+    }
+
+    Ok(half_edges)
+}
+
+// The outgoing half-edges around `vertex` in order, turning the same way the faces are wound.
+fn fan(half_edges: &HalfEdges, vertex: usize) -> Vec<usize> {
+    let start = half_edges.outgoing[vertex][0];
+    let mut out = vec![start];
+    let mut h = start;
+    loop {
+        let prev = half_edges.half_edges[h].prev;
+        h = half_edges.half_edges[prev].twin.expect("closed surfaces have every twin");
+        if h == start || out.len() > half_edges.outgoing[vertex].len() {
+            break;
+        }
+        out.push(h);
+    }
+
+    out
+}
+
+fn centroid(vertices: &[Vec3], face: &Face) -> Vec3 {
+    face.iter()
+        .fold(Vec3::zeroes(), |acc, &v| acc + vertices[v])
+        .scale(1.0 / face.len() as Element)
+}
+
+// The middle of `face`, pushed out to the average distance of its corners from the centre so it
+// doesn't leave a flat spot.
+fn lifted_centroid(vertices: &[Vec3], face: &Face) -> Vec3 {
+    let centre = centroid(vertices, face);
+    let radius = face.iter().map(|&v| vertices[v].magnitude()).sum::<Element>() / face.len() as Element;
+    if centre.magnitude() > 0.0 {
+        centre.scale(radius / centre.magnitude())
+    } else {
+        centre
+    }
+}
+
+// Scale everything into the unit sphere, like the seeds, so repeated operators don't drift.
+fn finish(vertices: Vec<Vec3>, faces: Vec<Face>) -> Result<Mesh, ConwayError> {
+    let radius = vertices.iter().map(Vec3::magnitude).fold(0.0, Element::max);
+    let vertices = if radius > 0.0 {
+        vertices.iter().map(|v| v.scale(1.0 / radius)).collect()
+    } else {
+        vertices
+    };
+
+    Mesh::with_faces(Vec3::zeroes(), vertices, faces)
+        .or_else(|e| error(format!("made a bad mesh: {}", e)))
+}
+
+// A vertex for every face and a face for every vertex. The new vertices are the poles of the faces'
+// planes (reciprocation in the unit sphere), so a convex polyhedron's dual comes out flat too.
+fn dual(mesh: &Mesh) -> Result<Mesh, ConwayError> {
+    let half_edges = surface(mesh)?;
+
+    let vertices = mesh.faces.iter()
+        .zip(mesh.face_normals())
+        .map(|(face, normal)| {
+            let distance = normal.dot(&centroid(&mesh.vertices, face));
+            if distance > 1e-6 {
+                normal.scale(1.0 / distance)
+            } else {
+                centroid(&mesh.vertices, face)
+            }
+        })
+        .collect::<Vec<Vec3>>();
+
+    let faces = (0..mesh.vertices.len())
+        .filter(|&v| !half_edges.outgoing[v].is_empty())
+        .map(|v| fan(&half_edges, v).into_iter().map(|h| half_edges.half_edges[h].face).collect())
+        .collect();
+
+    finish(vertices, faces)
+}
+
+// A vertex in the middle of every edge; each face shrinks to the one through its edges' middles
+// and each vertex becomes a face through the middles of the edges around it.
+fn ambo(mesh: &Mesh) -> Result<Mesh, ConwayError> {
+    let half_edges = surface(mesh)?;
+
+    let mut middles = HashMap::<Line, usize>::new();
+    let mut vertices = vec![];
+    let mut middle = |a: usize, b: usize| -> usize {
+        *middles.entry(canonical((a, b))).or_insert_with(|| {
+            vertices.push((mesh.vertices[a] + mesh.vertices[b]).scale(0.5));
+            vertices.len() - 1
+        })
+    };
+
+    let mut faces = vec![];
+    for face in mesh.faces.iter() {
+        faces.push((0..face.len()).map(|i| middle(face[i], face[(i + 1) % face.len()])).collect());
+    }
+    for v in 0..mesh.vertices.len() {
+        if half_edges.outgoing[v].is_empty() {
+            continue;
+        }
+        faces.push(fan(&half_edges, v)
+            .into_iter()
+            .map(|h| middle(v, half_edges.destination(h)))
+            .collect());
+    }
+
+    finish(vertices, faces)
+}
+
+// A pyramid on every face.
+fn kis(mesh: &Mesh) -> Result<Mesh, ConwayError> {
+    surface(mesh)?;
+
+    let mut vertices = mesh.vertices.clone();
+    let mut faces = vec![];
+    for face in mesh.faces.iter() {
+        let apex = vertices.len();
+        vertices.push(lifted_centroid(&mesh.vertices, face));
+        for i in 0..face.len() {
+            faces.push(vec![face[i], face[(i + 1) % face.len()], apex]);
+        }
+    }
+
+    finish(vertices, faces)
+}
+
+// Every edge is cut in three and every face is split into pentagons by spokes from its middle to
+// one of the cuts on each of its edges, all twisting the same way.
+fn gyro(mesh: &Mesh) -> Result<Mesh, ConwayError> {
+    surface(mesh)?;
+
+    let mut vertices = mesh.vertices.clone();
+    // (from, to) -> the point a third of the way along
+    let mut thirds = HashMap::<(usize, usize), usize>::new();
+    let mut third = |vertices: &mut Vec<Vec3>, a: usize, b: usize| -> usize {
+        *thirds.entry((a, b)).or_insert_with(|| {
+            vertices.push(mesh.vertices[a] + (mesh.vertices[b] - mesh.vertices[a]).scale(1.0 / 3.0));
+            vertices.len() - 1
+        })
+    };
+
+    let mut faces = vec![];
+    for face in mesh.faces.iter() {
+        let centre = vertices.len();
+        vertices.push(lifted_centroid(&mesh.vertices, face));
+        let n = face.len();
+        for i in 0..n {
+            let (a, b, c) = (face[i], face[(i + 1) % n], face[(i + 2) % n]);
+            faces.push(vec![
+                centre,
+                third(&mut vertices, a, b),
+                third(&mut vertices, b, a),
+                b,
+                third(&mut vertices, b, c),
+            ]);
+        }
+    }
+
+    finish(vertices, faces)
+}
+
+// The mirror image (which only matters for chiral things like snubs and gyros).
+fn reflect(mesh: &Mesh) -> Result<Mesh, ConwayError> {
+    surface(mesh)?;
+
+    let vertices = mesh.vertices.iter()
+        .map(|v| Vec3::new([-v.x(), v.y(), v.z()]))
+        .collect();
+    let faces = mesh.faces.iter()
+        .map(|face| face.iter().rev().copied().collect())
+        .collect();
+
+    finish(vertices, faces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (vertices, lines, faces)
+    fn counts(mesh: &Mesh) -> (usize, usize, usize) {
+        (mesh.vertices.len(), mesh.lines.len(), mesh.faces.len())
+    }
+
+    #[test]
+    fn known_polyhedra() {
+        let count = |notation: &str| counts(&Mesh::from_conway(notation).unwrap());
+        assert_eq!(count("C"), (8, 12, 6));
+        assert_eq!(count("tkD"), (180, 270, 92));
+        // snub dodecahedron
+        assert_eq!(count("sD"), (60, 150, 92));
+        // truncated icosahedron, the football
+        assert_eq!(count("tI"), (60, 90, 32));
+        // rhombicuboctahedron
+        assert_eq!(count("eC"), (24, 48, 26));
+        assert_eq!(count("dA5"), (12, 20, 10));
+    }
+
+    #[test]
+    fn every_operator_makes_a_closed_surface() {
+        for seed in ["T", "C", "I", "P5", "A4", "B3"] {
+            for op in Operator::ALL {
+                let mesh = Mesh::from_conway(seed).unwrap().apply_conway(op).unwrap();
+                assert!(mesh.is_closed(), "{}{}", op.letter(), seed);
+                assert!(mesh.is_manifold(), "{}{}", op.letter(), seed);
+                assert_eq!(mesh.euler_characteristic(), 2, "{}{}", op.letter(), seed);
+            }
+        }
+    }
+
+    #[test]
+    fn predicted_sizes() {
+        for notation in ["C", "tkD", "sD", "rgI", "bmoP6", "jzA7"] {
+            let mesh = Mesh::from_conway(notation).unwrap();
+            let (operators, seed) = notation.split_at(notation.len() - notation.trim_start_matches(char::is_lowercase).len());
+            let seed = conway_seed(seed).unwrap();
+            let predicted = operators.chars()
+                .rev()
+                .flat_map(|letter| Operator::from_letter(letter).unwrap().primitives().chars().rev())
+                .fold(counts(&seed), grow);
+            assert_eq!(predicted, counts(&mesh), "{}", notation);
+        }
+    }
+
+    #[test]
+    fn too_big() {
+        assert!(Mesh::from_conway("kkkkkC").is_ok());
+        assert!(Mesh::from_conway("kkkkkkkkkkkkkkkkkkkkC").is_err());
+        assert!(Mesh::from_conway("ttttttttD").is_err());
+        assert!(Mesh::from_conway("P1000").is_ok());
+        assert!(Mesh::from_conway("P1001").is_err());
+        assert!(Mesh::from_conway("A99999999999").is_err());
+        // long enough for the counts to overflow if we kept going
+        assert!(Mesh::from_conway(&format!("{}C", "g".repeat(40))).is_err());
+        assert!(Mesh::from_conway(&format!("{}P1000", "k".repeat(1000))).is_err());
+    }
+
+    #[test]
+    fn bad_notation() {
+        assert!(Mesh::from_conway("").is_err());
+        assert!(Mesh::from_conway("tk").is_err());
+        assert!(Mesh::from_conway("xC").is_err());
+        assert!(Mesh::from_conway("Q").is_err());
+        assert!(Mesh::from_conway("P2").is_err());
+        assert!(Mesh::from_conway("C3").is_err());
+    }
+}
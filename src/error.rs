@@ -0,0 +1,27 @@
+// Errors that are nothing but a message, for the importers and generators that can fail in too
+// many ways to be worth an enum (compare `MeshError`). `message_error!(StlError, "STL")` declares
+// `StlError`, which shows up as "STL: <message>", and a private `error` shortcut for returning one
+// from the module it's used in.
+
+macro_rules! message_error {
+    ($name:ident, $what:literal) => {
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $name {
+            pub message: String,
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, concat!($what, ": {}"), self.message)
+            }
+        }
+
+        impl std::error::Error for $name {}
+
+        fn error<T>(message: String) -> Result<T, $name> {
+            Err($name { message })
+        }
+    };
+}
+
+pub(crate) use message_error;
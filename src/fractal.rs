@@ -0,0 +1,376 @@
+// Fractal meshes, built to a given recursion depth. The solids are worked out on an integer
+// lattice fine enough to hold every corner exactly, so corners shared between pieces always end up
+// as the same vertex. Everything fits in the unit sphere like the polyhedra do.
+
+use std::collections::HashMap;
+
+use crate::mesh::{Face, Line, Mesh};
+use crate::point::{Element, Vec3};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Fractal {
+    // 20^depth cubes
+    MengerSponge,
+    // 4^depth tetrahedra
+    SierpinskiTetrahedron,
+    // 6^depth octahedra
+    OctahedronFlake,
+    // 3 * 4^depth segments
+    KochCurve,
+}
+
+impl Fractal {
+    pub const ALL: [Fractal; 4] = [
+        Fractal::MengerSponge,
+        Fractal::SierpinskiTetrahedron,
+        Fractal::OctahedronFlake,
+        Fractal::KochCurve,
+    ];
+
+    // by its snake_case name, like in scene files
+    pub fn from_name(name: &str) -> Option<Fractal> {
+        Fractal::ALL.into_iter().find(|fractal| format!("{:?}", fractal).to_lowercase() == name.replace('_', ""))
+    }
+
+    // Roughly where building and drawing every frame stops being reasonable.
+    pub fn max_depth(self) -> usize {
+        match self {
+            Fractal::MengerSponge => 3,
+            Fractal::SierpinskiTetrahedron => 7,
+            Fractal::OctahedronFlake => 5,
+            Fractal::KochCurve => 7,
+        }
+    }
+}
+
+// Vertices at integer coordinates, placed in the world at `p * scale + offset`.
+struct Lattice {
+    scale: Element,
+    offset: Element,
+    index: HashMap<[i64; 3], usize>,
+    vertices: Vec<Vec3>,
+}
+
+impl Lattice {
+    fn new(scale: Element, offset: Element) -> Self {
+        Self {
+            scale,
+            offset,
+            index: HashMap::new(),
+            vertices: vec![],
+        }
+    }
+
+    fn vertex(&mut self, p: [i64; 3]) -> usize {
+        let (scale, offset) = (self.scale, self.offset);
+        let vertices = &mut self.vertices;
+        *self.index.entry(p).or_insert_with(|| {
+            vertices.push(Vec3::new(p.map(|x| x as Element * scale + offset)));
+            vertices.len() - 1
+        })
+    }
+
+    fn into_mesh(self, faces: Vec<Face>) -> Mesh {
+        Mesh::with_faces(Vec3::zeroes(), self.vertices, faces).expect("fractals are built from valid faces")
+    }
+}
+
+impl Mesh {
+    pub fn mk_fractal(fractal: Fractal, depth: usize) -> Mesh {
+        match fractal {
+            Fractal::MengerSponge => menger_sponge(depth),
+            Fractal::SierpinskiTetrahedron => sierpinski_tetrahedron(depth),
+            Fractal::OctahedronFlake => octahedron_flake(depth),
+            Fractal::KochCurve => koch_curve(depth),
+        }
+    }
+}
+
+fn add(a: [i64; 3], b: [i64; 3]) -> [i64; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+// Split a cube into 27 and throw away the middle one and the middles of the faces, over and over.
+// Faces shared by two of the little cubes are inside the sponge and get dropped.
+fn menger_sponge(depth: usize) -> Mesh {
+    let side = 3i64.pow(depth as u32);
+    // (corner, size)
+    let mut cubes = vec![([0, 0, 0], side)];
+    for _ in 0..depth {
+        cubes = cubes.into_iter()
+            .flat_map(|(corner, size)| {
+                let size = size / 3;
+                (0..27)
+                    .map(|n| [n % 3, n / 3 % 3, n / 9])
+                    .filter(|at| at.iter().filter(|&&x| x == 1).count() <= 1)
+                    .map(move |at| (add(corner, at.map(|x| x * size)), size))
+                    .collect::<Vec<([i64; 3], i64)>>()
+            })
+            .collect();
+    }
+
+    // the cube is [-1, 1]^3 shrunk into the unit sphere
+    let radius = Element::sqrt(3.0);
+    let mut lattice = Lattice::new(2.0 / (side as Element * radius), -1.0 / radius);
+    // corners of a unit cube, each face wound to face out
+    let sides = [
+        [[0, 0, 0], [0, 0, 1], [0, 1, 1], [0, 1, 0]],
+        [[1, 0, 0], [1, 1, 0], [1, 1, 1], [1, 0, 1]],
+        [[0, 0, 0], [1, 0, 0], [1, 0, 1], [0, 0, 1]],
+        [[0, 1, 0], [0, 1, 1], [1, 1, 1], [1, 1, 0]],
+        [[0, 0, 0], [0, 1, 0], [1, 1, 0], [1, 0, 0]],
+        [[0, 0, 1], [1, 0, 1], [1, 1, 1], [0, 1, 1]],
+    ];
+
+    let mut faces = vec![];
+    // sorted corners -> how many cubes have that face
+    let mut seen = HashMap::<Vec<usize>, usize>::new();
+    for (corner, size) in cubes {
+        for side in sides.iter() {
+            let face = side.iter()
+                .map(|at| lattice.vertex(add(corner, at.map(|x| x * size))))
+                .collect::<Face>();
+            let mut key = face.clone();
+            key.sort();
+            *seen.entry(key).or_default() += 1;
+            faces.push(face);
+        }
+    }
+    faces.retain(|face| {
+        let mut key = face.clone();
+        key.sort();
+        seen[&key] == 1
+    });
+
+    // interior corners aren't on any face any more
+    compact(lattice, faces)
+}
+
+// Swap a tetrahedron for the four half-size ones in its corners, over and over.
+fn sierpinski_tetrahedron(depth: usize) -> Mesh {
+    let size = 1i64 << depth;
+    let mut tetrahedra = vec![[
+        [size, size, size],
+        [size, -size, -size],
+        [-size, size, -size],
+        [-size, -size, size],
+    ]];
+    for _ in 0..depth {
+        tetrahedra = tetrahedra.into_iter()
+            .flat_map(|corners| {
+                (0..4).map(move |i| corners.map(|c| {
+                    let sum = add(corners[i], c);
+                    sum.map(|x| x / 2)
+                }))
+            })
+            .collect();
+    }
+
+    let mut lattice = Lattice::new(1.0 / (size as Element * Element::sqrt(3.0)), 0.0);
+    let mut faces = vec![];
+    for corners in tetrahedra {
+        let [a, b, c, d] = corners.map(|corner| lattice.vertex(corner));
+        faces.extend([vec![a, b, c], vec![a, c, d], vec![a, d, b], vec![b, d, c]]);
+    }
+
+    lattice.into_mesh(faces)
+}
+
+// Swap an octahedron for the six half-size ones in its corners, over and over.
+fn octahedron_flake(depth: usize) -> Mesh {
+    let axes = [[1, 0, 0], [-1, 0, 0], [0, 1, 0], [0, -1, 0], [0, 0, 1], [0, 0, -1]];
+
+    // (centre, radius)
+    let mut octahedra = vec![([0, 0, 0], 1i64 << depth)];
+    for _ in 0..depth {
+        octahedra = octahedra.into_iter()
+            .flat_map(|(centre, radius)| {
+                let radius = radius / 2;
+                axes.map(|axis| (add(centre, axis.map(|x| x * radius)), radius))
+            })
+            .collect();
+    }
+
+    let mut lattice = Lattice::new(1.0 / (1i64 << depth) as Element, 0.0);
+    let mut faces = vec![];
+    for (centre, radius) in octahedra {
+        for sx in [-1, 1] {
+            for sy in [-1, 1] {
+                for sz in [-1, 1] {
+                    let x = lattice.vertex(add(centre, [sx * radius, 0, 0]));
+                    let y = lattice.vertex(add(centre, [0, sy * radius, 0]));
+                    let z = lattice.vertex(add(centre, [0, 0, sz * radius]));
+                    // neighbours share edges, so `orient_faces` can't put these right for us;
+                    // every minus sign mirrors the face
+                    faces.push(if sx * sy * sz > 0 { vec![x, y, z] } else { vec![x, z, y] });
+                }
+            }
+        }
+    }
+
+    lattice.into_mesh(faces)
+}
+
+// Drop the vertices no face uses.
+fn compact(lattice: Lattice, faces: Vec<Face>) -> Mesh {
+    let mut remap = vec![usize::MAX; lattice.vertices.len()];
+    let mut vertices = vec![];
+    let faces = faces.into_iter()
+        .map(|face| face.into_iter()
+            .map(|v| {
+                if remap[v] == usize::MAX {
+                    remap[v] = vertices.len();
+                    vertices.push(lattice.vertices[v]);
+                }
+                remap[v]
+            })
+            .collect())
+        .collect();
+
+    Mesh::with_faces(Vec3::zeroes(), vertices, faces).expect("fractals are built from valid faces")
+}
+
+// A Koch snowflake that doesn't stay flat: each segment's middle third is pushed out into a
+// triangular bump, and every generation bumps out at right angles to the one before.
+fn koch_curve(depth: usize) -> Mesh {
+    // (point, which way the segment starting there bumps)
+    let mut path = (0..3)
+        .map(|i| {
+            let angle = 2.0 * std::f64::consts::PI * i as Element / 3.0;
+            let start = Vec3::new([angle.sin(), angle.cos(), 0.0]);
+            let middle = Vec3::new([(angle + std::f64::consts::PI / 3.0).sin(), (angle + std::f64::consts::PI / 3.0).cos(), 0.0]);
+            (start, middle)
+        })
+        .collect::<Vec<(Vec3, Vec3)>>();
+
+    for _ in 0..depth {
+        let mut next = Vec::with_capacity(path.len() * 4);
+        for i in 0..path.len() {
+            let (a, out) = path[i];
+            let b = path[(i + 1) % path.len()].0;
+            let along = b - a;
+            let bump = (a + b).scale(0.5) + out.scale(along.magnitude() * Element::sqrt(3.0) / 6.0);
+            let points = [a, a + along.scale(1.0 / 3.0), bump, a + along.scale(2.0 / 3.0)];
+            for k in 0..4 {
+                let to = if k == 3 { b } else { points[k + 1] };
+                // turn the bump direction a quarter turn around the new segment
+                let direction = (to - points[k]).normal();
+                next.push((points[k], direction.cross(&out).normal()));
+            }
+        }
+        path = next;
+    }
+
+    let radius = path.iter().map(|(p, _)| p.magnitude()).fold(0.0, Element::max);
+    let vertices = path.iter().map(|(p, _)| p.scale(1.0 / radius)).collect::<Vec<Vec3>>();
+    let lines = (0..vertices.len())
+        .map(|i| (i, (i + 1) % vertices.len()))
+        .collect::<Vec<Line>>();
+    Mesh::new(Vec3::zeroes(), vertices, lines).expect("the curve is a simple loop")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::topology::face_area_vector;
+
+    fn counts(mesh: &Mesh) -> (usize, usize, usize) {
+        (mesh.vertices.len(), mesh.lines.len(), mesh.faces.len())
+    }
+
+    // by the divergence theorem, with the faces wound outwards
+    fn volume(mesh: &Mesh) -> Element {
+        mesh.faces.iter()
+            .map(|face| mesh.vertices[face[0]].dot(&face_area_vector(&mesh.vertices, face)) / 6.0)
+            .sum()
+    }
+
+    fn fits(mesh: &Mesh) -> bool {
+        mesh.vertices.iter().all(|v| v.magnitude() <= 1.0 + 1e-9)
+    }
+
+    #[test]
+    fn menger_sponge() {
+        let cube = Mesh::mk_fractal(Fractal::MengerSponge, 0);
+        assert_eq!(counts(&cube), (8, 12, 6));
+        let cube_volume = volume(&cube);
+
+        // one level down is a cube with three tunnels through it, a surface of genus 5
+        let sponge = Mesh::mk_fractal(Fractal::MengerSponge, 1);
+        assert_eq!(counts(&sponge), (64, 144, 72));
+        assert_eq!(sponge.euler_characteristic(), -8);
+
+        for depth in 0..=2 {
+            let sponge = Mesh::mk_fractal(Fractal::MengerSponge, depth);
+            assert!(sponge.is_closed());
+            assert!(sponge.is_manifold());
+            assert!(fits(&sponge));
+            // 20 of every 27 little cubes stay each time round
+            let expected = cube_volume * (20.0 / 27.0 as Element).powi(depth as i32);
+            assert!((volume(&sponge) - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn sierpinski_tetrahedron() {
+        let tetrahedron = volume(&Mesh::mk_fractal(Fractal::SierpinskiTetrahedron, 0));
+        for depth in 0..=3 {
+            let mesh = Mesh::mk_fractal(Fractal::SierpinskiTetrahedron, depth);
+            let pieces = 4usize.pow(depth as u32);
+            // the tetrahedra only touch at their corners
+            assert_eq!(counts(&mesh), (2 * pieces + 2, 6 * pieces, 4 * pieces));
+            assert!(mesh.is_closed());
+            assert_eq!(mesh.is_manifold(), depth == 0);
+            assert!(fits(&mesh));
+            assert!((volume(&mesh) - tetrahedron / 2.0f64.powi(depth as i32)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn octahedron_flake() {
+        let octahedron = volume(&Mesh::mk_fractal(Fractal::OctahedronFlake, 0));
+        for depth in 0..=3 {
+            let mesh = Mesh::mk_fractal(Fractal::OctahedronFlake, depth);
+            let pieces = 6usize.pow(depth as u32);
+            assert_eq!(mesh.faces.len(), 8 * pieces);
+            // neighbours share an edge, which four faces then meet along
+            assert_eq!(mesh.is_closed(), depth == 0);
+            assert!(fits(&mesh));
+            assert!((volume(&mesh) - octahedron * (6.0 / 8.0 as Element).powi(depth as i32)).abs() < 1e-9);
+        }
+        // six corners and twelve edges each, less the middle they all share and the twelve edges
+        // (with their far ends) where neighbours touch
+        assert_eq!(counts(&Mesh::mk_fractal(Fractal::OctahedronFlake, 1)), (36 - 5 - 12, 72 - 12, 48));
+    }
+
+    #[test]
+    fn koch_curve() {
+        for depth in 0..=4 {
+            let mesh = Mesh::mk_fractal(Fractal::KochCurve, depth);
+            let segments = 3 * 4usize.pow(depth as u32);
+            assert_eq!(counts(&mesh), (segments, segments, 0));
+            // one closed loop
+            let adjacency = mesh.adjacency();
+            assert!((0..segments).all(|v| adjacency.degree(v) == 2));
+            assert_eq!(adjacency.components().len(), 1);
+            assert!(fits(&mesh));
+        }
+    }
+
+    #[test]
+    fn names() {
+        for fractal in Fractal::ALL {
+            let name = match fractal {
+                Fractal::MengerSponge => "menger_sponge",
+                Fractal::SierpinskiTetrahedron => "sierpinski_tetrahedron",
+                Fractal::OctahedronFlake => "octahedron_flake",
+                Fractal::KochCurve => "koch_curve",
+            };
+            assert_eq!(Fractal::from_name(name), Some(fractal));
+        }
+        assert_eq!(Fractal::from_name("cantor_dust"), None);
+    }
+}
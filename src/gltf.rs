@@ -0,0 +1,685 @@
+// glTF 2.0 import (both the JSON `.gltf` flavour and binary `.glb`). Every node that references a
+// mesh becomes one of our meshes: LINES, LINE_LOOP and LINE_STRIP primitives become lines as-is
+// while triangle primitives go through `soup` so only their feature edges survive.
+//
+// glTF nodes can be scaled, sheared and nested arbitrarily but a `Mesh` only has an origin and a
+// rotation. So we compose each node's world matrix, split it into rotation * translation and
+// bake whatever is left over (scale, shear, mirroring) into the vertices. glTF is also
+// right-handed and we're left-handed, so z gets flipped on the way in.
+//
+// https://registry.khronos.org/glTF/specs/2.0/glTF-2.0.html
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::camera::Camera;
+use crate::error::message_error;
+use crate::mesh::{Line, Mesh};
+use crate::point::{Element, Mat4, Quaternion, Vec3};
+use crate::render::{Scene, Style};
+use crate::soup::{TriangleSoup, DEFAULT_CREASE_ANGLE};
+
+message_error!(GltfError, "glTF");
+
+// Just the parts of the schema we use. serde ignores everything else.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Document {
+    #[serde(default)]
+    buffers: Vec<Buffer>,
+    #[serde(default)]
+    buffer_views: Vec<BufferView>,
+    #[serde(default)]
+    accessors: Vec<Accessor>,
+    #[serde(default)]
+    meshes: Vec<GltfMesh>,
+    #[serde(default)]
+    nodes: Vec<Node>,
+    #[serde(default)]
+    scenes: Vec<GltfScene>,
+    scene: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Buffer {
+    uri: Option<String>,
+    byte_length: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BufferView {
+    buffer: usize,
+    #[serde(default)]
+    byte_offset: usize,
+    byte_length: usize,
+    byte_stride: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Accessor {
+    buffer_view: Option<usize>,
+    #[serde(default)]
+    byte_offset: usize,
+    component_type: u32,
+    #[serde(default)]
+    normalized: bool,
+    count: usize,
+    #[serde(rename = "type")]
+    kind: String,
+    sparse: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct GltfMesh {
+    primitives: Vec<Primitive>,
+}
+
+#[derive(Deserialize)]
+struct Primitive {
+    attributes: HashMap<String, usize>,
+    indices: Option<usize>,
+    // TRIANGLES is the default
+    #[serde(default = "default_mode")]
+    mode: u32,
+}
+
+fn default_mode() -> u32 {
+    4
+}
+
+#[derive(Deserialize)]
+struct Node {
+    #[serde(default)]
+    children: Vec<usize>,
+    mesh: Option<usize>,
+    matrix: Option<[Element; 16]>,
+    translation: Option<[Element; 3]>,
+    // a quaternion, (x, y, z, w)
+    rotation: Option<[Element; 4]>,
+    scale: Option<[Element; 3]>,
+}
+
+#[derive(Deserialize)]
+struct GltfScene {
+    #[serde(default)]
+    nodes: Vec<usize>,
+}
+
+const MODE_POINTS: u32 = 0;
+const MODE_LINES: u32 = 1;
+const MODE_LINE_LOOP: u32 = 2;
+const MODE_LINE_STRIP: u32 = 3;
+const MODE_TRIANGLES: u32 = 4;
+const MODE_TRIANGLE_STRIP: u32 = 5;
+const MODE_TRIANGLE_FAN: u32 = 6;
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, GltfError> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return error(format!("{:?} is not valid base64", c as char)),
+        };
+        bits = (bits << 6) | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+
+    Ok(out)
+}
+
+// Split a `.glb` into its JSON and (optional) binary chunks.
+fn split_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), GltfError> {
+    let word = |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as usize;
+
+    if bytes.len() < 20 {
+        return error("GLB file is too short".to_string());
+    }
+    if word(4) != 2 {
+        return error(format!("GLB version {} isn't supported, only 2", word(4)));
+    }
+
+    const JSON: usize = 0x4e4f534a;
+    const BIN: usize = 0x004e4942;
+    let mut json = None;
+    let mut bin = None;
+    let mut at = 12;
+    while at + 8 <= bytes.len() {
+        let length = word(at);
+        let kind = word(at + 4);
+        let start = at + 8;
+        let end = match start.checked_add(length) {
+            Some(end) if end <= bytes.len() => end,
+            _ => return error("GLB chunk runs past the end of the file".to_string()),
+        };
+        match kind {
+            JSON if json.is_none() => json = Some(&bytes[start..end]),
+            BIN if bin.is_none() => bin = Some(&bytes[start..end]),
+            // unknown chunks must be ignored
+            _ => {},
+        }
+        at = end;
+    }
+
+    match json {
+        Some(json) => Ok((json, bin)),
+        None => error("GLB file has no JSON chunk".to_string()),
+    }
+}
+
+struct Loader {
+    document: Document,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl Loader {
+    fn new(bytes: &[u8], mut resolve: impl FnMut(&str) -> Option<Vec<u8>>) -> Result<Self, GltfError> {
+        let (json, bin) = if bytes.starts_with(b"glTF") {
+            split_glb(bytes)?
+        } else {
+            (bytes, None)
+        };
+        let document = match serde_json::from_slice::<Document>(json) {
+            Ok(document) => document,
+            Err(e) => return error(format!("malformed JSON: {}", e)),
+        };
+
+        let mut buffers = Vec::with_capacity(document.buffers.len());
+        for (i, buffer) in document.buffers.iter().enumerate() {
+            let data = match buffer.uri.as_deref() {
+                // only the first buffer may live in the GLB binary chunk
+                None => match bin {
+                    Some(bin) if i == 0 => bin.to_vec(),
+                    _ => return error(format!("buffer {} has no uri", i)),
+                },
+                Some(uri) if uri.starts_with("data:") => match uri.split_once(";base64,") {
+                    Some((_, data)) => decode_base64(data)?,
+                    None => return error(format!("buffer {} has a data uri that isn't base64", i)),
+                },
+                Some(uri) => match resolve(uri) {
+                    Some(data) => data,
+                    None => return error(format!("unable to load buffer {} from {:?}", i, uri)),
+                },
+            };
+            if data.len() < buffer.byte_length {
+                return error(format!("buffer {} should be {} bytes but is only {}", i, buffer.byte_length, data.len()));
+            }
+            buffers.push(data);
+        }
+
+        Ok(Self {
+            document,
+            buffers,
+        })
+    }
+
+    // Everything an accessor points at, flattened into one `Element` per component.
+    fn read(&self, index: usize, expected_kind: &str) -> Result<Vec<Element>, GltfError> {
+        let accessor = match self.document.accessors.get(index) {
+            Some(accessor) => accessor,
+            None => return error(format!("accessor {} doesn't exist", index)),
+        };
+        if accessor.kind != expected_kind {
+            return error(format!("accessor {} is {} but should be {}", index, accessor.kind, expected_kind));
+        }
+        if accessor.sparse.is_some() {
+            return error(format!("accessor {} is sparse, which isn't supported", index));
+        }
+
+        let components = match accessor.kind.as_str() {
+            "SCALAR" => 1,
+            "VEC3" => 3,
+            kind => return error(format!("accessor {} has unexpected type {}", index, kind)),
+        };
+        // (size in bytes, largest value for normalized integers)
+        let (size, max) = match accessor.component_type {
+            5120 => (1, i8::MAX as Element),
+            5121 => (1, u8::MAX as Element),
+            5122 => (2, i16::MAX as Element),
+            5123 => (2, u16::MAX as Element),
+            5125 => (4, u32::MAX as Element),
+            5126 => (4, 1.0),
+            ty => return error(format!("accessor {} has unknown component type {}", index, ty)),
+        };
+
+        // No buffer view means all zeroes. Nothing in the file backs the count so don't believe
+        // one that's longer than all the buffers put together.
+        let view = match accessor.buffer_view {
+            Some(view) => match self.document.buffer_views.get(view) {
+                Some(view) => view,
+                None => return error(format!("accessor {} uses missing buffer view {}", index, view)),
+            },
+            None => {
+                let most = self.buffers.iter().map(Vec::len).sum::<usize>();
+                return match accessor.count.checked_mul(components) {
+                    Some(length) if accessor.count <= most => Ok(vec![0.0; length]),
+                    _ => error(format!("accessor {} has {} elements but no buffer view", index, accessor.count)),
+                };
+            },
+        };
+        let buffer = match self.buffers.get(view.buffer) {
+            Some(buffer) => buffer,
+            None => return error(format!("buffer view uses missing buffer {}", view.buffer)),
+        };
+        let view_end = view.byte_offset.checked_add(view.byte_length);
+        let bytes = match view_end {
+            Some(end) if end <= buffer.len() => &buffer[view.byte_offset..end],
+            _ => return error(format!("buffer view runs past the end of buffer {}", view.buffer)),
+        };
+
+        let element_size = size * components;
+        let stride = view.byte_stride.unwrap_or(element_size);
+        if accessor.count > 0 {
+            // where the last element ends, `None` if that doesn't even fit in a usize
+            let last = stride.checked_mul(accessor.count - 1)
+                .and_then(|x| x.checked_add(accessor.byte_offset))
+                .and_then(|x| x.checked_add(element_size));
+            match last {
+                Some(last) if stride >= element_size && last <= bytes.len() => {},
+                _ => return error(format!("accessor {} runs past the end of its buffer view", index)),
+            }
+        }
+
+        let mut out = Vec::with_capacity(accessor.count * components);
+        for i in 0..accessor.count {
+            for j in 0..components {
+                let at = accessor.byte_offset + i * stride + j * size;
+                let b = &bytes[at..at + size];
+                let value = match accessor.component_type {
+                    5120 => b[0] as i8 as Element,
+                    5121 => b[0] as Element,
+                    5122 => i16::from_le_bytes([b[0], b[1]]) as Element,
+                    5123 => u16::from_le_bytes([b[0], b[1]]) as Element,
+                    5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as Element,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as Element,
+                };
+                out.push(if accessor.normalized { (value / max).max(-1.0) } else { value });
+            }
+        }
+
+        Ok(out)
+    }
+
+    // Vertices in the mesh's own (left-handed) space and its lines.
+    fn mesh(&self, index: usize) -> Result<(Vec<Vec3>, Vec<Line>), GltfError> {
+        let mesh = match self.document.meshes.get(index) {
+            Some(mesh) => mesh,
+            None => return error(format!("mesh {} doesn't exist", index)),
+        };
+
+        let mut vertices = Vec::<Vec3>::new();
+        let mut lines = Vec::<Line>::new();
+        for (p, primitive) in mesh.primitives.iter().enumerate() {
+            let position = match primitive.attributes.get("POSITION") {
+                Some(position) => *position,
+                None => return error(format!("mesh {} primitive {} has no POSITION", index, p)),
+            };
+            let positions = self.read(position, "VEC3")?
+                .chunks(3)
+                .map(|c| Vec3::new([c[0], c[1], -c[2]]))
+                .collect::<Vec<Vec3>>();
+            if positions.iter().any(|v| !v.coord.iter().all(|x| x.is_finite())) {
+                return error(format!("mesh {} primitive {} has a non-finite position", index, p));
+            }
+
+            let indices = match primitive.indices {
+                Some(indices) => self.read(indices, "SCALAR")?
+                    .into_iter()
+                    .map(|i| i as usize)
+                    .collect::<Vec<usize>>(),
+                None => (0..positions.len()).collect(),
+            };
+            if let Some(i) = indices.iter().find(|&&i| i >= positions.len()) {
+                return error(format!("mesh {} primitive {} uses vertex {} but has {}", index, p, i, positions.len()));
+            }
+
+            let (part_vertices, part_lines) = match primitive.mode {
+                MODE_POINTS => continue,
+                MODE_LINES | MODE_LINE_LOOP | MODE_LINE_STRIP => {
+                    let mut part = match primitive.mode {
+                        MODE_LINES => indices.chunks_exact(2).map(|l| (l[0], l[1])).collect::<Vec<Line>>(),
+                        _ => indices.windows(2).map(|l| (l[0], l[1])).collect::<Vec<Line>>(),
+                    };
+                    if primitive.mode == MODE_LINE_LOOP && indices.len() > 2 {
+                        part.push((indices[indices.len() - 1], indices[0]));
+                    }
+                    part.retain(|(a, b)| a != b);
+                    (positions, part)
+                },
+                MODE_TRIANGLES | MODE_TRIANGLE_STRIP | MODE_TRIANGLE_FAN => {
+                    let triangles = match primitive.mode {
+                        MODE_TRIANGLES => indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect(),
+                        MODE_TRIANGLE_STRIP => indices.windows(3)
+                            .enumerate()
+                            // every other triangle in a strip is wound backwards
+                            .map(|(i, t)| if i % 2 == 0 { [t[0], t[1], t[2]] } else { [t[1], t[0], t[2]] })
+                            .collect(),
+                        _ => indices[1..].windows(2).map(|t| [indices[0], t[0], t[1]]).collect(),
+                    };
+                    let soup = TriangleSoup {
+                        vertices: positions,
+                        triangles,
+                    };
+                    if soup.triangles.is_empty() {
+                        continue;
+                    }
+                    let wireframe = soup.feature_edges(DEFAULT_CREASE_ANGLE)
+                        .or_else(|e| error(format!("mesh {} primitive {}: {}", index, p, e)))?;
+                    (wireframe.vertices, wireframe.lines)
+                },
+                mode => return error(format!("mesh {} primitive {} has unknown mode {}", index, p, mode)),
+            };
+
+            let base = vertices.len();
+            vertices.extend(part_vertices);
+            lines.extend(part_lines.into_iter().map(|(a, b)| (a + base, b + base)));
+        }
+
+        Ok((vertices, lines))
+    }
+}
+
+// A node's transform relative to its parent, for row vectors (v * M) like the rest of our math.
+fn local_matrix(node: &Node) -> Mat4 {
+    if let Some(m) = node.matrix {
+        // glTF matrices are column-major for column vectors, which read row by row is exactly the
+        // transpose we want
+        return Mat4::new([
+            [m[0], m[1], m[2], m[3]],
+            [m[4], m[5], m[6], m[7]],
+            [m[8], m[9], m[10], m[11]],
+            [m[12], m[13], m[14], m[15]],
+        ]);
+    }
+
+    let [sx, sy, sz] = node.scale.unwrap_or([1.0, 1.0, 1.0]);
+    let scale = Mat4::new([
+        [sx, 0.0, 0.0, 0.0],
+        [0.0, sy, 0.0, 0.0],
+        [0.0, 0.0, sz, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    let [x, y, z, w] = node.rotation.unwrap_or([0.0, 0.0, 0.0, 1.0]);
+    let rotation = Quaternion::new(x, y, z, w).to_mat4();
+
+    let [tx, ty, tz] = node.translation.unwrap_or([0.0, 0.0, 0.0]);
+
+    scale * rotation * Mat4::translation(tx, ty, tz)
+}
+
+// Split the upper 3x3 of a world matrix into `rest * rotation` where `rotation` is a proper
+// rotation (Gram-Schmidt on the rows) and `rest` is whatever scale/shear/mirroring remains.
+fn decompose(world: &Mat4) -> ([[Element; 3]; 3], Mat4) {
+    let row = |i: usize| Vec3::new([world.at(i, 0), world.at(i, 1), world.at(i, 2)]);
+
+    let mut x = row(0).normal();
+    if x.magnitude() == 0.0 {
+        x = Vec3::new([1.0, 0.0, 0.0]);
+    }
+    let mut y = row(1) - x.scale(x.dot(&row(1)));
+    y = y.normal();
+    if y.magnitude() == 0.0 {
+        // any direction perpendicular to x will do
+        let helper = if x.x().abs() < 0.9 { Vec3::new([1.0, 0.0, 0.0]) } else { Vec3::new([0.0, 1.0, 0.0]) };
+        y = helper.cross(&x).normal();
+    }
+    // left-handed basis: x cross y points along z
+    let z = x.cross(&y);
+
+    let rotation = Mat4::new([
+        [x.x(), x.y(), x.z(), 0.0],
+        [y.x(), y.y(), y.z(), 0.0],
+        [z.x(), z.y(), z.z(), 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    // rest = A * rotation^T
+    let basis = [x, y, z];
+    let mut rest = [[0.0; 3]; 3];
+    for (i, rest_row) in rest.iter_mut().enumerate() {
+        for (j, axis) in basis.iter().enumerate() {
+            rest_row[j] = row(i).dot(axis);
+        }
+    }
+
+    (rest, rotation)
+}
+
+impl Scene {
+    // `resolve` is handed the uri of every buffer that isn't embedded (data uris and the GLB
+    // binary chunk are handled for you) and should return its contents.
+    pub fn from_gltf(bytes: &[u8], resolve: impl FnMut(&str) -> Option<Vec<u8>>) -> Result<Scene, GltfError> {
+        let meshes = Mesh::from_gltf(bytes, resolve)?;
+        if meshes.is_empty() {
+            return error("no node in the scene has a mesh".to_string());
+        }
+
+        Ok(Scene::from_parts(Camera::new(Vec3::new([0.0, 0.0, 10.0])), meshes, Style::default()))
+    }
+}
+
+impl Mesh {
+    // One mesh per node (in the default scene) that has one, positioned where the node hierarchy
+    // puts it.
+    pub fn from_gltf(bytes: &[u8], resolve: impl FnMut(&str) -> Option<Vec<u8>>) -> Result<Vec<Mesh>, GltfError> {
+        let loader = Loader::new(bytes, resolve)?;
+        let document = &loader.document;
+
+        let roots = match document.scene.or(if document.scenes.is_empty() { None } else { Some(0) }) {
+            Some(scene) => match document.scenes.get(scene) {
+                Some(scene) => scene.nodes.clone(),
+                None => return error(format!("scene {} doesn't exist", scene)),
+            },
+            // no scenes at all: every node that isn't somebody's child is a root
+            None => (0..document.nodes.len())
+                .filter(|i| !document.nodes.iter().any(|n| n.children.contains(i)))
+                .collect(),
+        };
+
+        // glTF is right-handed, we're left-handed
+        let flip = Mat4::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, -1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        let mut meshes = Vec::<Mesh>::new();
+        // (node, parent's world matrix)
+        let mut pending = roots.into_iter()
+            .map(|node| (node, Mat4::identity()))
+            .collect::<Vec<(usize, Mat4)>>();
+        // A well-formed file is a forest, so every node turns up once. Seeing one again means a
+        // cycle or children shared between parents, which could blow up exponentially.
+        let mut visited = vec![false; document.nodes.len()];
+        while let Some((index, parent)) = pending.pop() {
+            let node = match document.nodes.get(index) {
+                Some(node) => node,
+                None => return error(format!("node {} doesn't exist", index)),
+            };
+            if std::mem::replace(&mut visited[index], true) {
+                return error(format!("node {} has more than one parent (or is its own ancestor)", index));
+            }
+
+            let world = local_matrix(node) * parent;
+            if (0..4).any(|i| (0..4).any(|j| !world.at(i, j).is_finite())) {
+                return error(format!("node {} has a non-finite transform", index));
+            }
+            for child in node.children.iter().rev() {
+                pending.push((*child, world));
+            }
+
+            let mesh = match node.mesh {
+                Some(mesh) => mesh,
+                None => continue,
+            };
+            let (vertices, lines) = loader.mesh(mesh)?;
+            // only POINTS (or no triangles left) is nothing we can draw, but no reason to give up
+            // on the rest of the file either
+            if vertices.is_empty() {
+                continue;
+            }
+
+            let (rest, rotation) = decompose(&(flip * world * flip));
+            let vertices = vertices.into_iter()
+                .map(|v| {
+                    Vec3::new([
+                        v.x() * rest[0][0] + v.y() * rest[1][0] + v.z() * rest[2][0],
+                        v.x() * rest[0][1] + v.y() * rest[1][1] + v.z() * rest[2][1],
+                        v.x() * rest[0][2] + v.y() * rest[1][2] + v.z() * rest[2][2],
+                    ])
+                })
+                .collect::<Vec<Vec3>>();
+            let origin = Vec3::new([world.at(3, 0), world.at(3, 1), -world.at(3, 2)]);
+
+            let mut mesh = Mesh::new(origin, vertices, lines)
+                .or_else(|e| error(format!("node {}: {}", index, e)))?;
+            mesh.rotation = Quaternion::from_mat4(&rotation);
+            // files come in sitting still
+            mesh.spin = Quaternion::identity();
+            meshes.push(mesh);
+        }
+
+        Ok(meshes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = String::new();
+        for chunk in bytes.chunks(3) {
+            let bits = chunk.iter().enumerate().fold(0u32, |acc, (i, b)| acc | (*b as u32) << (16 - 8 * i));
+            for i in 0..=chunk.len() {
+                out.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
+            }
+        }
+        out
+    }
+
+    // A triangle's corners drawn as a LINE_LOOP (mesh 0) and as POINTS (mesh 1), with `nodes` and
+    // `scenes` spliced in.
+    fn document(nodes: &str, scenes: &str) -> Vec<u8> {
+        let corners: [f32; 9] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let bytes = corners.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
+        format!(r#"{{
+            "buffers": [{{ "uri": "data:application/octet-stream;base64,{}", "byteLength": 36 }}],
+            "bufferViews": [{{ "buffer": 0, "byteLength": 36 }}],
+            "accessors": [{{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }}],
+            "meshes": [
+                {{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "mode": 2 }}] }},
+                {{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "mode": 0 }}] }}
+            ],
+            "nodes": {},
+            "scenes": {}
+        }}"#, base64(&bytes), nodes, scenes).into_bytes()
+    }
+
+    fn load(nodes: &str, scenes: &str) -> Result<Vec<Mesh>, GltfError> {
+        Mesh::from_gltf(&document(nodes, scenes), |_| None)
+    }
+
+    fn world_vertices(mesh: &Mesh) -> Vec<Vec3> {
+        mesh.vertices.iter()
+            .map(|v| Vec3::transform_coordinates(v, &mesh.world_matrix()))
+            .collect()
+    }
+
+    #[test]
+    fn loop_becomes_lines() {
+        let meshes = load(r#"[{ "mesh": 0 }]"#, r#"[{ "nodes": [0] }]"#).unwrap();
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].vertices.len(), 3);
+        assert_eq!(meshes[0].lines.len(), 3);
+        assert_eq!(meshes[0].spin, Quaternion::identity());
+    }
+
+    #[test]
+    fn trs_matches_matrix() {
+        // scale by 2, a quarter turn about z and then move by (1, 2, 3), column-major
+        let s = 0.5_f64.sqrt();
+        let nodes = format!(r#"[
+            {{ "mesh": 0, "translation": [1, 2, 3], "rotation": [0, 0, {s}, {s}], "scale": [2, 2, 2] }},
+            {{ "mesh": 0, "matrix": [0, 2, 0, 0, -2, 0, 0, 0, 0, 0, 2, 0, 1, 2, 3, 1] }}
+        ]"#);
+        let meshes = load(&nodes, r#"[{ "nodes": [0, 1] }]"#).unwrap();
+        assert_eq!(meshes.len(), 2);
+
+        let (trs, matrix) = (world_vertices(&meshes[0]), world_vertices(&meshes[1]));
+        for (a, b) in trs.iter().zip(matrix.iter()) {
+            assert!(a.approx_eq(b, 1e-6), "{:?} vs {:?}", a, b);
+        }
+        // (1, 0, 0) scaled, turned onto y, moved and z flipped
+        assert!(trs[1].approx_eq(&Vec3::new([1.0, 4.0, -3.0]), 1e-6));
+    }
+
+    #[test]
+    fn children_follow_parents() {
+        let meshes = load(
+            r#"[{ "translation": [5, 0, 0], "children": [1] }, { "mesh": 0, "translation": [0, 1, 0] }]"#,
+            r#"[{ "nodes": [0] }]"#,
+        ).unwrap();
+        assert!(meshes[0].origin.approx_eq(&Vec3::new([5.0, 1.0, 0.0]), 1e-9));
+    }
+
+    #[test]
+    fn cycles_and_shared_children() {
+        let cycle = load(r#"[{ "children": [1] }, { "children": [0] }]"#, r#"[{ "nodes": [0] }]"#);
+        assert!(cycle.is_err());
+
+        let shared = load(
+            r#"[{ "children": [2] }, { "children": [2] }, { "mesh": 0 }]"#,
+            r#"[{ "nodes": [0, 1] }]"#,
+        );
+        assert!(shared.is_err());
+    }
+
+    #[test]
+    fn accessor_sizes_are_checked() {
+        // mesh 0 again with its positions read through `accessor`
+        let load = |accessor: &str| {
+            let text = String::from_utf8(document(r#"[{ "mesh": 0 }]"#, r#"[{ "nodes": [0] }]"#)).unwrap();
+            let positions = r#"{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }"#;
+            assert!(text.contains(positions));
+            Mesh::from_gltf(text.replace(positions, accessor).as_bytes(), |_| None)
+        };
+
+        // counts and offsets too big to multiply or add up
+        assert!(load(r#"{ "componentType": 5126, "count": 4611686018427387904, "type": "VEC3" }"#).is_err());
+        assert!(load(r#"{ "componentType": 5126, "count": 10000000000, "type": "VEC3" }"#).is_err());
+        assert!(load(r#"{ "bufferView": 0, "componentType": 5126, "count": 4611686018427387904, "type": "VEC3" }"#).is_err());
+        assert!(load(r#"{ "bufferView": 0, "byteOffset": 18446744073709551615, "componentType": 5126, "count": 3, "type": "VEC3" }"#).is_err());
+        // one element too many
+        assert!(load(r#"{ "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3" }"#).is_err());
+
+        // a handful of zeroes is fine
+        let zeroes = load(r#"{ "componentType": 5126, "count": 3, "type": "VEC3" }"#).unwrap();
+        assert!(zeroes[0].vertices.iter().all(|v| *v == Vec3::zeroes()));
+    }
+
+    #[test]
+    fn points_are_skipped() {
+        let meshes = load(r#"[{ "mesh": 1 }, { "mesh": 0 }]"#, r#"[{ "nodes": [0, 1] }]"#).unwrap();
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].lines.len(), 3);
+    }
+}
@@ -0,0 +1,217 @@
+// A scene graph: a tree of nodes, each placed relative to its parent by a translation, rotation
+// and scale, so moving a node moves everything under it. A node can hang any of the scene's meshes
+// off it (by index into `Scene::meshes`, and the same mesh can hang off more than one node) which
+// are then drawn with their own origin and rotation inside the node's space.
+//
+// Meshes that aren't in the graph at all are drawn on their own, like before there was a graph.
+
+use std::f64::consts::PI;
+
+use crate::mesh::Mesh;
+use crate::point::{Element, Mat4, Quaternion, Vec3};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Node {
+    pub translation: Vec3,
+    pub rotation: Quaternion,
+    pub scale: Vec3,
+    // how far it turns about its own middle every tic, like `Mesh::spin`
+    pub spin: Quaternion,
+    pub meshes: Vec<usize>,
+    pub children: Vec<Node>,
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Self {
+            translation: Vec3::zeroes(),
+            rotation: Quaternion::identity(),
+            scale: Vec3::new([1.0, 1.0, 1.0]),
+            spin: Quaternion::identity(),
+            meshes: vec![],
+            children: vec![],
+        }
+    }
+}
+
+impl Node {
+    pub fn with_mesh(mesh: usize) -> Self {
+        Self {
+            meshes: vec![mesh],
+            ..Self::default()
+        }
+    }
+
+    // Scaled, then rotated, then moved into the parent's space.
+    pub fn local_matrix(&self) -> Mat4 {
+        Mat4::scaling(self.scale.x(), self.scale.y(), self.scale.z()) *
+            self.rotation.to_mat4() *
+            Mat4::translation(self.translation.x(), self.translation.y(), self.translation.z())
+    }
+
+    pub fn turn(&mut self) {
+        self.rotation = (self.spin * self.rotation).normal();
+        for child in self.children.iter_mut() {
+            child.turn();
+        }
+    }
+
+    fn place(&self, parent: &Mat4, meshes: &[Mesh], out: &mut Vec<(usize, Mat4)>) {
+        let world = self.local_matrix() * *parent;
+        for &mesh in self.meshes.iter().filter(|&&mesh| mesh < meshes.len()) {
+            out.push((mesh, meshes[mesh].world_matrix() * world));
+        }
+        for child in self.children.iter() {
+            child.place(&world, meshes, out);
+        }
+    }
+
+    fn uses(&self, mesh: usize) -> bool {
+        self.meshes.contains(&mesh) || self.children.iter().any(|child| child.uses(mesh))
+    }
+}
+
+// `centre` with `count` smaller copies of `satellite` going around it, evenly spaced, each with a
+// moon (another `satellite`) going around it in turn.
+pub fn mk_orbits(centre: usize, satellite: usize, count: usize) -> Node {
+    let up = Vec3::new([0.0, 1.0, 0.0]);
+    let moon = Node {
+        spin: Quaternion::from_axis_angle(&up, 0.03),
+        children: vec![Node {
+            translation: Vec3::new([2.5, 0.0, 0.0]),
+            scale: Vec3::new([0.4, 0.4, 0.4]),
+            ..Node::with_mesh(satellite)
+        }],
+        ..Node::default()
+    };
+    let planets = (0..count)
+        .map(|k| Node {
+            rotation: Quaternion::from_axis_angle(&up, 2.0 * PI * k as Element / count as Element),
+            spin: Quaternion::from_axis_angle(&up, 0.01),
+            children: vec![Node {
+                translation: Vec3::new([3.0, 0.0, 0.0]),
+                scale: Vec3::new([0.4, 0.4, 0.4]),
+                children: vec![moon.clone()],
+                ..Node::with_mesh(satellite)
+            }],
+            ..Node::default()
+        })
+        .collect();
+
+    Node {
+        children: planets,
+        ..Node::with_mesh(centre)
+    }
+}
+
+// Every mesh to draw and its world matrix: first the ones outside the graph, then the graph's
+// depth first.
+pub fn placements(meshes: &[Mesh], roots: &[Node]) -> Vec<(usize, Mat4)> {
+    let mut out = (0..meshes.len())
+        .filter(|&mesh| !roots.iter().any(|root| root.uses(mesh)))
+        .map(|mesh| (mesh, meshes[mesh].world_matrix()))
+        .collect::<Vec<(usize, Mat4)>>();
+    for root in roots.iter() {
+        root.place(&Mat4::identity(), meshes, &mut out);
+    }
+
+    out
+}
+
+pub fn tic(roots: &mut [Node]) {
+    for root in roots.iter_mut() {
+        root.turn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // where a mesh's own origin ends up
+    fn centre(placement: &(usize, Mat4)) -> Vec3 {
+        Vec3::transform_coordinates(&Vec3::zeroes(), &placement.1)
+    }
+
+    fn cubes(count: usize) -> Vec<Mesh> {
+        vec![Mesh::mk_cube(); count]
+    }
+
+    #[test]
+    fn nested_transforms() {
+        // a parent scaled up and moved, its child moved again in the parent's (scaled) space
+        let mut meshes = cubes(2);
+        meshes[1].origin = Vec3::new([1.0, 0.0, 0.0]);
+        let root = Node {
+            translation: Vec3::new([10.0, 0.0, 0.0]),
+            scale: Vec3::new([2.0, 2.0, 2.0]),
+            children: vec![Node {
+                translation: Vec3::new([0.0, 2.0, 0.0]),
+                ..Node::with_mesh(1)
+            }],
+            ..Node::with_mesh(0)
+        };
+        let placed = placements(&meshes, &[root]);
+        assert_eq!(placed.iter().map(|(mesh, _)| *mesh).collect::<Vec<usize>>(), vec![0, 1]);
+        assert!(centre(&placed[0]).approx_eq(&Vec3::new([10.0, 0.0, 0.0]), 1e-12));
+        // the mesh's own origin is scaled along with everything else in the node
+        assert!(centre(&placed[1]).approx_eq(&Vec3::new([12.0, 4.0, 0.0]), 1e-12));
+
+        // turning the parent swings the child round it without changing how far away it is
+        let root = Node {
+            rotation: Quaternion::from_axis_angle(&Vec3::new([0.0, 1.0, 0.0]), 1.0),
+            children: vec![Node {
+                translation: Vec3::new([3.0, 0.0, 0.0]),
+                ..Node::with_mesh(0)
+            }],
+            ..Node::default()
+        };
+        let child = centre(&placements(&cubes(1), &[root])[0]);
+        assert!((child.magnitude() - 3.0).abs() < 1e-12);
+        assert!(child.y().abs() < 1e-12);
+        assert!((child.x() - 3.0 * Element::cos(1.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn which_meshes() {
+        // 0 and 2 aren't in the graph so come first on their own, 1 is in it twice, 7 doesn't exist
+        let root = Node {
+            children: vec![Node::with_mesh(7), Node::with_mesh(1)],
+            ..Node::with_mesh(1)
+        };
+        let mut meshes = cubes(3);
+        meshes[2].origin = Vec3::new([0.0, 0.0, 5.0]);
+        let placed = placements(&meshes, &[root]);
+        assert_eq!(placed.iter().map(|(mesh, _)| *mesh).collect::<Vec<usize>>(), vec![0, 2, 1, 1]);
+        assert!(placed[1].1.approx_eq(&meshes[2].world_matrix(), 1e-12));
+
+        assert_eq!(placements(&meshes, &[]).len(), 3);
+    }
+
+    #[test]
+    fn orbits() {
+        let mut roots = [mk_orbits(0, 1, 3)];
+        let placed = placements(&cubes(2), &roots);
+        // the middle, three planets and a moon each
+        assert_eq!(placed.len(), 7);
+        let planets = [1, 3, 5].map(|i| centre(&placed[i]));
+        let moons = [2, 4, 6].map(|i| centre(&placed[i]));
+        for (planet, moon) in planets.iter().zip(moons.iter()) {
+            assert!((planet.magnitude() - 3.0).abs() < 1e-12);
+            // 2.5 out inside a planet that's been shrunk to 0.4
+            assert!((moon.distance(planet) - 1.0).abs() < 1e-12);
+        }
+        assert!((planets[0].distance(&planets[1]) - 3.0 * Element::sqrt(3.0)).abs() < 1e-12);
+
+        // a tic moves the planets along their orbits and the moons along theirs
+        tic(&mut roots);
+        let after = placements(&cubes(2), &roots);
+        let planet = centre(&after[1]);
+        let moon = centre(&after[2]);
+        assert!((planet.magnitude() - 3.0).abs() < 1e-12);
+        assert!(planet.distance(&planets[0]) > 1e-3);
+        assert!((moon.distance(&planet) - 1.0).abs() < 1e-12);
+        assert!((moon - planet).distance(&(moons[0] - planets[0])) > 1e-3);
+    }
+}
@@ -0,0 +1,307 @@
+// Hidden-line removal. Everything here happens in view space where the eye sits at the origin.
+// A point is hidden by a triangle when it's inside the (infinite) pyramid running from the eye
+// through the triangle and on the far side of the triangle's plane. Each of those four conditions
+// is a half-space so the hidden part of a line is just the line clipped against them, and
+// because we never leave 3D the perspective divide can't throw the answer off.
+//
+// Clipping is the slow part, so `occluders` works out everything that doesn't depend on the line
+// once per frame and `spans` throws out triangles that are too near or off to the side before
+// clipping against the rest.
+
+use crate::mesh::Mesh;
+use crate::point::{Element, Mat4, Vec3};
+use crate::topology::face_area_vector;
+
+// A triangle in view space, with its plane, the walls of its pyramid and how much room it takes
+// up on screen worked out ahead of time.
+pub struct Occluder {
+    // which placement and which of its vertices the corners came from
+    owner: usize,
+    vertices: [usize; 3],
+    // facing the far side, so `normal.dot(p) > offset` is behind the triangle
+    normal: Vec3,
+    offset: Element,
+    // facing in, so `wall.dot(p) > 0` is inside the pyramid
+    walls: [Vec3; 3],
+    tolerance: Element,
+    // the corner nearest the eye; nothing nearer can be behind the triangle
+    near: Element,
+    // x/z and y/z of the corners, if they're all in front of the eye
+    bounds: Option<Bounds>,
+}
+
+// (min x, min y, max x, max y) on the screen before it's scaled to the canvas
+type Bounds = (Element, Element, Element, Element);
+
+// Lines lying in (or touching) an occluding face must not count as behind it, so every test
+// needs to pass by a little bit, relative to how far away things are.
+const EPSILON: Element = 1e-7;
+
+fn bounds(points: &[Vec3]) -> Option<Bounds> {
+    if points.iter().any(|p| p.z() <= 0.0) {
+        return None;
+    }
+
+    Some(points.iter().fold(
+        (Element::INFINITY, Element::INFINITY, -Element::INFINITY, -Element::INFINITY),
+        |(lo_x, lo_y, hi_x, hi_y), p| {
+            let (x, y) = (p.x() / p.z(), p.y() / p.z());
+            (lo_x.min(x), lo_y.min(y), hi_x.max(x), hi_y.max(y))
+        }))
+}
+
+// The faces of `mesh` as view space triangles (polygons are fanned), tagged with `owner` so
+// `spans` can tell which ones a line lies on.
+//
+// If the mesh is closed, anything behind one of its triangles facing away from the eye is also
+// behind one facing it (the line of sight has to get in before it can get out), so only the
+// ones facing the eye are kept. That needs the eye to be outside the mesh, which it is unless
+// the camera flies into something.
+pub fn occluders(mesh: &Mesh, world_view: &Mat4, owner: usize) -> Vec<Occluder> {
+    let view = mesh.vertices.iter()
+        .map(|v| Vec3::transform_coordinates(v, world_view))
+        .collect::<Vec<Vec3>>();
+
+    // +1 for faces wound to point out (so the ones facing the eye point at it), -1 for faces
+    // wound the other way and 0 if there's no inside to go by. Worked out in view space so a
+    // placement that mirrors the mesh is taken care of.
+    let facing = if mesh.is_closed() {
+        let volume = mesh.faces.iter()
+            .map(|face| view[face[0]].dot(&face_area_vector(&view, face)))
+            .sum::<Element>();
+        volume.signum()
+    } else {
+        0.0
+    };
+
+    mesh.faces.iter()
+        .flat_map(|face| (1..face.len() - 1).map(move |i| [face[0], face[i], face[i + 1]]))
+        .filter_map(|vertices| {
+            let corners = vertices.map(|v| view[v]);
+            let [p, q, r] = corners;
+            let normal = (q - p).cross(&(r - p)).normal();
+            if facing * normal.dot(&p) > 0.0 {
+                // facing away
+                return None;
+            }
+
+            let scale = p.magnitude().max(q.magnitude()).max(r.magnitude());
+            let tolerance = EPSILON * scale;
+            let offset = normal.dot(&p);
+            if offset.abs() <= tolerance {
+                // seen edge on
+                return None;
+            }
+
+            // far side of the plane, i.e. the opposite side to the eye
+            let side = offset.signum();
+
+            // each side of the pyramid passes through the eye and two corners
+            let walls = [(p, q, r), (q, r, p), (r, p, q)].map(|(a, b, c)| {
+                let wall = a.cross(&b).normal();
+                if wall.dot(&c) < 0.0 {
+                    wall.scale(-1.0)
+                } else {
+                    wall
+                }
+            });
+
+            Some(Occluder {
+                owner,
+                vertices,
+                normal: normal.scale(side),
+                offset: offset * side,
+                walls,
+                tolerance,
+                near: p.z().min(q.z()).min(r.z()),
+                bounds: bounds(&corners),
+            })
+        })
+        .collect()
+}
+
+// a + b*s > 0 for s in [lo, hi]; shrinks the range to where it holds
+fn clip(range: (Element, Element), a: Element, b: Element) -> (Element, Element) {
+    let (lo, hi) = range;
+    if b.abs() < Element::EPSILON {
+        return if a > 0.0 { range } else { (1.0, 0.0) };
+    }
+
+    let root = -a / b;
+    if b > 0.0 {
+        (lo.max(root), hi)
+    } else {
+        (lo, hi.min(root))
+    }
+}
+
+// The stretch (as fractions of the way from `from` to `to`) that `occluder` hides, if any.
+fn hidden_by(from: &Vec3, to: &Vec3, occluder: &Occluder) -> Option<(Element, Element)> {
+    let Occluder { normal, offset, walls, tolerance, .. } = occluder;
+    let dir = *to - *from;
+
+    let mut range = clip((0.0, 1.0), normal.dot(from) - offset - tolerance, normal.dot(&dir));
+
+    // inside the pyramid, grown a touch so neighbouring triangles overlap instead of leaving
+    // slivers between them
+    for wall in walls {
+        if range.0 >= range.1 {
+            return None;
+        }
+        range = clip(range, wall.dot(from) + tolerance, wall.dot(&dir));
+    }
+
+    let (lo, hi) = range;
+    if lo < hi {
+        Some((lo, hi))
+    } else {
+        None
+    }
+}
+
+// Whether `occluder` could hide any of the line at all. It can't if the line is one of the
+// triangle's own edges (or a diagonal of the face it was fanned from), if the whole line is
+// nearer than the triangle or if they don't overlap on the screen.
+fn in_the_way(line: (usize, usize, usize), far: Element, bounds: Option<Bounds>, occluder: &Occluder) -> bool {
+    let (owner, a, b) = line;
+    if owner == occluder.owner && occluder.vertices.contains(&a) && occluder.vertices.contains(&b) {
+        return false;
+    }
+
+    if far < occluder.near - occluder.tolerance {
+        return false;
+    }
+
+    match (bounds, occluder.bounds) {
+        (Some((lo_x, lo_y, hi_x, hi_y)), Some((o_lo_x, o_lo_y, o_hi_x, o_hi_y))) => {
+            // a little slack for the same reason the pyramid is grown
+            let slack = EPSILON;
+            lo_x <= o_hi_x + slack && o_lo_x <= hi_x + slack && lo_y <= o_hi_y + slack && o_lo_y <= hi_y + slack
+        },
+        // something's behind the eye, so don't trust the projection
+        _ => true,
+    }
+}
+
+// Split the view space line `from`-`to` into consecutive spans covering all of it, each flagged
+// visible or not. Spans are fractions of the way along the line. `line` is the placement and the
+// vertices the ends came from, matching `Occluder::owner` and `Occluder::vertices`.
+pub fn spans(from: &Vec3, to: &Vec3, line: (usize, usize, usize), occluders: &[Occluder]) -> Vec<(Element, Element, bool)> {
+    // the far end; all of the line is nearer than that
+    let far = from.z().max(to.z());
+    let bounds = bounds(&[*from, *to]);
+
+    let mut hidden = occluders.iter()
+        .filter(|occluder| in_the_way(line, far, bounds, occluder))
+        .filter_map(|occluder| hidden_by(from, to, occluder))
+        .collect::<Vec<(Element, Element)>>();
+    hidden.sort_by(|lhs, rhs| lhs.0.total_cmp(&rhs.0));
+
+    let mut out = vec![];
+    let mut at = 0.0;
+    for (lo, hi) in hidden {
+        if hi <= at {
+            continue;
+        }
+        if lo > at {
+            out.push((at, lo, true));
+        } else if let Some(last) = out.last_mut().filter(|last| !last.2) {
+            // overlaps the hidden span before it
+            last.1 = hi;
+            at = hi;
+            continue;
+        }
+        out.push((lo.max(at), hi, false));
+        at = hi;
+    }
+    if at < 1.0 {
+        out.push((at, 1.0, true));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a unit square facing the eye, 5 away
+    fn square(flip: bool) -> Mesh {
+        let face = if flip { vec![0, 3, 2, 1] } else { vec![0, 1, 2, 3] };
+        Mesh::with_faces(
+            Vec3::zeroes(),
+            vec![
+                Vec3::new([-1.0, -1.0, 5.0]),
+                Vec3::new([1.0, -1.0, 5.0]),
+                Vec3::new([1.0, 1.0, 5.0]),
+                Vec3::new([-1.0, 1.0, 5.0]),
+            ],
+            vec![face],
+        ).expect("a fine square")
+    }
+
+    fn close(lhs: Element, rhs: Element) -> bool {
+        (lhs - rhs).abs() < 1e-6
+    }
+
+    #[test]
+    fn closed_meshes_keep_the_faces_towards_the_eye() {
+        let cube = Mesh::mk_cube();
+        let away = Mat4::translation(0.0, 0.0, 5.0);
+        assert_eq!(occluders(&cube, &away, 0).len(), 2);
+
+        // turned inside out by the placement
+        let mirrored = Mat4::scaling(1.0, 1.0, -1.0) * away;
+        assert_eq!(occluders(&cube, &mirrored, 0).len(), 2);
+    }
+
+    #[test]
+    fn open_meshes_keep_every_face() {
+        for flip in [false, true] {
+            assert_eq!(occluders(&square(flip), &Mat4::identity(), 0).len(), 2);
+        }
+    }
+
+    #[test]
+    fn behind_in_front_and_beside() {
+        let occluders = occluders(&square(false), &Mat4::identity(), 0);
+        let line = (1, 0, 1);
+
+        // at twice the distance the square covers the middle two thirds of x in [-3, 3]
+        let behind = spans(&Vec3::new([-3.0, 0.0, 10.0]), &Vec3::new([3.0, 0.0, 10.0]), line, &occluders);
+        assert_eq!(behind.len(), 3);
+        assert!(behind[0].2 && !behind[1].2 && behind[2].2);
+        assert!(close(behind[1].0, 1.0 / 6.0) && close(behind[1].1, 5.0 / 6.0));
+
+        let in_front = spans(&Vec3::new([-3.0, 0.0, 2.0]), &Vec3::new([3.0, 0.0, 2.0]), line, &occluders);
+        assert_eq!(in_front, vec![(0.0, 1.0, true)]);
+
+        let beside = spans(&Vec3::new([-3.0, 5.0, 10.0]), &Vec3::new([3.0, 5.0, 10.0]), line, &occluders);
+        assert_eq!(beside, vec![(0.0, 1.0, true)]);
+
+        // one end behind the eye
+        let through = spans(&Vec3::new([0.0, 0.0, -1.0]), &Vec3::new([0.0, 0.0, 9.0]), line, &occluders);
+        assert!(through.iter().any(|span| !span.2));
+        assert!(close(through[0].1, 0.6));
+    }
+
+    #[test]
+    fn near_and_far_edges_of_a_cube() {
+        let cube = Mesh::mk_cube();
+        let away = Mat4::translation(0.0, 0.0, 5.0);
+        let occluders = occluders(&cube, &away, 0);
+
+        let view = cube.vertices.iter()
+            .map(|v| Vec3::transform_coordinates(v, &away))
+            .collect::<Vec<Vec3>>();
+        for &(a, b) in cube.lines.iter() {
+            let spans = spans(&view[a], &view[b], (0, a, b), &occluders);
+            if view[a].z() == 4.0 && view[b].z() == 4.0 {
+                assert_eq!(spans, vec![(0.0, 1.0, true)], "{:?}", (a, b));
+            }
+            if view[a].z() == 6.0 && view[b].z() == 6.0 {
+                assert_eq!(spans, vec![(0.0, 1.0, false)], "{:?}", (a, b));
+            }
+        }
+    }
+}
@@ -0,0 +1,359 @@
+// Convex hulls of point clouds. The hull is grown one point at a time from a starting tetrahedron:
+// a point that sees some of the hull's triangles (is in front of their planes) replaces them with a
+// fan of new triangles from the point to the edge of what it sees. Points that don't see anything,
+// including repeats and points that land on the hull's surface, are left out.
+//
+// Once it's done the triangles lying in the same plane are merged back into polygons and corners
+// in the middle of a straight edge are dropped, so a cube's worth of points gives six squares and
+// no diagonals. Flat and straight point sets give a single polygon or a line.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::mesh::{Face, Mesh, MeshError};
+use crate::point::{Element, Vec3};
+
+// How far off a plane (or line) a point has to be to count, relative to the size of the cloud.
+const EPSILON: Element = 1e-9;
+
+struct Triangle {
+    corners: [usize; 3],
+    normal: Vec3,
+    offset: Element,
+    alive: bool,
+}
+
+impl Triangle {
+    fn new(points: &[Vec3], corners: [usize; 3]) -> Self {
+        let [a, b, c] = corners.map(|i| points[i]);
+        let normal = (b - a).cross(&(c - a)).normal();
+        Self {
+            corners,
+            normal,
+            offset: normal.dot(&a),
+            alive: true,
+        }
+    }
+
+    fn height(&self, p: &Vec3) -> Element {
+        self.normal.dot(p) - self.offset
+    }
+}
+
+// the point with the largest `distance`, and what that is
+fn furthest(points: &[Vec3], distance: impl Fn(&Vec3) -> Element) -> (usize, Element) {
+    points.iter()
+        .enumerate()
+        .map(|(i, p)| (i, distance(p)))
+        .fold((0, Element::NEG_INFINITY), |best, x| if x.1 > best.1 { x } else { best })
+}
+
+impl Mesh {
+    // The smallest convex mesh containing every point. Only points on its corners make it in.
+    pub fn convex_hull(points: &[Vec3]) -> Result<Mesh, MeshError> {
+        if points.is_empty() {
+            return Err(MeshError::Empty);
+        }
+        if let Some((index, vertex)) = points.iter().enumerate().find(|(_, p)| p.coord.iter().any(|x| !x.is_finite())) {
+            return Err(MeshError::NonFiniteVertex { index, vertex: *vertex });
+        }
+
+        let size = points.iter()
+            .flat_map(|p| p.coord)
+            .fold(0.0, |acc: Element, x| acc.max(x.abs()));
+        let tolerance = EPSILON * size.max(1.0);
+
+        // a starting tetrahedron, as big as we can easily find
+        let a = furthest(points, |p| -p.x()).0;
+        let (b, spread) = furthest(points, |p| (*p - points[a]).magnitude());
+        if spread <= tolerance {
+            return Mesh::new(Vec3::zeroes(), vec![points[a]], vec![]);
+        }
+        let along = (points[b] - points[a]).normal();
+        let (c, width) = furthest(points, |p| {
+            let offset = *p - points[a];
+            (offset - along.scale(offset.dot(&along))).magnitude()
+        });
+        if width <= tolerance {
+            // all in a line, from one end to the other
+            let (lo, _) = furthest(points, |p| -p.dot(&along));
+            let (hi, _) = furthest(points, |p| p.dot(&along));
+            return Mesh::new(Vec3::zeroes(), vec![points[lo], points[hi]], vec![(0, 1)]);
+        }
+        let normal = (points[b] - points[a]).cross(&(points[c] - points[a])).normal();
+        let (d, depth) = furthest(points, |p| normal.dot(&(*p - points[a])).abs());
+        if depth <= tolerance {
+            return flat_hull(points, &normal, tolerance);
+        }
+
+        let centre = (points[a] + points[b] + points[c] + points[d]).scale(0.25);
+        let mut triangles = vec![];
+        for corners in [[a, b, c], [a, c, d], [a, d, b], [b, d, c]] {
+            let mut triangle = Triangle::new(points, corners);
+            if triangle.height(&centre) > 0.0 {
+                triangle = Triangle::new(points, [corners[0], corners[2], corners[1]]);
+            }
+            triangles.push(triangle);
+        }
+
+        // far points first, they swallow the most
+        let mut order = (0..points.len()).collect::<Vec<usize>>();
+        order.sort_by(|&i, &j| (points[j] - centre).magnitude().total_cmp(&(points[i] - centre).magnitude()));
+        for p in order {
+            let seen = (0..triangles.len())
+                .filter(|&t| triangles[t].alive && triangles[t].height(&points[p]) > tolerance)
+                .collect::<Vec<usize>>();
+            if seen.is_empty() {
+                continue;
+            }
+
+            // the edges of the seen patch are the ones whose other side wasn't seen
+            let mut edges = HashSet::<(usize, usize)>::new();
+            for &t in seen.iter() {
+                triangles[t].alive = false;
+                let [x, y, z] = triangles[t].corners;
+                for edge in [(x, y), (y, z), (z, x)] {
+                    edges.insert(edge);
+                }
+            }
+            let mut horizon = edges.iter()
+                .filter(|&&(x, y)| !edges.contains(&(y, x)))
+                .copied()
+                .collect::<Vec<(usize, usize)>>();
+            // (so the same points always give the same mesh)
+            horizon.sort();
+            for (x, y) in horizon {
+                triangles.push(Triangle::new(points, [x, y, p]));
+            }
+        }
+
+        let triangles = triangles.into_iter().filter(|t| t.alive).collect::<Vec<Triangle>>();
+        let faces = merge_coplanar(points, &triangles, tolerance);
+        compact(points, faces)
+    }
+}
+
+// Join neighbouring triangles that lie in the same plane into polygons, wound the same way.
+fn merge_coplanar(points: &[Vec3], triangles: &[Triangle], tolerance: Element) -> Vec<Face> {
+    let mut owner = HashMap::<(usize, usize), usize>::new();
+    for (t, triangle) in triangles.iter().enumerate() {
+        let [x, y, z] = triangle.corners;
+        for edge in [(x, y), (y, z), (z, x)] {
+            owner.insert(edge, t);
+        }
+    }
+
+    // union-find over the triangles
+    let mut group = (0..triangles.len()).collect::<Vec<usize>>();
+    fn root(group: &mut [usize], mut t: usize) -> usize {
+        while group[t] != t {
+            group[t] = group[group[t]];
+            t = group[t];
+        }
+        t
+    }
+    for (t, triangle) in triangles.iter().enumerate() {
+        let [x, y, z] = triangle.corners;
+        for (from, to) in [(x, y), (y, z), (z, x)] {
+            let Some(&other) = owner.get(&(to, from)) else {
+                continue;
+            };
+            let flat = triangles[other].corners
+                .iter()
+                .all(|&corner| triangle.height(&points[corner]).abs() <= tolerance);
+            if flat {
+                let (r, s) = (root(&mut group, t), root(&mut group, other));
+                group[r] = s;
+            }
+        }
+    }
+
+    // each group's outline: the edges whose reverse is in some other group
+    let mut outlines = HashMap::<usize, HashMap<usize, usize>>::new();
+    for (t, triangle) in triangles.iter().enumerate() {
+        let r = root(&mut group, t);
+        let [x, y, z] = triangle.corners;
+        for (from, to) in [(x, y), (y, z), (z, x)] {
+            let inside = owner.get(&(to, from)).is_some_and(|&other| root(&mut group, other) == r);
+            if !inside {
+                outlines.entry(r).or_default().insert(from, to);
+            }
+        }
+    }
+
+    let mut roots = outlines.keys().copied().collect::<Vec<usize>>();
+    roots.sort();
+    roots.into_iter()
+        .filter_map(|r| {
+            let next = &outlines[&r];
+            let start = *next.keys().min()?;
+            let mut face = vec![start];
+            let mut at = next[&start];
+            while at != start && face.len() <= next.len() {
+                face.push(at);
+                at = *next.get(&at)?;
+            }
+            Some(straighten(points, face, tolerance))
+        })
+        .filter(|face| face.len() >= 3)
+        .collect()
+}
+
+// Drop corners that sit on the straight line between their neighbours.
+fn straighten(points: &[Vec3], mut face: Face, tolerance: Element) -> Face {
+    let mut i = 0;
+    while face.len() > 3 && i < face.len() {
+        let n = face.len();
+        let (prev, here, next) = (points[face[(i + n - 1) % n]], points[face[i]], points[face[(i + 1) % n]]);
+        let along = (next - prev).normal();
+        let offset = here - prev;
+        if (offset - along.scale(offset.dot(&along))).magnitude() <= tolerance {
+            face.remove(i);
+            i = 0;
+        } else {
+            i += 1;
+        }
+    }
+
+    face
+}
+
+// All the points lie in the plane with `normal`: their hull is a single polygon (Andrew's
+// monotone chain in the plane).
+fn flat_hull(points: &[Vec3], normal: &Vec3, tolerance: Element) -> Result<Mesh, MeshError> {
+    let across = if normal.x().abs() < 0.9 { Vec3::new([1.0, 0.0, 0.0]) } else { Vec3::new([0.0, 1.0, 0.0]) };
+    let u = normal.cross(&across).normal();
+    let v = normal.cross(&u);
+    let flat = points.iter().map(|p| (p.dot(&u), p.dot(&v))).collect::<Vec<(Element, Element)>>();
+
+    let mut order = (0..points.len()).collect::<Vec<usize>>();
+    order.sort_by(|&i, &j| flat[i].0.total_cmp(&flat[j].0).then(flat[i].1.total_cmp(&flat[j].1)));
+
+    // whether o -> a -> b turns anticlockwise (seen from the side `normal` points to), by more
+    // than a going just off the line from o to b
+    let turns = |o: usize, a: usize, b: usize| {
+        let (ax, ay) = (flat[a].0 - flat[o].0, flat[a].1 - flat[o].1);
+        let (bx, by) = (flat[b].0 - flat[o].0, flat[b].1 - flat[o].1);
+        ax * by - ay * bx > tolerance * bx.hypot(by)
+    };
+    let chain = |order: &mut dyn Iterator<Item = usize>| {
+        let mut hull = Vec::<usize>::new();
+        for p in order {
+            while hull.len() >= 2 && !turns(hull[hull.len() - 2], hull[hull.len() - 1], p) {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop();
+        hull
+    };
+    let mut face = chain(&mut order.iter().copied());
+    face.extend(chain(&mut order.iter().rev().copied()));
+
+    compact(points, vec![face])
+}
+
+// A mesh of just the points the faces use.
+fn compact(points: &[Vec3], faces: Vec<Face>) -> Result<Mesh, MeshError> {
+    let mut remap = HashMap::<usize, usize>::new();
+    let mut vertices = vec![];
+    let faces = faces.into_iter()
+        .map(|face| face.into_iter()
+            .map(|p| *remap.entry(p).or_insert_with(|| {
+                vertices.push(points[p]);
+                vertices.len() - 1
+            }))
+            .collect())
+        .collect();
+
+    Mesh::with_faces(Vec3::zeroes(), vertices, faces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::polyhedra::Platonic;
+    use crate::topology::face_normal;
+
+    fn counts(mesh: &Mesh) -> (usize, usize, usize) {
+        (mesh.vertices.len(), mesh.lines.len(), mesh.faces.len())
+    }
+
+    // every point on or behind every face
+    fn contains(hull: &Mesh, points: &[Vec3]) -> bool {
+        hull.faces.iter().all(|face| {
+            let normal = face_normal(&hull.vertices, face);
+            let offset = normal.dot(&hull.vertices[face[0]]);
+            points.iter().all(|p| normal.dot(p) - offset <= 1e-9)
+        })
+    }
+
+    // the same "random" points every run
+    fn cloud(count: usize) -> Vec<Vec3> {
+        let mut state = 12345u64;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 11) as Element / (1u64 << 53) as Element * 2.0 - 1.0
+        };
+        (0..count).map(|_| Vec3::new([next(), next(), next()])).collect()
+    }
+
+    #[test]
+    fn solids() {
+        // every point of a 3x3x3 grid, some twice: only the corners make it and the faces are squares
+        let mut grid = (0..27)
+            .map(|n| Vec3::new([n % 3, n / 3 % 3, n / 9].map(|x| x as Element - 1.0)))
+            .collect::<Vec<Vec3>>();
+        grid.extend(grid.clone());
+        let cube = Mesh::convex_hull(&grid).unwrap();
+        assert_eq!(counts(&cube), (8, 12, 6));
+        assert!(cube.faces.iter().all(|face| face.len() == 4));
+        assert!(cube.is_closed());
+        assert!(cube.is_manifold());
+        assert!(contains(&cube, &grid));
+
+        // an icosahedron with a cloud well inside it (its faces are about 0.79 from the middle)
+        let mut points = cloud(200).into_iter().map(|p| p.scale(0.4)).collect::<Vec<Vec3>>();
+        points.extend(Mesh::mk_platonic(Platonic::Icosahedron).vertices);
+        let icosahedron = Mesh::convex_hull(&points).unwrap();
+        assert_eq!(counts(&icosahedron), (12, 30, 20));
+        assert!(icosahedron.vertices.iter().all(|v| (v.magnitude() - 1.0).abs() < 1e-9));
+        assert!(contains(&icosahedron, &points));
+
+        // and the cloud on its own
+        let points = cloud(500);
+        let hull = Mesh::convex_hull(&points).unwrap();
+        assert_eq!(hull.euler_characteristic(), 2);
+        assert!(hull.is_closed());
+        assert!(hull.is_manifold());
+        assert!(contains(&hull, &points));
+    }
+
+    #[test]
+    fn flat_and_straight() {
+        // a tilted 4x4 grid of points is one square
+        let (u, v) = (Vec3::new([1.0, 1.0, 0.0]), Vec3::new([0.0, 1.0, 1.0]));
+        let plane = (0..16)
+            .map(|n| u.scale((n % 4) as Element) + v.scale((n / 4) as Element))
+            .collect::<Vec<Vec3>>();
+        let square = Mesh::convex_hull(&plane).unwrap();
+        assert_eq!(counts(&square), (4, 4, 1));
+        assert!(!square.is_closed());
+
+        // points along a line are a line from one end to the other
+        let line = [3.0, -1.0, 0.5, 2.0, -1.0].map(|t| Vec3::new([t, 2.0 * t, 1.0]));
+        let segment = Mesh::convex_hull(&line).unwrap();
+        assert_eq!(counts(&segment), (2, 1, 0));
+        assert!(segment.vertices.contains(&line[0]) && segment.vertices.contains(&line[1]));
+
+        let same = Mesh::convex_hull(&[Vec3::new([1.0, 2.0, 3.0]); 4]).unwrap();
+        assert_eq!(counts(&same), (1, 0, 0));
+    }
+
+    #[test]
+    fn bad_points() {
+        assert_eq!(Mesh::convex_hull(&[]).unwrap_err(), MeshError::Empty);
+        let points = [Vec3::zeroes(), Vec3::new([1.0, Element::INFINITY, 0.0])];
+        assert!(matches!(Mesh::convex_hull(&points), Err(MeshError::NonFiniteVertex { index: 1, .. })));
+    }
+}
@@ -0,0 +1,638 @@
+// Wireframes with more than three dimensions. A `Polytope<N>` keeps its vertices in N dimensions,
+// turns them in any of the planes between two axes (4D has six of them, 5D ten and so on) and then
+// casts a perspective shadow one dimension down (like a camera flattens 3D onto the screen), over
+// and over until it's 3D and becomes an ordinary `Mesh` for the rest of the pipeline to draw.
+//
+// Shrinking the far side in the shadow is what makes the rotating tesseract look like a cube
+// turning itself inside out.
+
+use std::collections::BTreeSet;
+
+use crate::error::message_error;
+use crate::mesh::{Line, Mesh};
+use crate::point::{Element, Matrix, Point, Quaternion, Vec3, Vec4};
+use crate::polyhedra::shortest_lines;
+
+const PHI: Element = 1.618_033_988_749_895;
+
+// how far past the unit hypersphere a vertex can stray through rounding
+const ROUNDING: Element = 1e-9;
+
+message_error!(PolytopeError, "polytope");
+
+// The regular polytopes worth drawing (the 5-cell is left out, it's just a tetrahedron with a
+// vertex in the middle from most angles).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Regular {
+    // 16 vertices, 32 edges
+    Tesseract,
+    // 8 vertices, 24 edges
+    SixteenCell,
+    // 24 vertices, 96 edges
+    TwentyFourCell,
+    // 600 vertices, 1200 edges
+    HundredTwentyCell,
+    // 120 vertices, 720 edges
+    SixHundredCell,
+}
+
+impl Regular {
+    pub const ALL: [Regular; 5] = [
+        Regular::Tesseract,
+        Regular::SixteenCell,
+        Regular::TwentyFourCell,
+        Regular::HundredTwentyCell,
+        Regular::SixHundredCell,
+    ];
+
+    // by its snake_case name, like in scene files
+    pub fn from_name(name: &str) -> Option<Regular> {
+        Regular::ALL.into_iter().find(|regular| format!("{:?}", regular).to_lowercase() == name.replace('_', ""))
+    }
+}
+
+// A turn in the plane of two axes, from the first towards the second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Turn {
+    pub axes: (usize, usize),
+    // radians
+    pub angle: Element,
+    // added to `angle` every tic
+    pub spin: Element,
+}
+
+// The families that exist in every number of dimensions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Family {
+    // 2^n vertices, n * 2^(n-1) edges
+    Cube,
+    // n + 1 vertices, all joined up
+    Simplex,
+    // 2n vertices, joined to all but their opposite
+    Orthoplex,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "de::PolytopeParts<N>"))]
+pub struct Polytope<const N: usize> {
+    pub vertices: Vec<Point<N>>,
+    pub lines: Vec<Line>,
+    // applied in order
+    pub turns: Vec<Turn>,
+    // how far up the last axis we look from, at every step of the way down to 3D; it has to be
+    // outside the polytope (further than 1)
+    pub eye: Element,
+}
+
+impl<const N: usize> Polytope<N> {
+    // Vertices get scaled to fit in the unit hypersphere. By default it turns x into each extra
+    // axis, which is the interesting part, a bit of z too and a little in xy so it isn't all one
+    // way.
+    pub fn new(vertices: Vec<Point<N>>, lines: Vec<Line>) -> Self {
+        let radius = vertices.iter().map(Point::magnitude).fold(0.0, Element::max);
+        let vertices = if radius > 0.0 {
+            vertices.iter().map(|v| v.scale(1.0 / radius)).collect()
+        } else {
+            vertices
+        };
+
+        let mut turns = vec![Turn { axes: (0, 1), angle: 0.0, spin: 0.002 }];
+        for axis in 3..N {
+            let slower = (axis - 2) as Element;
+            turns.push(Turn { axes: (0, axis), angle: 0.0, spin: 0.005 / slower });
+            turns.push(Turn { axes: (2, axis), angle: 0.0, spin: 0.003 / slower });
+        }
+
+        Self {
+            vertices,
+            lines,
+            turns,
+            eye: 2.0,
+        }
+    }
+
+    pub fn mk_family(family: Family) -> Self {
+        match family {
+            Family::Cube => Self::mk_cube(),
+            Family::Simplex => Self::mk_simplex(),
+            Family::Orthoplex => Self::mk_orthoplex(),
+        }
+    }
+
+    // corners at (±1, ..., ±1), joined when they differ in one coordinate
+    pub fn mk_cube() -> Self {
+        let vertices = (0..1usize << N)
+            .map(|bits| Point::new(std::array::from_fn(|i| if bits >> i & 1 == 1 { 1.0 } else { -1.0 })))
+            .collect();
+        let lines = (0..1usize << N)
+            .flat_map(|bits| (0..N).map(move |i| (bits, bits ^ 1 << i)))
+            .filter(|(a, b)| a < b)
+            .collect();
+
+        Self::new(vertices, lines)
+    }
+
+    // one corner along each axis and one more on the diagonal as far from each of them as they are
+    // from each other, moved so the middle is at the origin
+    pub fn mk_simplex() -> Self {
+        let n = N as Element;
+        let mut vertices = (0..N)
+            .map(|i| {
+                let mut v = Point::zeroes();
+                v.coord[i] = 1.0;
+                v
+            })
+            .collect::<Vec<Point<N>>>();
+        vertices.push(Point::new([(1.0 - (n + 1.0).sqrt()) / n; N]));
+        let centre = vertices.iter().fold(Point::zeroes(), |acc, v| acc + *v).scale(1.0 / (n + 1.0));
+        let vertices = vertices.into_iter().map(|v| v - centre).collect::<Vec<Point<N>>>();
+        let lines = (0..=N)
+            .flat_map(|a| (a + 1..=N).map(move |b| (a, b)))
+            .collect();
+
+        Self::new(vertices, lines)
+    }
+
+    // ±1 along each axis, joined to everything but the opposite corner
+    pub fn mk_orthoplex() -> Self {
+        let vertices = (0..2 * N)
+            .map(|i| {
+                let mut v = Point::zeroes();
+                v.coord[i / 2] = if i % 2 == 0 { 1.0 } else { -1.0 };
+                v
+            })
+            .collect();
+        let lines = (0..2 * N)
+            .flat_map(|a| (a + 1..2 * N).map(move |b| (a, b)))
+            .filter(|(a, b)| a / 2 != b / 2)
+            .collect();
+
+        Self::new(vertices, lines)
+    }
+
+    // Everything `shadow` and `to_mesh` count on: vertices in the unit hypersphere, lines between
+    // vertices that exist, turns in real planes and an eye outside the unit hypersphere. (Each
+    // step down keeps the hypersphere's shadow inside the next one, so nothing ever gets as far
+    // out as the eye.) The fields are public so anything that pokes at them (or a scene file)
+    // should check.
+    pub fn validate(&self) -> Result<(), PolytopeError> {
+        if self.vertices.is_empty() {
+            return error("there are no vertices".to_string());
+        }
+        if let Some(v) = self.vertices.iter().position(|v| !v.coord.iter().all(|x| x.is_finite())) {
+            return error(format!("vertex {} is not finite", v));
+        }
+        if let Some(v) = self.vertices.iter().position(|v| v.magnitude() > 1.0 + ROUNDING) {
+            return error(format!("vertex {} is outside the unit hypersphere", v));
+        }
+        if let Some(&(a, b)) = self.lines.iter().find(|&&(a, b)| a >= self.vertices.len() || b >= self.vertices.len() || a == b) {
+            return error(format!("line {:?} isn't between two of the {} vertices", (a, b), self.vertices.len()));
+        }
+        for turn in self.turns.iter() {
+            let (a, b) = turn.axes;
+            if a >= N || b >= N || a == b {
+                return error(format!("{:?} isn't a plane in {} dimensions", turn.axes, N));
+            }
+            if !turn.angle.is_finite() || !turn.spin.is_finite() {
+                return error(format!("the turn in {:?} isn't finite", turn.axes));
+            }
+        }
+        if !(self.eye.is_finite() && self.eye > 1.0) {
+            return error(format!("the eye has to be further out than 1, got {}", self.eye));
+        }
+
+        Ok(())
+    }
+
+    // Every turn, one after another.
+    pub fn rotation_matrix(&self) -> Matrix<N> {
+        self.turns.iter()
+            .fold(Matrix::identity(), |m, turn| {
+                let (a, b) = turn.axes;
+                m * Matrix::rotation_in_plane(a, b, turn.angle)
+            })
+    }
+
+    pub fn turn(&mut self) {
+        for turn in self.turns.iter_mut() {
+            turn.angle += turn.spin;
+        }
+    }
+}
+
+impl<const N: usize> Polytope<N>
+where
+    Point<N>: Shadow,
+{
+    // Rotated, then flattened one dimension at a time all the way down to 3D.
+    pub fn shadow(&self) -> Vec<Vec3> {
+        let m = self.rotation_matrix();
+        self.vertices.iter()
+            .map(|v| v.transform(&m).shadow(self.eye))
+            .collect()
+    }
+
+    // The shadow as a mesh that holds still in 3D, so all the movement is the turning in the
+    // higher dimensions.
+    pub fn to_mesh(&self) -> Mesh {
+        let mut mesh = Mesh::new(Vec3::zeroes(), self.shadow(), self.lines.clone())
+            .expect("polytope lines are between its own vertices");
+        mesh.spin = Quaternion::identity();
+
+        mesh
+    }
+}
+
+impl Polytope<4> {
+    pub fn mk_regular(regular: Regular) -> Self {
+        let vertices = match regular {
+            Regular::Tesseract => signs([1.0, 1.0, 1.0, 1.0]),
+            Regular::SixteenCell => axes(),
+            Regular::TwentyFourCell => (0..4)
+                .flat_map(|i| (i + 1..4).map(move |j| (i, j)))
+                .flat_map(|(i, j)| {
+                    let mut v = [0.0; 4];
+                    v[i] = 1.0;
+                    v[j] = 1.0;
+                    signs(v)
+                })
+                .collect(),
+            Regular::SixHundredCell => six_hundred_cell(),
+            Regular::HundredTwentyCell => return hundred_twenty_cell(),
+        };
+        let lines = shortest_lines(&vertices);
+
+        Self::new(vertices, lines)
+    }
+}
+
+// Points that can be flattened down to 3D.
+pub trait Shadow {
+    fn shadow(&self, eye: Element) -> Vec3;
+}
+
+impl Shadow for Vec3 {
+    fn shadow(&self, _eye: Element) -> Vec3 {
+        *self
+    }
+}
+
+// Drop the last coordinate, seen from `eye` along it: things nearer the eye look bigger. It's
+// scaled so the unit sphere's shadow just fits in the unit sphere, otherwise a few steps down the
+// next eye would end up inside the shadow. `M` has to be `N - 1`.
+pub fn project<const N: usize, const M: usize>(v: &Point<N>, eye: Element) -> Point<M> {
+    debug_assert!(M + 1 == N, "projecting from {} dimensions into {}", N, M);
+    // (the widest part of the sphere's shadow comes from w = 1 / eye)
+    let s = (eye * eye - 1.0).sqrt() / (eye - v.coord[N - 1]);
+    Point::new(std::array::from_fn(|i| v.coord[i] * s))
+}
+
+// A polytope of any of the dimensions we know how to flatten, so a scene can hold a mix of them.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Hyper {
+    Four(Polytope<4>),
+    Five(Polytope<5>),
+    Six(Polytope<6>),
+    Seven(Polytope<7>),
+    Eight(Polytope<8>),
+}
+
+// one line per dimension: its number, the one below and which `Hyper` it goes in
+macro_rules! dimensions {
+    ($($n:literal => $lower:literal, $variant:ident;)*) => {
+        $(
+            impl Shadow for Point<$n> {
+                fn shadow(&self, eye: Element) -> Vec3 {
+                    project::<$n, $lower>(self, eye).shadow(eye)
+                }
+            }
+
+            impl From<Polytope<$n>> for Hyper {
+                fn from(polytope: Polytope<$n>) -> Self {
+                    Hyper::$variant(polytope)
+                }
+            }
+        )*
+
+        impl Hyper {
+            // `None` if we don't go that high (or that low, 3D things are plain meshes)
+            pub fn mk_family(family: Family, dimensions: usize) -> Option<Hyper> {
+                match dimensions {
+                    $($n => Some(Polytope::<$n>::mk_family(family).into()),)*
+                    _ => None,
+                }
+            }
+
+            pub fn validate(&self) -> Result<(), PolytopeError> {
+                match self {
+                    $(Hyper::$variant(polytope) => polytope.validate(),)*
+                }
+            }
+
+            pub fn turn(&mut self) {
+                match self {
+                    $(Hyper::$variant(polytope) => polytope.turn(),)*
+                }
+            }
+
+            pub fn shadow(&self) -> Vec<Vec3> {
+                match self {
+                    $(Hyper::$variant(polytope) => polytope.shadow(),)*
+                }
+            }
+
+            pub fn to_mesh(&self) -> Mesh {
+                match self {
+                    $(Hyper::$variant(polytope) => polytope.to_mesh(),)*
+                }
+            }
+        }
+    };
+}
+
+dimensions! {
+    4 => 3, Four;
+    5 => 4, Five;
+    6 => 5, Six;
+    7 => 6, Seven;
+    8 => 7, Eight;
+}
+
+impl Hyper {
+    // A regular 4D polytope by its snake_case name (like in scene files) or a family and how many
+    // dimensions, like `cube_5` or `simplex_6`.
+    pub fn from_name(name: &str) -> Option<Hyper> {
+        if let Some(regular) = Regular::from_name(name) {
+            return Some(Polytope::mk_regular(regular).into());
+        }
+        let (family, dimensions) = name.rsplit_once('_')?;
+        let family = match family {
+            "cube" => Family::Cube,
+            "simplex" => Family::Simplex,
+            "orthoplex" => Family::Orthoplex,
+            _ => return None,
+        };
+        Hyper::mk_family(family, dimensions.parse().ok()?)
+    }
+}
+
+// Step the polytopes and redraw their shadows into the meshes they were given. Meshes that don't
+// exist are skipped and ones that don't match (from a scene file, say) are swapped for the shadow.
+pub fn tic(polytopes: &mut [(Hyper, usize)], meshes: &mut [Mesh]) {
+    for (polytope, mesh) in polytopes.iter_mut() {
+        polytope.turn();
+        let Some(mesh) = meshes.get_mut(*mesh) else {
+            continue;
+        };
+        let shadow = polytope.shadow();
+        if shadow.len() == mesh.vertices.len() {
+            mesh.vertices = shadow;
+        } else {
+            *mesh = polytope.to_mesh();
+        }
+    }
+}
+
+// `v` with every combination of signs on its non-zero coordinates.
+fn signs(v: [Element; 4]) -> Vec<Vec4> {
+    let mut out = vec![Vec4::new(v)];
+    for (i, &x) in v.iter().enumerate() {
+        if x == 0.0 {
+            continue;
+        }
+        out = out.into_iter()
+            .flat_map(|p| {
+                let mut flipped = p;
+                flipped.coord[i] = -flipped.coord[i];
+                [p, flipped]
+            })
+            .collect();
+    }
+
+    out
+}
+
+// ±1 along each axis
+fn axes() -> Vec<Vec4> {
+    (0..4)
+        .flat_map(|i| {
+            let mut v = [0.0; 4];
+            v[i] = 1.0;
+            signs(v)
+        })
+        .collect()
+}
+
+// (±1/2, ±1/2, ±1/2, ±1/2), ±1 along each axis and the even permutations of
+// (±φ, ±1, ±1/φ, 0) / 2
+fn six_hundred_cell() -> Vec<Vec4> {
+    let mut out = signs([0.5, 0.5, 0.5, 0.5]);
+    out.extend(axes());
+
+    let base = [PHI / 2.0, 0.5, 0.5 / PHI, 0.0];
+    for order in permutations4() {
+        if !even(&order) {
+            continue;
+        }
+        out.extend(signs(order.map(|i| base[i])));
+    }
+
+    out
+}
+
+fn permutations4() -> Vec<[usize; 4]> {
+    let mut out = vec![];
+    for a in 0..4 {
+        for b in (0..4).filter(|&b| b != a) {
+            for c in (0..4).filter(|&c| c != a && c != b) {
+                out.push([a, b, c, 6 - a - b - c]);
+            }
+        }
+    }
+
+    out
+}
+
+fn even(order: &[usize; 4]) -> bool {
+    let inversions = (0..4)
+        .flat_map(|i| (i + 1..4).map(move |j| (i, j)))
+        .filter(|&(i, j)| order[i] > order[j])
+        .count();
+
+    inversions % 2 == 0
+}
+
+// The dual of the 600-cell: a vertex in the middle of each of its 600 tetrahedra, joined up where
+// two tetrahedra share a triangle (which is also where they're closest).
+fn hundred_twenty_cell() -> Polytope<4> {
+    let outer = Polytope::mk_regular(Regular::SixHundredCell);
+    let mut neighbours = vec![BTreeSet::<usize>::new(); outer.vertices.len()];
+    for &(a, b) in outer.lines.iter() {
+        neighbours[a].insert(b);
+        neighbours[b].insert(a);
+    }
+
+    // each tetrahedron is four mutual neighbours, found once in increasing order
+    let mut vertices = vec![];
+    for a in 0..outer.vertices.len() {
+        for &b in neighbours[a].iter().filter(|&&b| b > a) {
+            for &c in neighbours[a].intersection(&neighbours[b]).filter(|&&c| c > b) {
+                for &d in neighbours[c].iter().filter(|&&d| d > c) {
+                    if neighbours[a].contains(&d) && neighbours[b].contains(&d) {
+                        let sum = outer.vertices[a] + outer.vertices[b] + outer.vertices[c] + outer.vertices[d];
+                        vertices.push(sum.scale(0.25));
+                    }
+                }
+            }
+        }
+    }
+    let lines = shortest_lines(&vertices);
+
+    Polytope::new(vertices, lines)
+}
+
+// Deserialized polytopes go through `Polytope::validate`.
+#[cfg(feature = "serde")]
+mod de {
+    use serde::Deserialize;
+
+    use super::{Polytope, PolytopeError, Turn};
+    use crate::mesh::Line;
+    use crate::point::{Element, Point};
+
+    #[derive(Deserialize)]
+    pub struct PolytopeParts<const N: usize> {
+        vertices: Vec<Point<N>>,
+        lines: Vec<Line>,
+        turns: Vec<Turn>,
+        eye: Element,
+    }
+
+    impl<const N: usize> TryFrom<PolytopeParts<N>> for Polytope<N> {
+        type Error = PolytopeError;
+
+        fn try_from(parts: PolytopeParts<N>) -> Result<Self, Self::Error> {
+            let polytope = Polytope {
+                vertices: parts.vertices,
+                lines: parts.lines,
+                turns: parts.turns,
+                eye: parts.eye,
+            };
+            polytope.validate()?;
+
+            Ok(polytope)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regular_counts() {
+        let counts = |regular| {
+            let polytope = Polytope::mk_regular(regular);
+            assert_eq!(polytope.validate(), Ok(()));
+            (polytope.vertices.len(), polytope.lines.len())
+        };
+        assert_eq!(counts(Regular::Tesseract), (16, 32));
+        assert_eq!(counts(Regular::SixteenCell), (8, 24));
+        assert_eq!(counts(Regular::TwentyFourCell), (24, 96));
+        assert_eq!(counts(Regular::SixHundredCell), (120, 720));
+        assert_eq!(counts(Regular::HundredTwentyCell), (600, 1200));
+    }
+
+    fn family_counts<const N: usize>() {
+        let counts = [
+            (Family::Cube, 1 << N, N << (N - 1)),
+            (Family::Simplex, N + 1, N * (N + 1) / 2),
+            (Family::Orthoplex, 2 * N, 2 * N * (N - 1)),
+        ];
+        for (family, vertices, lines) in counts {
+            let polytope = Polytope::<N>::mk_family(family);
+            assert_eq!(polytope.validate(), Ok(()));
+            assert_eq!((polytope.vertices.len(), polytope.lines.len()), (vertices, lines), "{:?} in {}D", family, N);
+
+            // regular: every corner on the unit hypersphere and every edge the same length
+            assert!(polytope.vertices.iter().all(|v| (v.magnitude() - 1.0).abs() < 1e-9));
+            let edge = |&(a, b): &Line| (polytope.vertices[a] - polytope.vertices[b]).magnitude();
+            let first = edge(&polytope.lines[0]);
+            assert!(polytope.lines.iter().all(|line| (edge(line) - first).abs() < 1e-9));
+
+            let mesh = Hyper::mk_family(family, N).unwrap().to_mesh();
+            assert_eq!((mesh.vertices.len(), mesh.lines.len()), (vertices, lines));
+        }
+    }
+
+    #[test]
+    fn families() {
+        family_counts::<4>();
+        family_counts::<5>();
+        family_counts::<6>();
+        family_counts::<7>();
+        family_counts::<8>();
+
+        assert!(Hyper::mk_family(Family::Cube, 3).is_none());
+        assert!(Hyper::mk_family(Family::Cube, 9).is_none());
+        assert_eq!(Hyper::from_name("simplex_6"), Hyper::mk_family(Family::Simplex, 6));
+        assert_eq!(Hyper::from_name("tesseract"), Some(Polytope::mk_regular(Regular::Tesseract).into()));
+        assert_eq!(Hyper::from_name("orthoplex_9"), None);
+        assert_eq!(Hyper::from_name("prism_5"), None);
+    }
+
+    #[test]
+    fn bad_polytopes() {
+        let good = Polytope::<4>::mk_cube();
+
+        let mut bad = good.clone();
+        bad.lines.push((0, 16));
+        assert!(bad.validate().is_err());
+
+        let mut bad = good.clone();
+        bad.turns.push(Turn { axes: (1, 4), angle: 0.0, spin: 0.0 });
+        assert!(bad.validate().is_err());
+
+        let mut bad = good.clone();
+        bad.eye = 1.0;
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn tic_skips_missing_meshes() {
+        let hyper = Hyper::from(Polytope::<4>::mk_cube());
+        let mut meshes = vec![Mesh::mk_cube()];
+        let mut polytopes = vec![(hyper.clone(), 0), (hyper, 7)];
+        tic(&mut polytopes, &mut meshes);
+        // the cube didn't match so it became the shadow
+        assert_eq!(meshes[0].vertices.len(), 16);
+        assert_eq!(meshes[0].lines.len(), 32);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialized_polytopes_are_checked() {
+        let good = serde_json::to_string(&Hyper::from(Polytope::<4>::mk_cube())).unwrap();
+        assert!(serde_json::from_str::<Hyper>(&good).is_ok());
+
+        let line = r#"{"four": {"vertices": [[1, 0, 0, 0], [0, 1, 0, 0]], "lines": [[0, 2]], "turns": [], "eye": 2}}"#;
+        assert!(serde_json::from_str::<Hyper>(line).is_err());
+        let eye = r#"{"four": {"vertices": [[1, 0, 0, 0], [0, 1, 0, 0]], "lines": [[0, 1]], "turns": [], "eye": 0.5}}"#;
+        assert!(serde_json::from_str::<Hyper>(eye).is_err());
+        let turn = r#"{"four": {"vertices": [[1, 0, 0, 0], [0, 1, 0, 0]], "lines": [[0, 1]], "turns": [{"axes": [0, 9], "angle": 0, "spin": 0}], "eye": 2}}"#;
+        assert!(serde_json::from_str::<Hyper>(turn).is_err());
+        // at the eye, and past the unit hypersphere but short of the eye
+        let far = r#"{"four": {"vertices": [[0, 0, 0, 2], [0, 1, 0, 0]], "lines": [[0, 1]], "turns": [], "eye": 2}}"#;
+        assert!(serde_json::from_str::<Hyper>(far).is_err());
+        let outside = r#"{"five": {"vertices": [[0, 0, 0, 0, 1.5], [0, 1, 0, 0, 0]], "lines": [[0, 1]], "turns": [], "eye": 2}}"#;
+        assert!(serde_json::from_str::<Hyper>(outside).is_err());
+    }
+}
@@ -11,6 +11,8 @@ extern "C" {
     fn dbg_break();
 }
 
+// not every simulation cares about the window or document (yet)
+#[allow(dead_code)]
 pub struct SystemContext {
     pub window: web_sys::Window,
     pub document: web_sys::Document,
@@ -46,7 +48,7 @@ impl<Obj: Simulation + 'static> ObjInstance<Obj> {
                 canvas_ctx,
             },
 
-            obj: obj,
+            obj,
 
             frames: 0,
         }
@@ -0,0 +1,289 @@
+// L-systems drawn by a 3D turtle. Starting from the `axiom`, every symbol with a rule is swapped for
+// its replacement at once, over and over, and the string that's left is read as turtle commands.
+// The commands are the usual ones from "The Algorithmic Beauty of Plants":
+//
+// - `F` and `G` step forward drawing a line, `f` steps without drawing
+// - `+` and `-` turn left and right (yaw), `&` and `^` pitch down and up, `\` and `/` roll left
+//   and right, all by `angle`, and `|` turns right around
+// - `[` remembers where the turtle is and `]` goes back there
+//
+// Anything else (`A`, `X`, `!`, `{` and so on) only matters to the rules. The turtle starts at the
+// origin heading up the y axis. The mesh is squeezed into the unit sphere like the polyhedra and
+// places the turtle visits more than once are a single vertex.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::error::message_error;
+use crate::mesh::{Line, Mesh};
+use crate::point::{Element, Vec3};
+
+// Longest string we're willing to draw; they grow exponentially.
+const MAX_LENGTH: usize = 1 << 20;
+// Positions closer than this (in steps) are the same vertex.
+const WELD: Element = 1e-6;
+
+message_error!(LSystemError, "L-system");
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LSystem {
+    pub axiom: String,
+    pub rules: BTreeMap<char, String>,
+    // how far every turn goes, radians
+    pub angle: Element,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Preset {
+    // a weed, flat
+    Plant,
+    // a bush with leaves, from ABOP figure 1.25
+    Bush,
+    // three branches from every branch, each rolled a third of the way round from the last
+    Tree,
+    // a cube's worth of points visited in one go, from ABOP figure 1.19
+    Hilbert,
+    // the Heighway dragon, flat
+    Dragon,
+    // the terdragon, flat
+    Terdragon,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 6] = [
+        Preset::Plant,
+        Preset::Bush,
+        Preset::Tree,
+        Preset::Hilbert,
+        Preset::Dragon,
+        Preset::Terdragon,
+    ];
+
+    // by its snake_case name, like in scene files
+    pub fn from_name(name: &str) -> Option<Preset> {
+        Preset::ALL.into_iter().find(|preset| format!("{:?}", preset).to_lowercase() == name)
+    }
+
+    pub fn lsystem(self) -> LSystem {
+        match self {
+            Preset::Plant => LSystem::new("X", &[('X', "F+[[X]-X]-F[-FX]+X"), ('F', "FF")], 25.0),
+            Preset::Bush => LSystem::new("A", &[
+                ('A', "[&FL!A]/////'[&FL!A]///////'[&FL!A]"),
+                ('F', "S/////F"),
+                ('S', "FL"),
+                ('L', "['''^^{-G+G+G-|-G+G+G}]"),
+            ], 22.5),
+            Preset::Tree => LSystem::new("FA", &[('A', "F[&A]////[&A]////[&A]")], 30.0),
+            Preset::Hilbert => LSystem::new("A", &[
+                ('A', "B-F+CFC+F-D&F^D-F+&&CFC+F+B//"),
+                ('B', "A&F^CFB^F^D^^-F-D^|F^B|FC^F^A//"),
+                ('C', "|D^|F^B-F+C^F^A&&FA&F^C+F+B^F^D//"),
+                ('D', "|CFB-F+B|FA&F^A&&FB-F+B|FC//"),
+            ], 90.0),
+            Preset::Dragon => LSystem::new("FX", &[('X', "X+YF+"), ('Y', "-FX-Y")], 90.0),
+            Preset::Terdragon => LSystem::new("F", &[('F', "F+F-F")], 120.0),
+        }
+    }
+
+    // Roughly where the mesh gets too busy to see (or draw every frame).
+    pub fn max_iterations(self) -> usize {
+        match self {
+            Preset::Plant => 5,
+            Preset::Bush => 5,
+            Preset::Tree => 6,
+            Preset::Hilbert => 3,
+            Preset::Dragon => 12,
+            Preset::Terdragon => 7,
+        }
+    }
+}
+
+// Where the turtle is and which way it's facing.
+#[derive(Clone, Copy, Debug)]
+struct Turtle {
+    position: Vec3,
+    heading: Vec3,
+    left: Vec3,
+    up: Vec3,
+}
+
+// `a` and `b` turned by `angle` in the plane they span, from `a` towards `b`.
+fn turn(a: Vec3, b: Vec3, angle: Element) -> (Vec3, Vec3) {
+    let (sin, cos) = angle.sin_cos();
+    (a.scale(cos) + b.scale(sin), b.scale(cos) - a.scale(sin))
+}
+
+impl LSystem {
+    // `angle` in degrees, because that's how every book lists them.
+    pub fn new(axiom: &str, rules: &[(char, &str)], angle: Element) -> Self {
+        Self {
+            axiom: axiom.to_string(),
+            rules: rules.iter().map(|&(symbol, replacement)| (symbol, replacement.to_string())).collect(),
+            angle: angle.to_radians(),
+        }
+    }
+
+    // The string after `iterations` rounds of rewriting.
+    pub fn expand(&self, iterations: usize) -> Result<String, LSystemError> {
+        let mut current = self.axiom.clone();
+        for _ in 0..iterations {
+            let mut next = String::with_capacity(current.len() * 2);
+            for symbol in current.chars() {
+                match self.rules.get(&symbol) {
+                    Some(replacement) => next.push_str(replacement),
+                    None => next.push(symbol),
+                }
+                if next.len() > MAX_LENGTH {
+                    return error(format!("the string grows past {} symbols", MAX_LENGTH));
+                }
+            }
+            current = next;
+        }
+
+        Ok(current)
+    }
+
+    pub fn to_mesh(&self, iterations: usize) -> Result<Mesh, LSystemError> {
+        let commands = self.expand(iterations)?;
+
+        let mut turtle = Turtle {
+            position: Vec3::zeroes(),
+            heading: Vec3::new([0.0, 1.0, 0.0]),
+            left: Vec3::new([-1.0, 0.0, 0.0]),
+            up: Vec3::new([0.0, 0.0, 1.0]),
+        };
+        let mut stack = vec![];
+
+        let mut index = HashMap::<[i64; 3], usize>::new();
+        let mut vertices = vec![];
+        let mut vertex = |p: Vec3| -> usize {
+            *index.entry(p.coord.map(|x| (x / WELD).round() as i64)).or_insert_with(|| {
+                vertices.push(p);
+                vertices.len() - 1
+            })
+        };
+        let mut lines = Vec::<Line>::new();
+
+        let angle = self.angle;
+        for command in commands.chars() {
+            match command {
+                'F' | 'G' => {
+                    let from = vertex(turtle.position);
+                    turtle.position += turtle.heading;
+                    let to = vertex(turtle.position);
+                    if from != to {
+                        lines.push((from, to));
+                    }
+                },
+                'f' => turtle.position += turtle.heading,
+                '+' => (turtle.heading, turtle.left) = turn(turtle.heading, turtle.left, angle),
+                '-' => (turtle.heading, turtle.left) = turn(turtle.heading, turtle.left, -angle),
+                '&' => (turtle.heading, turtle.up) = turn(turtle.heading, turtle.up, -angle),
+                '^' => (turtle.heading, turtle.up) = turn(turtle.heading, turtle.up, angle),
+                '\\' => (turtle.left, turtle.up) = turn(turtle.left, turtle.up, angle),
+                '/' => (turtle.left, turtle.up) = turn(turtle.left, turtle.up, -angle),
+                '|' => (turtle.heading, turtle.left) = (turtle.heading.scale(-1.0), turtle.left.scale(-1.0)),
+                '[' => stack.push(turtle),
+                ']' => match stack.pop() {
+                    Some(saved) => turtle = saved,
+                    None => return error("more ] than [".to_string()),
+                },
+                _ => {},
+            }
+        }
+
+        if lines.is_empty() {
+            return error("the turtle never draws anything".to_string());
+        }
+
+        // into the unit sphere, centred on the middle of the bounding box
+        let (mut min, mut max) = (vertices[0], vertices[0]);
+        for v in vertices.iter() {
+            min = Vec3::new(std::array::from_fn(|i| min.coord[i].min(v.coord[i])));
+            max = Vec3::new(std::array::from_fn(|i| max.coord[i].max(v.coord[i])));
+        }
+        let centre = (min + max).scale(0.5);
+        let radius = vertices.iter().map(|v| (*v - centre).magnitude()).fold(0.0, Element::max);
+        let vertices = vertices.iter().map(|v| (*v - centre).scale(1.0 / radius)).collect();
+
+        Mesh::new(Vec3::zeroes(), vertices, lines)
+            .or_else(|e| error(format!("made a bad mesh: {}", e)))
+    }
+}
+
+impl Mesh {
+    pub fn mk_lsystem(preset: Preset, iterations: usize) -> Result<Mesh, LSystemError> {
+        preset.lsystem().to_mesh(iterations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(mesh: &Mesh) -> (usize, usize) {
+        (mesh.vertices.len(), mesh.lines.len())
+    }
+
+    #[test]
+    fn rewriting() {
+        // Lindenmayer's algae, whose lengths go up the Fibonacci numbers
+        let algae = LSystem::new("A", &[('A', "AB"), ('B', "A")], 0.0);
+        assert_eq!(algae.expand(0).unwrap(), "A");
+        assert_eq!(algae.expand(4).unwrap(), "ABAABABA");
+        assert_eq!(algae.expand(10).unwrap().len(), 144);
+
+        let dragon = Preset::Dragon.lsystem();
+        assert_eq!(dragon.expand(2).unwrap(), "FX+YF++-FX-YF+");
+    }
+
+    #[test]
+    fn turtle() {
+        let draw = |commands: &str| LSystem::new(commands, &[], 90.0).to_mesh(0).map(|mesh| counts(&mesh));
+
+        // coming back round to the start welds the corners
+        assert_eq!(draw("F+F+F+F"), Ok((4, 4)));
+        assert_eq!(draw("F&F&F&F"), Ok((4, 4)));
+        // stepping without drawing, turning round and going back over a line
+        assert_eq!(draw("FfF"), Ok((4, 2)));
+        assert_eq!(draw("F|F"), Ok((2, 1)));
+        // branches start from where they were remembered
+        assert_eq!(draw("F[+F][-F]F"), Ok((5, 4)));
+        // rolling changes which way pitching goes, not where the turtle is
+        assert_eq!(draw("F\\\\F"), Ok((3, 2)));
+        assert_eq!(draw("F\\&F//&F"), Ok((4, 3)));
+
+        assert_eq!(draw("F]").unwrap_err().message, "more ] than [");
+        assert_eq!(draw("f+f").unwrap_err().message, "the turtle never draws anything");
+    }
+
+    #[test]
+    fn presets() {
+        // the Hilbert curve visits every point of a 2^n cube once, the dragons never retrace a line
+        for n in 1..=2 {
+            let points = 8usize.pow(n as u32);
+            assert_eq!(counts(&Mesh::mk_lsystem(Preset::Hilbert, n).unwrap()), (points, points - 1));
+        }
+        assert_eq!(Mesh::mk_lsystem(Preset::Dragon, 8).unwrap().lines.len(), 1 << 8);
+        assert_eq!(Mesh::mk_lsystem(Preset::Terdragon, 4).unwrap().lines.len(), 81);
+
+        for preset in Preset::ALL {
+            let mesh = Mesh::mk_lsystem(preset, preset.max_iterations()).unwrap();
+            let furthest = mesh.vertices.iter().map(Vec3::magnitude).fold(0.0, Element::max);
+            assert!((furthest - 1.0).abs() < 1e-9);
+            assert_eq!(mesh.adjacency().components().len(), 1);
+            assert_eq!(Preset::from_name(&format!("{:?}", preset).to_lowercase()), Some(preset));
+        }
+        assert_eq!(Preset::from_name("fern"), None);
+    }
+
+    #[test]
+    fn too_long() {
+        let doubling = LSystem::new("F", &[('F', "FF")], 90.0);
+        assert_eq!(doubling.expand(20).unwrap().len(), MAX_LENGTH);
+        assert_eq!(doubling.expand(21).unwrap_err().message, format!("the string grows past {} symbols", MAX_LENGTH));
+        assert!(doubling.to_mesh(21).is_err());
+    }
+}
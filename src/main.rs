@@ -4,12 +4,47 @@ mod point;
 use crate::point::Vec3;
 mod mesh;
 use crate::mesh::Mesh;
+#[cfg(feature = "gltf")]
+mod gltf;
+mod conway;
+mod error;
+mod fractal;
+mod graph;
+mod hidden;
+mod hull;
+mod hyper;
+mod lsystem;
+use crate::fractal::Fractal;
+use crate::lsystem::Preset;
+use crate::parametric::Surface;
+use crate::subdivide::Scheme;
+use crate::polyhedra::{Archimedean, Platonic};
+use crate::hyper::Hyper;
+mod morph;
+use crate::morph::{Easing, Morph};
+mod obj;
+mod parametric;
+mod ply;
+mod polyhedra;
+mod solid;
+mod soup;
+mod stl;
+mod subdivide;
+mod text;
+mod topology;
 mod camera;
+use crate::camera::Camera;
 
 mod render;
-use crate::render::Scene;
+use crate::render::{HiddenLines, Scene, Style};
+#[cfg(feature = "serde")]
+mod scene_file;
+#[cfg(feature = "serde")]
+use crate::scene_file::SceneFile;
 
 mod instance;
+#[cfg(test)]
+mod bench;
 use crate::instance::ObjInstance;
 
 // This is recommended for debug builds. Panics will be logged to the console.
@@ -27,11 +62,9 @@ pub fn main() -> Result<(), JsValue> {
 
     let window = web_sys::window()
         .ok_or(JsValue::from_str("no global window exists"))?;
-    /*
     let href = window.location().href()?;
     let url = url::Url::parse(&href)
         .or(Err(JsValue::from_str("unable to parse url")))?;
-    */
 
     let document = window
         .document()
@@ -56,13 +89,243 @@ pub fn main() -> Result<(), JsValue> {
         .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
 
     log!("smoke weed every day");
-    let origin = Vec3::new([0.0, 0.0, 10.0]);
-    let _poly = Mesh::mk_tetra_cube();
-    let poly = Mesh::mk_cube();
-    let render_state = Scene::new(origin, poly);
-    ObjInstance::<Scene>::new(
-            window, document, canvas, canvas_ctx, render_state)
+    let start = move |scene: Scene| ObjInstance::<Scene>::new(
+            window, document, canvas, canvas_ctx, scene)
         .start_fire();
+    // `&export=obj` also writes the shape out to the console (`json` or `ron` for scene files), to
+    // open in Blender or turn one format into another
+    let export = url.query_pairs().find(|(key, _)| key == "export").map(|(_, format)| format.into_owned());
+
+    // `?scene=pair` draws one of the scenes in scenes/ (or `?scene={...}` one written out in full,
+    // JSON or RON) instead of anything the rest of the query asks for
+    if let Some((_, scene)) = url.query_pairs().find(|(key, _)| key == "scene") {
+        start(load_scene(&scene, export.as_deref()).map_err(|e| JsValue::from_str(&e))?);
+        return Ok(());
+    }
+    // `?model=teapot.obj` fetches an OBJ, STL or PLY file (or glTF/GLB with that feature) and
+    // spins it. It has to wait for the download so we start drawing once that's in.
+    if let Some((_, model)) = url.query_pairs().find(|(key, _)| key == "model") {
+        let model = model.into_owned();
+        wasm_bindgen_futures::spawn_local(async move {
+            match load_model(&model, export.as_deref()).await {
+                Ok(scene) => start(scene),
+                Err(e) => {
+                    log!("can't draw {}: {}", model, e);
+                },
+            }
+        });
+        return Ok(());
+    }
 
+    let origin = Vec3::new([0.0, 0.0, 10.0]);
+    // `?conway=tkD` swaps the cube for any polyhedron Conway notation can describe
+    let poly = match url.query_pairs().find(|(key, _)| key == "conway") {
+        Some((_, notation)) => Mesh::from_conway(&notation)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?,
+        None => match url.query_pairs().find(|(key, _)| key == "text") {
+            // `?text=mirage` spins a word instead
+            Some((_, text)) => Mesh::mk_text(&text, 0.25)
+                .map_err(|e| JsValue::from_str(&e.to_string()))?,
+            None => match url.query_pairs().find(|(key, _)| key == "lsystem") {
+                // `?lsystem=tree` grows one of the L-system presets as far as it goes
+                Some((_, name)) => {
+                    let preset = Preset::from_name(&name)
+                        .ok_or(JsValue::from_str(&format!("no L-system called \"{}\"", name)))?;
+                    Mesh::mk_lsystem(preset, preset.max_iterations())
+                        .map_err(|e| JsValue::from_str(&e.to_string()))?
+                },
+                None => match url.query_pairs().find(|(key, _)| key == "polyhedron") {
+                    // `?polyhedron=snub_cube` for any of the Platonic or Archimedean solids
+                    Some((_, name)) => match (Platonic::from_name(&name), Archimedean::from_name(&name)) {
+                        (Some(solid), _) => Mesh::mk_platonic(solid),
+                        (_, Some(solid)) => Mesh::mk_archimedean(solid),
+                        _ => return Err(JsValue::from_str(&format!("no polyhedron called \"{}\"", name))),
+                    },
+                    None => match url.query_pairs().find(|(key, _)| key == "fractal") {
+                        // `?fractal=menger_sponge` builds one as deep as it goes
+                        Some((_, name)) => {
+                            let fractal = Fractal::from_name(&name)
+                                .ok_or(JsValue::from_str(&format!("no fractal called \"{}\"", name)))?;
+                            Mesh::mk_fractal(fractal, fractal.max_depth())
+                        },
+                        None => match url.query_pairs().find(|(key, _)| key == "surface") {
+                            // `?surface=torus` for a parametric surface
+                            Some((_, name)) => {
+                                let surface = Surface::from_name(&name)
+                                    .ok_or(JsValue::from_str(&format!("no surface called \"{}\"", name)))?;
+                                Mesh::mk_surface(surface)
+                                    .map_err(|e| JsValue::from_str(&e.to_string()))?
+                            },
+                            None => Mesh::mk_cube(),
+                        },
+                    },
+                },
+            },
+        },
+    };
+    // `&subdivide=catmull_clark` (or `loop`) smooths a shape with faces, `loop_3` three times over
+    let poly = match url.query_pairs().find(|(key, _)| key == "subdivide") {
+        Some((_, name)) => {
+            let (scheme, levels) = name.rsplit_once('_')
+                .and_then(|(scheme, levels)| Some((scheme, levels.parse::<usize>().ok()?)))
+                .unwrap_or((&name, 1));
+            let scheme = Scheme::from_name(scheme)
+                .filter(|_| (1..=subdivide::MAX_LEVELS).contains(&levels))
+                .ok_or(JsValue::from_str(&format!("no subdivision called \"{}\"", name)))?;
+            poly.subdivide(scheme, levels)
+                .map_err(|e| JsValue::from_str(&e.to_string()))?
+        },
+        None => poly,
+    };
+    // `&hull` shrink-wraps it in its convex hull, which suits trees and fractals
+    let poly = if url.query_pairs().any(|(key, _)| key == "hull") {
+        Mesh::convex_hull(&poly.vertices).map_err(|e| JsValue::from_str(&e.to_string()))?
+    } else {
+        poly
+    };
+    export_mesh(&poly, export.as_deref()).map_err(|e| JsValue::from_str(&e))?;
+    // `?polytope=tesseract` (or `cube_5` etc.) swaps it for the shadow of a turning polytope
+    let polytope = match url.query_pairs().find(|(key, _)| key == "polytope") {
+        Some((_, name)) => Some(Hyper::from_name(&name)
+            .ok_or(JsValue::from_str(&format!("no polytope called \"{}\"", name)))?),
+        None => None,
+    };
+    // `?orbits=3` sends that many little cubes around it, each with a moon of its own
+    let orbits = match url.query_pairs().find(|(key, _)| key == "orbits") {
+        Some((_, count)) => Some(count.parse::<usize>().ok()
+            .filter(|count| (1..=12).contains(count))
+            .ok_or(JsValue::from_str(&format!("orbits wants a count from 1 to 12, got \"{}\"", count)))?),
+        None => None,
+    };
+    // `?morph=tetra_cube` (or Conway notation) melts it into something else and back, over and over
+    let morph = match url.query_pairs().find(|(key, _)| key == "morph") {
+        Some((_, target)) => {
+            let target = match target.as_ref() {
+                "tetra_cube" => Mesh::mk_tetra_cube(),
+                notation => Mesh::from_conway(notation)
+                    .map_err(|e| JsValue::from_str(&e.to_string()))?,
+            };
+            let mut morph = Morph::new(&poly, &target, 240)
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            morph.bounce = true;
+            // `&easing=linear` (or `ease_in` or `ease_out`) for a different pace than `ease_in_out`
+            if let Some((_, name)) = url.query_pairs().find(|(key, _)| key == "easing") {
+                morph.easing = Easing::from_name(&name)
+                    .ok_or(JsValue::from_str(&format!("no easing called \"{}\"", name)))?;
+            }
+            Some(morph)
+        }
+        None => None,
+    };
+    // `?hidden=dash` (or `skip` or `fade`) for the bits of lines behind faces
+    let style = match url.query_pairs().find(|(key, _)| key == "hidden") {
+        Some((_, name)) => Style {
+            hidden_lines: HiddenLines::from_name(&name)
+                .ok_or(JsValue::from_str(&format!("no hidden line style called \"{}\"", name)))?,
+            ..Style::default()
+        },
+        None => Style::default(),
+    };
+    let render_state = match polytope {
+        Some(polytope) => {
+            let mut scene = Scene::from_parts(Camera::new(origin), vec![], style);
+            scene.add_polytope(polytope)
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            scene
+        }
+        None => {
+            let mut scene = Scene::from_parts(Camera::new(origin), vec![poly], style);
+            if let Some(morph) = morph {
+                scene.add_morph(0, morph)
+                    .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            }
+            if let Some(count) = orbits {
+                let satellite = scene.add_mesh(Mesh::mk_cube());
+                scene.add_node(graph::mk_orbits(0, satellite, count));
+            }
+            scene
+        }
+    };
+    start(render_state);
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+fn load_scene(scene: &str, export: Option<&str>) -> Result<Scene, String> {
+    let file = match SceneFile::builtin(scene) {
+        Some(file) => file,
+        None if scene.trim_start().starts_with('{') => SceneFile::from_json(scene),
+        None if scene.trim_start().starts_with('(') => SceneFile::from_ron(scene),
+        None => return Err(format!("no scene called \"{}\"", scene)),
+    }.map_err(|e| e.to_string())?;
+    match export {
+        Some("json") => {
+            log!("{}", file.to_json());
+        },
+        Some("ron") => {
+            log!("{}", file.to_ron());
+        },
+        Some(format) => return Err(format!("can't write a scene file as {}", format)),
+        None => {},
+    }
+    file.into_scene().map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "serde"))]
+fn load_scene(_scene: &str, _export: Option<&str>) -> Result<Scene, String> {
+    Err("scene files need the serde feature".to_string())
+}
+
+// Which of the importers to use goes by the file's extension.
+async fn load_model(url: &str, export: Option<&str>) -> Result<Scene, String> {
+    let response = gloo::net::http::Request::get(url)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.ok() {
+        return Err(format!("{} {}", response.status(), response.status_text()));
+    }
+    let bytes = response.binary().await.map_err(|e| e.to_string())?;
+
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_ascii_lowercase();
+    let extension = path.rsplit_once('.').map(|(_, extension)| extension).unwrap_or("");
+    let mesh = match extension {
+        "obj" => Mesh::from_obj(&String::from_utf8_lossy(&bytes)).map_err(|e| e.to_string())?,
+        "stl" => Mesh::from_stl(&bytes, soup::DEFAULT_CREASE_ANGLE).map_err(|e| e.to_string())?,
+        "ply" => Mesh::from_ply(&bytes, soup::DEFAULT_CREASE_ANGLE).map_err(|e| e.to_string())?,
+        // only what's inside the file itself, we can't go back for more while loading it
+        #[cfg(feature = "gltf")]
+        "gltf" | "glb" => return Scene::from_gltf(&bytes, |_| None).map_err(|e| e.to_string()),
+        _ => return Err(format!("don't know what to do with a .{} file", extension)),
+    };
+
+    // files off the internet are often not quite what they claim to be
+    let adjacency = mesh.adjacency();
+    let stray = (0..mesh.vertices.len()).filter(|&v| adjacency.degree(v) == 0).count();
+    log!(
+        "{}: {} vertices ({} on no line), {} lines, {} faces, {} pieces, Euler characteristic {}{}",
+        url, mesh.vertices.len(), stray, mesh.lines.len(), mesh.faces.len(), adjacency.components().len(),
+        mesh.euler_characteristic(),
+        match (mesh.faces.is_empty(), mesh.is_closed(), mesh.is_manifold()) {
+            (true, _, _) => "",
+            (false, true, _) => ", closed",
+            (false, false, true) => ", manifold with a boundary",
+            (false, false, false) => ", not manifold",
+        },
+    );
+    export_mesh(&mesh, export)?;
+
+    Ok(Scene::new(Vec3::new([0.0, 0.0, 10.0]), mesh))
+}
+
+fn export_mesh(mesh: &Mesh, export: Option<&str>) -> Result<(), String> {
+    match export {
+        Some("obj") => {
+            log!("{}", mesh.to_obj(false));
+        },
+        Some(format) => return Err(format!("can't write a mesh as {}", format)),
+        None => {},
+    }
     Ok(())
 }
@@ -1,28 +1,316 @@
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use std::fmt;
+
 use crate::point::Vec3;
+use crate::point::Mat4;
+use crate::point::Quaternion;
+use crate::topology::{face_area_vector, face_normal, HalfEdges};
 
+// Lines are undirected. `Mesh::new` stores every one with its smaller vertex first and drops
+// repeats, so (0, 1) and (1, 0) are the same line and only get stroked once.
 pub type Line = (usize, usize);
 
+pub fn canonical(line: Line) -> Line {
+    let (a, b) = line;
+    (a.min(b), a.max(b))
+}
+
+// A polygon as its corners in order. See `topology` for which way round they go.
+pub type Face = Vec<usize>;
+
+// Canonicalize and deduplicate, keeping the order lines first showed up in.
+pub fn canonical_lines(lines: Vec<Line>) -> Vec<Line> {
+    let mut seen = HashSet::with_capacity(lines.len());
+    lines.into_iter()
+        .map(canonical)
+        .filter(|line| seen.insert(*line))
+        .collect()
+}
+
+// Everything that can be wrong with a mesh. Rendering indexes `vertices` with `lines` blindly and
+// feeds coordinates straight to the canvas, so we'd rather find out when the mesh is built.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MeshError {
+    Empty,
+    NonFiniteOrigin(Vec3),
+    NonFiniteVertex {
+        index: usize,
+        vertex: Vec3,
+    },
+    LineOutOfRange {
+        index: usize,
+        line: Line,
+        vertex_count: usize,
+    },
+    SelfLoop {
+        index: usize,
+        vertex: usize,
+    },
+    FaceOutOfRange {
+        index: usize,
+        vertex_count: usize,
+    },
+    DegenerateFace {
+        index: usize,
+    },
+    // a prism, antiprism or bipyramid around something that isn't a polygon
+    TooFewSides(usize),
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "mesh has no vertices"),
+            Self::NonFiniteOrigin(origin) =>
+                write!(f, "mesh origin {:?} is not finite", origin.coord),
+            Self::NonFiniteVertex { index, vertex } =>
+                write!(f, "vertex {} at {:?} is not finite", index, vertex.coord),
+            Self::LineOutOfRange { index, line, vertex_count } =>
+                write!(f, "line {} {:?} refers past the last of {} vertices", index, line, vertex_count),
+            Self::SelfLoop { index, vertex } =>
+                write!(f, "line {} starts and ends at vertex {}", index, vertex),
+            Self::FaceOutOfRange { index, vertex_count } =>
+                write!(f, "face {} refers past the last of {} vertices", index, vertex_count),
+            Self::DegenerateFace { index } =>
+                write!(f, "face {} needs at least 3 distinct corners", index),
+            Self::TooFewSides(sides) =>
+                write!(f, "{} sides aren't enough for a polygon, it takes at least 3", sides),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "de::MeshParts"))]
 pub struct Mesh {
     pub origin: Vec3,
-    pub rotation: Vec3,
+    pub rotation: Quaternion,
+    // how far it turns every tic, on top of `rotation`
+    pub spin: Quaternion,
     pub vertices: Vec<Vec3>,
     pub lines: Vec<Line>,
+    // Optional; plenty of wireframes have no surface to speak of. When there are faces every one
+    // of their edges is also in `lines`.
+    pub faces: Vec<Face>,
 }
 
 impl Mesh {
-    pub fn new(origin: Vec3, vertices: Vec<Vec3>, lines: Vec<Line>) -> Self {
-        // TODO ensure every offset in lines fits within |vertices|
-        Mesh {
+    pub fn new(origin: Vec3, vertices: Vec<Vec3>, lines: Vec<Line>) -> Result<Self, MeshError> {
+        let mesh = Mesh {
             origin,
-            //rotation: Vec3::zeroes(),
-            rotation: Vec3::new([3.14159 / 4.0, 3.14159 / 4.0, 0.0]),
+            //rotation: Quaternion::identity(),
+            rotation: Quaternion::from_yaw_pitch_roll(PI / 4.0, PI / 4.0, 0.0),
+            spin: Quaternion::from_yaw_pitch_roll(0.005, 0.005, 0.0),
             vertices,
-            lines,
+            lines: canonical_lines(lines),
+            faces: vec![],
+        };
+        mesh.validate()?;
+
+        Ok(mesh)
+    }
+
+    // A mesh with a surface. The lines are the face edges and the faces are re-wound (see
+    // `orient_faces`) so neighbours agree and closed surfaces face outwards.
+    pub fn with_faces(origin: Vec3, vertices: Vec<Vec3>, faces: Vec<Face>) -> Result<Self, MeshError> {
+        let mut mesh = Self::new(origin, vertices, vec![])?;
+        mesh.faces = faces;
+        mesh.lines = mesh.face_lines().into_iter().collect();
+        mesh.lines.sort_unstable();
+        mesh.validate()?;
+        mesh.orient_faces();
+
+        Ok(mesh)
+    }
+
+    // Lines that aren't on any face, e.g. a flagpole sticking out of a cube.
+    pub fn add_lines(&mut self, lines: Vec<Line>) -> Result<(), MeshError> {
+        let mut all = std::mem::take(&mut self.lines);
+        all.extend(lines);
+        self.lines = canonical_lines(all);
+        self.validate()
+    }
+
+    // Every face edge, canonicalized.
+    pub fn face_lines(&self) -> HashSet<Line> {
+        self.faces.iter()
+            .flat_map(|face| (0..face.len()).map(move |i| canonical((face[i], face[(i + 1) % face.len()]))))
+            .collect()
+    }
+
+    pub fn half_edges(&self) -> HalfEdges {
+        HalfEdges::new(self.vertices.len(), &self.faces)
+    }
+
+    pub fn face_normals(&self) -> Vec<Vec3> {
+        self.faces.iter()
+            .map(|face| face_normal(&self.vertices, face))
+            .collect()
+    }
+
+    // V - E + F, counting every vertex and line. 2 for anything shaped like a sphere.
+    pub fn euler_characteristic(&self) -> isize {
+        self.vertices.len() as isize - self.lines.len() as isize + self.faces.len() as isize
+    }
+
+    pub fn is_manifold(&self) -> bool {
+        self.half_edges().is_manifold()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        !self.faces.is_empty() && self.half_edges().is_closed()
+    }
+
+    // Flip faces until every pair of neighbours walks their shared edge in opposite directions,
+    // then flip whole closed pieces that enclose negative volume so their normals point out.
+    // Edges with more than two faces on them don't make neighbours, so solids touching along an
+    // edge keep the winding they came with. Returns false if that's impossible (a Moebius strip, say); the
+    // faces are left half-done.
+    pub fn orient_faces(&mut self) -> bool {
+        let mut by_edge = std::collections::HashMap::<Line, Vec<usize>>::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            for i in 0..face.len() {
+                by_edge.entry(canonical((face[i], face[(i + 1) % face.len()]))).or_default().push(f);
+            }
+        }
+        let walks = |face: &Face, from: usize, to: usize| -> bool {
+            (0..face.len()).any(|i| face[i] == from && face[(i + 1) % face.len()] == to)
+        };
+
+        let mut orientable = true;
+        let mut visited = vec![false; self.faces.len()];
+        for seed in 0..self.faces.len() {
+            if visited[seed] {
+                continue;
+            }
+
+            visited[seed] = true;
+            let mut component = vec![seed];
+            let mut pending = vec![seed];
+            while let Some(f) = pending.pop() {
+                let face = self.faces[f].clone();
+                for i in 0..face.len() {
+                    let (a, b) = (face[i], face[(i + 1) % face.len()]);
+                    let sharing = &by_edge[&canonical((a, b))];
+                    if sharing.len() != 2 {
+                        continue;
+                    }
+                    for &g in sharing.iter() {
+                        if g == f {
+                            continue;
+                        }
+                        // g should walk this edge as b -> a
+                        let agrees = !walks(&self.faces[g], a, b);
+                        if visited[g] {
+                            orientable &= agrees;
+                            continue;
+                        }
+                        if !agrees {
+                            self.faces[g].reverse();
+                        }
+                        visited[g] = true;
+                        component.push(g);
+                        pending.push(g);
+                    }
+                }
+            }
+
+            // Divergence theorem: with outward normals the signed volume is positive. Only
+            // meaningful if the piece is closed, and a piece that stopped at a crowded edge isn't.
+            let closed = component.iter().all(|&f| {
+                let face = &self.faces[f];
+                (0..face.len()).all(|i| by_edge[&canonical((face[i], face[(i + 1) % face.len()]))].len() == 2)
+            });
+            if closed {
+                let volume = component.iter()
+                    .map(|&f| {
+                        let face = &self.faces[f];
+                        self.vertices[face[0]].dot(&face_area_vector(&self.vertices, face))
+                    })
+                    .sum::<crate::point::Element>();
+                if volume < 0.0 {
+                    for &f in component.iter() {
+                        self.faces[f].reverse();
+                    }
+                }
+            }
         }
+
+        orientable
+    }
+
+    pub fn adjacency(&self) -> Adjacency {
+        Adjacency::new(self.vertices.len(), &self.lines)
+    }
+
+    // Everything is public so anybody poking at a mesh after construction can check it's still
+    // sane with this.
+    pub fn validate(&self) -> Result<(), MeshError> {
+        if self.vertices.is_empty() {
+            return Err(MeshError::Empty);
+        }
+        if !self.origin.coord.iter().all(|x| x.is_finite()) {
+            return Err(MeshError::NonFiniteOrigin(self.origin));
+        }
+        if let Some((index, vertex)) = self.vertices.iter()
+            .enumerate()
+            .find(|(_, v)| !v.coord.iter().all(|x| x.is_finite()))
+        {
+            return Err(MeshError::NonFiniteVertex {
+                index,
+                vertex: *vertex,
+            });
+        }
+
+        let vertex_count = self.vertices.len();
+        for (index, face) in self.faces.iter().enumerate() {
+            if face.iter().any(|&v| v >= vertex_count) {
+                return Err(MeshError::FaceOutOfRange {
+                    index,
+                    vertex_count,
+                });
+            }
+            let distinct = face.iter().collect::<HashSet<&usize>>().len();
+            if face.len() < 3 || distinct != face.len() {
+                return Err(MeshError::DegenerateFace {
+                    index,
+                });
+            }
+        }
+
+        for (index, &(a, b)) in self.lines.iter().enumerate() {
+            if a >= vertex_count || b >= vertex_count {
+                return Err(MeshError::LineOutOfRange {
+                    index,
+                    line: (a, b),
+                    vertex_count,
+                });
+            }
+            if a == b {
+                return Err(MeshError::SelfLoop {
+                    index,
+                    vertex: a,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // the rotation of the mesh followed by its position
+    pub fn world_matrix(&self) -> Mat4 {
+        self.rotation.to_mat4() *
+                Mat4::translation(
+                    self.origin.x(),
+                    self.origin.y(),
+                    self.origin.z())
     }
 
     pub fn mk_cube() -> Mesh {
-        Self::new(
+        Self::with_faces(
             Vec3::zeroes(),
             vec![
                 Vec3::new([-1.0, 1.0, 1.0]),
@@ -36,41 +324,20 @@ impl Mesh {
             ],
 
             vec![
-                // inner cube
-                (0, 1),
-                (0, 2),
-                (0, 4),
-
-                (1, 0),
-                (1, 5),
-                (1, 6),
-
-                (2, 0),
-                (2, 3),
-                (2, 6),
-
-                (3, 2),
-                (3, 4),
-                (3, 7),
-
-                (4, 0),
-                (4, 3),
-                (4, 5),
-
-                (5, 1),
-                (5, 4),
-                (5, 7),
-
-                (6, 1),
-                (6, 2),
-                (6, 7),
-
-                (7, 3),
-                (7, 5),
-                (7, 6),
-
+                // +z
+                vec![2, 6, 1, 0],
+                // -z
+                vec![4, 5, 7, 3],
+                // +y
+                vec![0, 1, 5, 4],
+                // -y
+                vec![2, 3, 7, 6],
+                // +x
+                vec![1, 6, 7, 5],
+                // -x
+                vec![0, 4, 3, 2],
             ],
-        )
+        ).expect("the cube is hand-checked")
     }
 
     pub fn mk_tetra_cube() -> Mesh {
@@ -110,7 +377,7 @@ impl Mesh {
                 (8, 12), (9, 12), (10, 12), (11, 12),
                 */
             ],
-        )
+        ).expect("the tetra cube is hand-checked")
     }
 
     /*
@@ -132,3 +399,216 @@ impl Mesh {
     */
 
 }
+
+// Who's connected to whom. Built once from a mesh's lines (see `Mesh::adjacency`) so renderers
+// and generators don't have to rescan every line per query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Adjacency {
+    neighbours: Vec<Vec<usize>>,
+}
+
+impl Adjacency {
+    pub fn new(vertex_count: usize, lines: &[Line]) -> Self {
+        let mut neighbours = vec![vec![]; vertex_count];
+        for &(a, b) in lines.iter().map(|line| canonical(*line)).collect::<HashSet<Line>>().iter() {
+            if a != b {
+                neighbours[a].push(b);
+                neighbours[b].push(a);
+            }
+        }
+        for list in neighbours.iter_mut() {
+            list.sort_unstable();
+        }
+
+        Self {
+            neighbours,
+        }
+    }
+
+    // in increasing order
+    pub fn neighbours(&self, vertex: usize) -> &[usize] {
+        &self.neighbours[vertex]
+    }
+
+    pub fn degree(&self, vertex: usize) -> usize {
+        self.neighbours[vertex].len()
+    }
+
+    // Every connected component as a sorted list of its vertices, ordered by their smallest
+    // vertex. A vertex with no lines is a component all by itself.
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut seen = vec![false; self.neighbours.len()];
+        let mut components = vec![];
+        for start in 0..self.neighbours.len() {
+            if seen[start] {
+                continue;
+            }
+
+            seen[start] = true;
+            let mut component = vec![start];
+            let mut pending = vec![start];
+            while let Some(vertex) = pending.pop() {
+                for &next in self.neighbours[vertex].iter() {
+                    if !seen[next] {
+                        seen[next] = true;
+                        component.push(next);
+                        pending.push(next);
+                    }
+                }
+            }
+
+            component.sort_unstable();
+            components.push(component);
+        }
+
+        components
+    }
+}
+
+// Deserialized meshes go through `Mesh::validate` like everything else.
+#[cfg(feature = "serde")]
+mod de {
+    use serde::Deserialize;
+
+    use super::{Face, Line, Mesh, MeshError};
+    use crate::point::{Quaternion, Vec3};
+
+    #[derive(Deserialize)]
+    pub struct MeshParts {
+        origin: Vec3,
+        rotation: Quaternion,
+        spin: Quaternion,
+        vertices: Vec<Vec3>,
+        lines: Vec<Line>,
+        #[serde(default)]
+        faces: Vec<Face>,
+    }
+
+    impl TryFrom<MeshParts> for Mesh {
+        type Error = MeshError;
+
+        // the same way as `Mesh::with_faces` so every face edge is a line, with any loose lines
+        // added after
+        fn try_from(parts: MeshParts) -> Result<Self, Self::Error> {
+            let mut mesh = Mesh::with_faces(parts.origin, parts.vertices, parts.faces)?;
+            mesh.add_lines(parts.lines)?;
+            mesh.rotation = parts.rotation;
+            mesh.spin = parts.spin;
+
+            Ok(mesh)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::point::Element;
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialized_faces_draw_their_edges() {
+        let mut cube = Mesh::mk_cube();
+        cube.lines.clear();
+        cube.lines.push((0, 7));
+        let json = serde_json::to_string(&cube).unwrap();
+
+        let back = serde_json::from_str::<Mesh>(&json).unwrap();
+        assert_eq!(back.faces.len(), 6);
+        assert_eq!(back.lines.len(), 13);
+        assert!(back.face_lines().iter().all(|line| back.lines.contains(line)));
+        assert!(back.lines.contains(&(0, 7)));
+        assert!((back.rotation.dot(&cube.rotation) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn invalid_meshes() {
+        let triangle = || vec![Vec3::zeroes(), Vec3::new([1.0, 0.0, 0.0]), Vec3::new([0.0, 1.0, 0.0])];
+        let nan = Vec3::new([0.0, Element::NAN, 0.0]);
+
+        assert_eq!(Mesh::new(Vec3::zeroes(), vec![], vec![]).unwrap_err(), MeshError::Empty);
+        assert!(matches!(Mesh::new(nan, triangle(), vec![]), Err(MeshError::NonFiniteOrigin(_))));
+        let mut vertices = triangle();
+        vertices[2].coord[0] = Element::INFINITY;
+        assert!(matches!(Mesh::new(Vec3::zeroes(), vertices, vec![]), Err(MeshError::NonFiniteVertex { index: 2, .. })));
+
+        // lines are reported after canonicalizing
+        assert_eq!(Mesh::new(Vec3::zeroes(), triangle(), vec![(0, 1), (3, 1)]).unwrap_err(), MeshError::LineOutOfRange {
+            index: 1,
+            line: (1, 3),
+            vertex_count: 3,
+        });
+        assert_eq!(Mesh::new(Vec3::zeroes(), triangle(), vec![(2, 2)]).unwrap_err(), MeshError::SelfLoop {
+            index: 0,
+            vertex: 2,
+        });
+
+        assert_eq!(Mesh::with_faces(Vec3::zeroes(), triangle(), vec![vec![0, 1, 2], vec![0, 1, 3]]).unwrap_err(), MeshError::FaceOutOfRange {
+            index: 1,
+            vertex_count: 3,
+        });
+        assert_eq!(Mesh::with_faces(Vec3::zeroes(), triangle(), vec![vec![0, 1]]).unwrap_err(), MeshError::DegenerateFace { index: 0 });
+        assert_eq!(Mesh::with_faces(Vec3::zeroes(), triangle(), vec![vec![0, 1, 1]]).unwrap_err(), MeshError::DegenerateFace { index: 0 });
+
+        // and a mesh that was fine can be broken after the fact
+        let mut mesh = Mesh::new(Vec3::zeroes(), triangle(), vec![(0, 1)]).unwrap();
+        assert_eq!(mesh.add_lines(vec![(1, 1)]).unwrap_err(), MeshError::SelfLoop { index: 1, vertex: 1 });
+        mesh.lines.pop();
+        mesh.vertices.clear();
+        assert_eq!(mesh.validate().unwrap_err(), MeshError::Empty);
+    }
+
+    #[test]
+    fn error_messages() {
+        assert_eq!(MeshError::Empty.to_string(), "mesh has no vertices");
+        assert_eq!(MeshError::LineOutOfRange { index: 4, line: (1, 9), vertex_count: 8 }.to_string(),
+                   "line 4 (1, 9) refers past the last of 8 vertices");
+        assert_eq!(MeshError::DegenerateFace { index: 2 }.to_string(), "face 2 needs at least 3 distinct corners");
+        assert_eq!(MeshError::TooFewSides(2).to_string(), "2 sides aren't enough for a polygon, it takes at least 3");
+    }
+
+    #[test]
+    fn lines_are_undirected() {
+        assert_eq!(canonical((5, 2)), (2, 5));
+        assert_eq!(canonical_lines(vec![(3, 1), (0, 2), (1, 3), (2, 0), (1, 2)]), vec![(1, 3), (0, 2), (1, 2)]);
+
+        // the cube's edges written in both directions still make 12 lines
+        let cube = Mesh::mk_cube();
+        let both_ways = cube.lines.iter()
+            .flat_map(|&(a, b)| [(a, b), (b, a)])
+            .collect::<Vec<Line>>();
+        let mesh = Mesh::new(Vec3::zeroes(), cube.vertices.clone(), both_ways).unwrap();
+        assert_eq!(mesh.lines.len(), 12);
+        assert!(mesh.lines.iter().all(|&(a, b)| a < b));
+    }
+
+    #[test]
+    fn adjacency() {
+        let cube = Mesh::mk_cube().adjacency();
+        for vertex in 0..8 {
+            assert_eq!(cube.degree(vertex), 3);
+            assert!(cube.neighbours(vertex).windows(2).all(|pair| pair[0] < pair[1]));
+            for &other in cube.neighbours(vertex) {
+                assert!(cube.neighbours(other).contains(&vertex));
+            }
+        }
+        // opposite corners
+        assert!(!cube.neighbours(0).contains(&7));
+        assert_eq!(cube.components(), vec![(0..8).collect::<Vec<usize>>()]);
+
+        // a triangle, a loose line and a vertex on its own, given out of order and with repeats
+        let pieces = Adjacency::new(6, &[(4, 3), (1, 0), (2, 1), (0, 2), (3, 4)]);
+        assert_eq!(pieces.neighbours(0), &[1, 2]);
+        assert_eq!(pieces.degree(3), 1);
+        assert_eq!(pieces.degree(5), 0);
+        assert_eq!(pieces.components(), vec![vec![0, 1, 2], vec![3, 4], vec![5]]);
+    }
+
+    #[test]
+    fn face_edges_are_lines() {
+        let cube = Mesh::mk_cube();
+        assert_eq!(cube.lines.len(), 12);
+        assert!(cube.face_lines().iter().all(|line| cube.lines.contains(line)));
+    }
+}
@@ -0,0 +1,385 @@
+// Morphing one mesh into another. Every vertex of each mesh is paired with a vertex of the other:
+// as many as possible one-to-one (closest pairs first) and whatever's left over on the bigger mesh
+// shares the nearest vertex of the smaller one. So the smaller mesh is resampled up to the bigger
+// one's vertex count by doubling up vertices, which keeps it looking exactly like itself at the
+// start (or end) of the morph.
+//
+// Each pair becomes a vertex sliding from one position to the other. The first mesh's lines fade
+// out as the second's fade in: for a stretch in the middle both are drawn.
+
+
+use crate::error::message_error;
+use crate::mesh::{Line, Mesh};
+use crate::point::{Element, Vec3};
+
+message_error!(MorphError, "morph");
+
+// How much of the way through both sets of lines are drawn, centred on the middle.
+const CROSSOVER: Element = 1.0 / 3.0;
+
+// Most vertices either side of a morph can have. Pairing them up weighs every vertex of one against
+// every vertex of the other, so this keeps it to a million distances.
+pub const MAX_VERTICES: usize = 1000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Easing {
+    Linear,
+    // slow to start
+    EaseIn,
+    // slow to stop
+    EaseOut,
+    // slow at both ends
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 4] = [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut];
+
+    // by its snake_case name, like in scene files
+    pub fn from_name(name: &str) -> Option<Easing> {
+        Easing::ALL.into_iter().find(|easing| format!("{:?}", easing).to_lowercase() == name.replace('_', ""))
+    }
+
+    // How far along (0 to 1) we are at `t` of the way through (0 to 1), cubic.
+    pub fn apply(self, t: Element) -> Element {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => if t < 0.5 {
+                4.0 * t * t * t
+            } else {
+                1.0 - (2.0 - 2.0 * t).powi(3) / 2.0
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "de::MorphParts"))]
+pub struct Morph {
+    // where each sliding vertex starts and ends up
+    from: Vec<Vec3>,
+    to: Vec<Vec3>,
+    // both meshes' lines, between the sliding vertices
+    from_lines: Vec<Line>,
+    to_lines: Vec<Line>,
+    // what the mesh ends up as, faces and all
+    target: Mesh,
+    pub frames: usize,
+    pub frame: usize,
+    pub easing: Easing,
+    // run back and forth forever rather than stopping at `target`
+    pub bounce: bool,
+}
+
+impl Morph {
+    // From the vertices and lines of `from` to all of `to` over `frames` tics. Origin, rotation
+    // and spin are left to whichever mesh it's applied to.
+    pub fn new(from: &Mesh, to: &Mesh, frames: usize) -> Result<Self, MorphError> {
+        for (side, mesh) in [("from", from), ("to", to)] {
+            if mesh.vertices.len() > MAX_VERTICES {
+                return error(format!("the mesh to morph {} has {} vertices, at most {} are allowed", side, mesh.vertices.len(), MAX_VERTICES));
+            }
+        }
+
+        let pairs = correspondence(&from.vertices, &to.vertices);
+        // a vertex's lines go from its one-to-one pair, which comes first
+        let lines = |lines: &[Line], side: fn(&(usize, usize)) -> usize| {
+            let mut index = vec![usize::MAX; pairs.len()];
+            for (i, pair) in pairs.iter().enumerate().rev() {
+                index[side(pair)] = i;
+            }
+            lines.iter()
+                .map(|&(a, b)| (index[a], index[b]))
+                .collect::<Vec<Line>>()
+        };
+
+        Ok(Self {
+            from: pairs.iter().map(|&(a, _)| from.vertices[a]).collect(),
+            to: pairs.iter().map(|&(_, b)| to.vertices[b]).collect(),
+            from_lines: lines(&from.lines, |pair| pair.0),
+            to_lines: lines(&to.lines, |pair| pair.1),
+            target: to.clone(),
+            frames: frames.max(1),
+            frame: 0,
+            easing: Easing::default(),
+            bounce: false,
+        })
+    }
+
+    // What `apply` counts on: a start and end for every sliding vertex and lines between them.
+    // `new` always gets this right; deserialized morphs and ones added to a scene get checked with
+    // it.
+    pub fn validate(&self) -> Result<(), MorphError> {
+        if self.from.len() != self.to.len() {
+            return error(format!("{} vertices start out but {} end up", self.from.len(), self.to.len()));
+        }
+        if self.from.iter().chain(self.to.iter()).any(|v| !v.coord.iter().all(|x| x.is_finite())) {
+            return error("a vertex is not finite".to_string());
+        }
+        let count = self.from.len();
+        if let Some(line) = self.from_lines.iter().chain(self.to_lines.iter()).find(|&&(a, b)| a >= count || b >= count || a == b) {
+            return error(format!("line {:?} isn't between two of the {} vertices", line, count));
+        }
+        if self.frames == 0 {
+            return error("it has to take at least one frame".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn done(&self) -> bool {
+        !self.bounce && self.frame >= self.frames
+    }
+
+    // How far along the morph is (0 to 1), before easing. Bouncing goes 0 to 1 and back again.
+    pub fn progress(&self) -> Element {
+        let t = self.frame as Element / self.frames as Element;
+        if self.bounce && t > 1.0 {
+            1.0 - (t - 1.0)
+        } else {
+            t.min(1.0)
+        }
+    }
+
+    pub fn step(&mut self) {
+        self.frame += 1;
+        if self.bounce {
+            self.frame %= 2 * self.frames;
+        }
+    }
+
+    // Move `mesh` to where the morph is up to. At the very end it becomes `target`.
+    pub fn apply(&self, mesh: &mut Mesh) {
+        if self.done() {
+            mesh.vertices = self.target.vertices.clone();
+            mesh.lines = self.target.lines.clone();
+            mesh.faces = self.target.faces.clone();
+            return;
+        }
+
+        let t = self.easing.apply(self.progress());
+        mesh.vertices = self.from.iter()
+            .zip(self.to.iter())
+            .map(|(a, b)| *a + (*b - *a).scale(t))
+            .collect();
+
+        let mut lines = vec![];
+        if t < 0.5 + CROSSOVER / 2.0 {
+            lines.extend(self.from_lines.iter().copied());
+        }
+        if t > 0.5 - CROSSOVER / 2.0 {
+            lines.extend(self.to_lines.iter().copied());
+        }
+        lines.sort_unstable();
+        lines.dedup();
+        mesh.lines = lines;
+        // (faces don't survive the vertices moving about)
+        mesh.faces = vec![];
+    }
+}
+
+// Pairs of (vertex of `from`, vertex of `to`) using every vertex on both sides, the one-to-one
+// pairs first. Closeness is measured with both scaled to the same size, so a big cube still lines
+// up with a small tetrahedron.
+pub fn correspondence(from: &[Vec3], to: &[Vec3]) -> Vec<(usize, usize)> {
+    let normalised = |vertices: &[Vec3]| {
+        let radius = vertices.iter().map(Vec3::magnitude).fold(0.0, Element::max);
+        let scale = if radius > 0.0 { 1.0 / radius } else { 1.0 };
+        vertices.iter().map(|v| v.scale(scale)).collect::<Vec<Vec3>>()
+    };
+    let (from, to) = (normalised(from), normalised(to));
+    let distance = |a: usize, b: usize| (from[a] - to[b]).magnitude();
+
+    let mut candidates = (0..from.len())
+        .flat_map(|a| (0..to.len()).map(move |b| (distance(a, b), a, b)))
+        .collect::<Vec<(Element, usize, usize)>>();
+    candidates.sort_unstable_by(|x, y| x.0.total_cmp(&y.0).then((x.1, x.2).cmp(&(y.1, y.2))));
+
+    let mut pairs = vec![];
+    let (mut from_used, mut to_used) = (vec![false; from.len()], vec![false; to.len()]);
+    for (_, a, b) in candidates {
+        if !from_used[a] && !to_used[b] {
+            from_used[a] = true;
+            to_used[b] = true;
+            pairs.push((a, b));
+        }
+    }
+
+    // the leftovers double up on their nearest
+    let nearest = |distances: &mut dyn Iterator<Item = (usize, Element)>| {
+        distances.fold((0, Element::INFINITY), |best, x| if x.1 < best.1 { x } else { best }).0
+    };
+    for a in (0..from.len()).filter(|&a| !from_used[a]) {
+        pairs.push((a, nearest(&mut (0..to.len()).map(|b| (b, distance(a, b))))));
+    }
+    for b in (0..to.len()).filter(|&b| !to_used[b]) {
+        pairs.push((nearest(&mut (0..from.len()).map(|a| (a, distance(a, b)))), b));
+    }
+
+    pairs
+}
+
+// Step the morphs along and move the meshes they're for, skipping any that don't exist.
+pub fn tic(morphs: &mut [(Morph, usize)], meshes: &mut [Mesh]) {
+    for (morph, mesh) in morphs.iter_mut() {
+        if morph.done() {
+            continue;
+        }
+        morph.step();
+        if let Some(mesh) = meshes.get_mut(*mesh) {
+            morph.apply(mesh);
+        }
+    }
+}
+
+// Deserialized morphs go through `Morph::validate` (the target is a `Mesh`, which checks itself).
+#[cfg(feature = "serde")]
+mod de {
+    use serde::Deserialize;
+
+    use super::{Easing, Morph, MorphError};
+    use crate::mesh::{Line, Mesh};
+    use crate::point::Vec3;
+
+    #[derive(Deserialize)]
+    pub struct MorphParts {
+        from: Vec<Vec3>,
+        to: Vec<Vec3>,
+        from_lines: Vec<Line>,
+        to_lines: Vec<Line>,
+        target: Mesh,
+        frames: usize,
+        frame: usize,
+        easing: Easing,
+        bounce: bool,
+    }
+
+    impl TryFrom<MorphParts> for Morph {
+        type Error = MorphError;
+
+        fn try_from(parts: MorphParts) -> Result<Self, Self::Error> {
+            let morph = Morph {
+                from: parts.from,
+                to: parts.to,
+                from_lines: parts.from_lines,
+                to_lines: parts.to_lines,
+                target: parts.target,
+                frames: parts.frames,
+                frame: parts.frame,
+                easing: parts.easing,
+                bounce: parts.bounce,
+            };
+            morph.validate()?;
+
+            Ok(morph)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_to_finish() {
+        let (cube, tetra_cube) = (Mesh::mk_cube(), Mesh::mk_tetra_cube());
+        let mut morph = Morph::new(&cube, &tetra_cube, 10).unwrap();
+        assert_eq!(morph.validate(), Ok(()));
+
+        // every vertex of both gets used
+        let slides = cube.vertices.len().max(tetra_cube.vertices.len());
+        let mut mesh = cube.clone();
+        morph.apply(&mut mesh);
+        assert_eq!(mesh.vertices.len(), slides);
+        assert!(mesh.vertices.iter().all(|v| cube.vertices.contains(v)));
+        assert_eq!(mesh.lines.len(), cube.lines.len());
+
+        for _ in 0..10 {
+            morph.step();
+        }
+        assert!(morph.done());
+        morph.apply(&mut mesh);
+        assert_eq!(mesh.vertices, tetra_cube.vertices);
+        assert_eq!(mesh.lines, tetra_cube.lines);
+    }
+
+    #[test]
+    fn bouncing() {
+        let mut morph = Morph::new(&Mesh::mk_cube(), &Mesh::mk_tetra_cube(), 4).unwrap();
+        morph.bounce = true;
+        let mut progress = vec![];
+        for _ in 0..8 {
+            progress.push(morph.progress());
+            morph.step();
+        }
+        assert_eq!(progress, vec![0.0, 0.25, 0.5, 0.75, 1.0, 0.75, 0.5, 0.25]);
+        assert!(!morph.done());
+    }
+
+    #[test]
+    fn easing_ends() {
+        for easing in Easing::ALL {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+
+        assert_eq!(Easing::from_name("ease_out"), Some(Easing::EaseOut));
+        assert_eq!(Easing::from_name("bounce"), None);
+    }
+
+    #[test]
+    fn one_to_one_first() {
+        let a = [Vec3::new([1.0, 0.0, 0.0]), Vec3::new([0.0, 1.0, 0.0])];
+        let b = [Vec3::new([0.0, 2.0, 0.0]), Vec3::new([2.0, 0.0, 0.0]), Vec3::new([0.0, 0.0, 2.0])];
+        let pairs = correspondence(&a, &b);
+        assert_eq!(&pairs[..2], &[(0, 1), (1, 0)]);
+        assert_eq!(pairs.len(), 3);
+    }
+
+    #[test]
+    fn too_many_vertices() {
+        let line = |count: usize| {
+            let vertices = (0..count).map(|i| Vec3::new([i as Element, 0.0, 0.0])).collect();
+            Mesh::new(Vec3::zeroes(), vertices, vec![]).unwrap()
+        };
+        assert!(Morph::new(&line(MAX_VERTICES), &Mesh::mk_cube(), 10).is_ok());
+        assert!(Morph::new(&line(MAX_VERTICES + 1), &Mesh::mk_cube(), 10).is_err());
+        assert!(Morph::new(&Mesh::mk_cube(), &line(MAX_VERTICES + 1), 10).is_err());
+    }
+
+    #[test]
+    fn tic_skips_missing_meshes() {
+        let morph = Morph::new(&Mesh::mk_cube(), &Mesh::mk_tetra_cube(), 10).unwrap();
+        let mut meshes = vec![Mesh::mk_cube()];
+        tic(&mut [(morph.clone(), 3), (morph, 0)], &mut meshes);
+        assert_ne!(meshes[0].vertices.len(), 8);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialized_morphs_are_checked() {
+        let morph = Morph::new(&Mesh::mk_cube(), &Mesh::mk_tetra_cube(), 10).unwrap();
+        let json = serde_json::to_value(&morph).unwrap();
+        assert!(serde_json::from_value::<Morph>(json.clone()).is_ok());
+
+        let mut short = json.clone();
+        short["to"].as_array_mut().unwrap().pop();
+        assert!(serde_json::from_value::<Morph>(short).is_err());
+
+        let mut past = json.clone();
+        past["from_lines"].as_array_mut().unwrap().push(serde_json::json!([0, 99]));
+        assert!(serde_json::from_value::<Morph>(past).is_err());
+
+        let mut still = json;
+        still["frames"] = serde_json::json!(0);
+        assert!(serde_json::from_value::<Morph>(still).is_err());
+    }
+}
@@ -0,0 +1,346 @@
+// Wavefront OBJ support. `v` records become vertices, `l` polylines become lines and `f` records
+// become faces (whose edges are lines too). Everything else (normals, texture coords, groups,
+// materials, ...) is skipped. Going the other way we write `v`, `f` and `l` records, which Blender
+// and friends happily open.
+//
+// http://paulbourke.net/dataformats/obj/
+
+use std::fmt;
+use std::fmt::Write;
+
+use crate::mesh::{canonical, Face, Line, Mesh, MeshError};
+use crate::point::{Element, Vec3};
+use crate::point::Mat4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjError {
+    // 1-based, like every text editor; `None` when it's the mesh as a whole (no vertices, say)
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+struct Parser {
+    vertices: Vec<Vec3>,
+    lines: Vec<Line>,
+    faces: Vec<Face>,
+    line: usize,
+    // the line of the file each vertex, line and face came from
+    vertex_from: Vec<usize>,
+    line_from: Vec<usize>,
+    face_from: Vec<usize>,
+}
+
+impl Parser {
+    fn error<T>(&self, message: String) -> Result<T, ObjError> {
+        Err(ObjError {
+            line: Some(self.line),
+            message,
+        })
+    }
+
+    // Where in the file `Mesh` found something wrong, if it's down to one record.
+    fn source(&self, e: &MeshError) -> Option<usize> {
+        match *e {
+            MeshError::NonFiniteVertex { index, .. } => self.vertex_from.get(index).copied(),
+            MeshError::FaceOutOfRange { index, .. } | MeshError::DegenerateFace { index } =>
+                self.face_from.get(index).copied(),
+            // (lines get deduplicated so their index is no use, but the line itself is)
+            MeshError::LineOutOfRange { line, .. } => self.lines.iter()
+                .position(|&l| canonical(l) == canonical(line))
+                .map(|i| self.line_from[i]),
+            MeshError::SelfLoop { vertex, .. } => self.lines.iter()
+                .position(|&l| l == (vertex, vertex))
+                .map(|i| self.line_from[i]),
+            MeshError::Empty | MeshError::NonFiniteOrigin(_) | MeshError::TooFewSides(_) => None,
+        }
+    }
+
+    fn mesh_error(&self, e: MeshError) -> ObjError {
+        ObjError {
+            line: self.source(&e),
+            message: e.to_string(),
+        }
+    }
+
+    fn vertex(&mut self, args: &[&str]) -> Result<(), ObjError> {
+        // `w` and vertex colours (which some exporters tack on) are allowed but we have no use
+        // for them
+        if !matches!(args.len(), 3 | 4 | 6 | 7) {
+            return self.error(format!("expected `v x y z [w]` or `v x y z r g b [w]`, got {} numbers", args.len()));
+        }
+
+        let mut coord = [0.0; 3];
+        for (x, arg) in coord.iter_mut().zip(args.iter()) {
+            *x = match arg.parse::<Element>() {
+                Ok(x) if x.is_finite() => x,
+                Ok(_) => return self.error(format!("vertex coordinate {:?} is not finite", arg)),
+                Err(_) => return self.error(format!("vertex coordinate {:?} is not a number", arg)),
+            };
+        }
+
+        self.vertices.push(Vec3::new(coord));
+        self.vertex_from.push(self.line);
+        Ok(())
+    }
+
+    // Face and line elements look like `v`, `v/vt`, `v//vn` or `v/vt/vn`. Only `v` matters to us.
+    // Indices are 1-based and negative ones count back from the most recent vertex.
+    fn index(&self, arg: &str) -> Result<usize, ObjError> {
+        let raw = arg.split('/').next().unwrap_or("");
+        let index = match raw.parse::<isize>() {
+            Ok(index) => index,
+            Err(_) => return self.error(format!("vertex reference {:?} is not an integer", arg)),
+        };
+
+        let count = self.vertices.len() as isize;
+        let resolved = match index {
+            0 => return self.error("vertex references start at 1, not 0".to_string()),
+            i if i > 0 => i - 1,
+            i => count + i,
+        };
+        if resolved < 0 || resolved >= count {
+            return self.error(format!("vertex reference {} is out of range ({} vertices so far)", index, count));
+        }
+
+        Ok(resolved as usize)
+    }
+
+    // `Mesh::new` takes care of shared edges showing up once per face
+    fn edge(&mut self, a: usize, b: usize) {
+        if a != b {
+            self.lines.push((a, b));
+            self.line_from.push(self.line);
+        }
+    }
+
+    fn polyline(&mut self, args: &[&str], closed: bool) -> Result<(), ObjError> {
+        let indices = args.iter()
+            .map(|arg| self.index(arg))
+            .collect::<Result<Vec<usize>, ObjError>>()?;
+
+        // Faces that repeat a corner are out there in the wild. Their edges are still worth
+        // drawing even if they can't be a proper face.
+        if closed && indices.iter().collect::<std::collections::HashSet<&usize>>().len() == indices.len() {
+            self.faces.push(indices);
+            self.face_from.push(self.line);
+            return Ok(());
+        }
+
+        for pair in indices.windows(2) {
+            self.edge(pair[0], pair[1]);
+        }
+        if closed {
+            self.edge(indices[indices.len() - 1], indices[0]);
+        }
+
+        Ok(())
+    }
+
+    fn record(&mut self, text: &str) -> Result<(), ObjError> {
+        // comments can trail a record too
+        let text = text.split('#').next().unwrap_or("");
+        let mut words = text.split_whitespace();
+        let keyword = match words.next() {
+            Some(keyword) => keyword,
+            None => return Ok(()),
+        };
+        let args = words.collect::<Vec<&str>>();
+
+        match keyword {
+            "v" => self.vertex(&args),
+            "l" => {
+                if args.len() < 2 {
+                    return self.error("a line needs at least 2 vertices".to_string());
+                }
+                self.polyline(&args, false)
+            },
+            "f" => {
+                if args.len() < 3 {
+                    return self.error("a face needs at least 3 vertices".to_string());
+                }
+                self.polyline(&args, true)
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Mesh {
+    pub fn from_obj(text: &str) -> Result<Mesh, ObjError> {
+        let mut parser = Parser {
+            vertices: vec![],
+            lines: vec![],
+            faces: vec![],
+            line: 0,
+            vertex_from: vec![],
+            line_from: vec![],
+            face_from: vec![],
+        };
+
+        // OBJ lets a trailing backslash continue a record onto the next line
+        let mut pending = String::new();
+        for (i, text) in text.lines().enumerate() {
+            if pending.is_empty() {
+                parser.line = i + 1;
+            }
+            match text.strip_suffix('\\') {
+                Some(head) => {
+                    pending.push_str(head);
+                    pending.push(' ');
+                },
+                None => {
+                    pending.push_str(text);
+                    parser.record(&pending)?;
+                    pending.clear();
+                },
+            }
+        }
+        parser.record(&pending)?;
+
+        if parser.faces.is_empty() {
+            return Mesh::new(Vec3::zeroes(), parser.vertices.clone(), parser.lines.clone())
+                .map_err(|e| parser.mesh_error(e));
+        }
+
+        let mut mesh = Mesh::with_faces(Vec3::zeroes(), parser.vertices.clone(), parser.faces.clone())
+            .map_err(|e| parser.mesh_error(e))?;
+        mesh.add_lines(parser.lines.clone()).map_err(|e| parser.mesh_error(e))?;
+        Ok(mesh)
+    }
+
+    // With `bake_transform` the vertices are written where the mesh currently sits in the world
+    // (after `rotation` and `origin`), otherwise they're written as-is in model space.
+    pub fn to_obj(&self, bake_transform: bool) -> String {
+        let transform = if bake_transform {
+            self.world_matrix()
+        } else {
+            Mat4::identity()
+        };
+
+        let mut out = String::new();
+        writeln!(out, "# apate mesh: {} vertices, {} lines, {} faces", self.vertices.len(), self.lines.len(), self.faces.len())
+            .expect("writing to a String can't fail");
+        for vertex in self.vertices.iter() {
+            let v = Vec3::transform_coordinates(vertex, &transform);
+            writeln!(out, "v {} {} {}", v.x(), v.y(), v.z())
+                .expect("writing to a String can't fail");
+        }
+        // OBJ indices are 1-based
+        for face in self.faces.iter() {
+            let corners = face.iter()
+                .map(|v| (v + 1).to_string())
+                .collect::<Vec<String>>();
+            writeln!(out, "f {}", corners.join(" "))
+                .expect("writing to a String can't fail");
+        }
+        // the face edges are implied by the `f` records
+        let face_edges = self.face_lines();
+        for (a, b) in self.lines.iter().filter(|line| !face_edges.contains(line)) {
+            writeln!(out, "l {} {}", a + 1, b + 1)
+                .expect("writing to a String can't fail");
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_and_faces() {
+        let mesh = Mesh::from_obj("\
+# a square with a tail
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0 1.0
+v 2 2 2
+f 1 2/7 3//2 -2
+l 3 5
+vn 0 0 1
+").unwrap();
+        assert_eq!(mesh.vertices.len(), 5);
+        assert_eq!(mesh.faces.len(), 1);
+        assert_eq!(mesh.lines.len(), 5);
+        assert!(mesh.lines.contains(&(2, 4)));
+    }
+
+    #[test]
+    fn vertex_colours() {
+        let mesh = Mesh::from_obj("v 0 0 0 1 0 0\nv 1 0 0 0 1 0 1\nv 0 1 0\nl 1 2 3\n").unwrap();
+        assert_eq!(mesh.vertices[1], Vec3::new([1.0, 0.0, 0.0]));
+        assert_eq!(mesh.lines, vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn continued_records() {
+        let mesh = Mesh::from_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nl 1 \\\n2 3\n").unwrap();
+        assert_eq!(mesh.lines, vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn error_lines() {
+        let line = |text: &str| Mesh::from_obj(text).unwrap_err().line;
+
+        assert_eq!(line("v 0 0 0\nv 1 0 0\n\nl 1 3\nv 2 0 0\n"), Some(4));
+        assert_eq!(line("v 0 0 0\nv 1 x 0\n"), Some(2));
+        assert_eq!(line("v 0 0 0\nv 1 0 0\nf 1 2\n"), Some(3));
+        assert_eq!(line("v 0 0 0\nl 1 \\\n0\nv 1 0 0\n"), Some(2));
+        assert_eq!(line("v 0 0 inf\n"), Some(1));
+        assert_eq!(line("v 0 0 0\nv 1 0 0 1 0\n"), Some(2));
+        // nothing to point at
+        assert_eq!(line("# empty\n\n"), None);
+    }
+
+    fn sorted(mut lines: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        lines.sort_unstable();
+        lines
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut cube = Mesh::mk_cube();
+        cube.add_lines(vec![(0, 7)]).unwrap();
+        let text = cube.to_obj(false);
+        // only the diagonal needs an `l`, the faces' edges come with the `f`s
+        assert_eq!(text.lines().filter(|line| line.starts_with("l ")).count(), 1);
+
+        let back = Mesh::from_obj(&text).unwrap();
+        assert_eq!(back.vertices, cube.vertices);
+        assert_eq!(back.faces, cube.faces);
+        assert_eq!(sorted(back.lines), sorted(cube.lines));
+
+        let wire = Mesh::new(Vec3::zeroes(), vec![Vec3::new([0.5, -0.25, 1e-3]), Vec3::new([1.0 / 3.0, 2.0, 0.0])], vec![(0, 1)]).unwrap();
+        let back = Mesh::from_obj(&wire.to_obj(false)).unwrap();
+        assert_eq!(back.vertices, wire.vertices);
+        assert_eq!(back.lines, wire.lines);
+        assert!(back.faces.is_empty());
+    }
+
+    #[test]
+    fn baked_transform() {
+        let mut cube = Mesh::mk_cube();
+        cube.origin = Vec3::new([10.0, 0.0, 0.0]);
+        let world = cube.world_matrix();
+
+        let back = Mesh::from_obj(&cube.to_obj(true)).unwrap();
+        for (baked, vertex) in back.vertices.iter().zip(cube.vertices.iter()) {
+            assert!(baked.approx_eq(&Vec3::transform_coordinates(vertex, &world), 1e-9));
+            // the cube comes tilted, so its corners only keep their distance from the origin
+            assert!((baked.distance(&cube.origin) - 3.0f64.sqrt()).abs() < 1e-9);
+        }
+    }
+}
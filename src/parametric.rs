@@ -0,0 +1,323 @@
+// Wireframes of parametric surfaces. A surface is a function of (u, v), both running from 0 to 1,
+// sampled on a grid of `u_steps` by `v_steps` quads. Each parameter's ends are either left apart or
+// glued together (`Seam`) and a row of samples that all land on the same point (the poles of a
+// sphere, the tip of a cone) becomes a single vertex, so the quads next to it turn into triangles.
+
+use std::f64::consts::PI;
+
+use crate::error::message_error;
+use crate::mesh::{Face, Mesh};
+use crate::point::{Element, Vec3};
+
+// How far apart (relative to the size of the surface) samples can be and still count as the same.
+const WELD_TOLERANCE: Element = 1e-9;
+
+message_error!(ParametricError, "parametric surface");
+
+// What happens where a parameter wraps from 1 back to 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Seam {
+    // the ends are an edge of the surface
+    Open,
+    // the ends are the same place, like going around a cylinder
+    Closed,
+    // the ends are the same place but the other parameter runs backwards there, like going around
+    // a Möbius strip
+    Twisted,
+}
+
+// Sample `f` and join up the samples. Fails on zero steps, fewer than three across a seam or two
+// twisted seams.
+pub fn surface<F>(f: F, u_steps: usize, v_steps: usize, u_seam: Seam, v_seam: Seam) -> Result<Mesh, ParametricError>
+where
+    F: Fn(Element, Element) -> Vec3,
+{
+    // (gluing the corners together gets ambiguous)
+    if u_seam == Seam::Twisted && v_seam == Seam::Twisted {
+        return error("only one seam can be twisted".to_string());
+    }
+    for (name, steps, seam) in [("u", u_steps, u_seam), ("v", v_steps, v_seam)] {
+        let least = if seam == Seam::Open { 1 } else { 3 };
+        if steps < least {
+            return error(format!("{:?} seams need at least {} steps, got {} for {}", seam, least, steps, name));
+        }
+    }
+
+    let samples = (0..=u_steps)
+        .map(|i| (0..=v_steps)
+            .map(|j| f(i as Element / u_steps as Element, j as Element / v_steps as Element))
+            .collect())
+        .collect::<Vec<Vec<Vec3>>>();
+
+    // where each sample really lives once the seams are glued
+    let glue = |i: usize, j: usize| -> (usize, usize) {
+        let (mut i, mut j) = (i, j);
+        loop {
+            match (i == u_steps, u_seam, j == v_steps, v_seam) {
+                (true, Seam::Closed, _, _) => i = 0,
+                (true, Seam::Twisted, _, _) => (i, j) = (0, v_steps - j),
+                (_, _, true, Seam::Closed) => j = 0,
+                (_, _, true, Seam::Twisted) => (i, j) = (u_steps - i, 0),
+                _ => return (i, j),
+            }
+        }
+    };
+
+    let size = samples.iter()
+        .flatten()
+        .map(Vec3::magnitude)
+        .fold(0.0, Element::max);
+    let tolerance = WELD_TOLERANCE * size.max(1.0);
+    let collapsed = |row: Vec<Vec3>| row.iter().all(|p| (*p - row[0]).magnitude() <= tolerance);
+    let collapsed_u = (0..=u_steps)
+        .map(|i| collapsed((0..=v_steps).map(|j| samples[i][j]).collect()))
+        .collect::<Vec<bool>>();
+    let collapsed_v = (0..=v_steps)
+        .map(|j| collapsed((0..=u_steps).map(|i| samples[i][j]).collect()))
+        .collect::<Vec<bool>>();
+
+    let mut vertices = vec![];
+    let mut index = vec![vec![usize::MAX; v_steps + 1]; u_steps + 1];
+    for i in 0..=u_steps {
+        for j in 0..=v_steps {
+            let (gi, gj) = glue(i, j);
+            // a collapsed row (or column) goes to its first sample
+            let (gi, gj) = if collapsed_u[gi] {
+                glue(gi, 0)
+            } else if collapsed_v[gj] {
+                glue(0, gj)
+            } else {
+                (gi, gj)
+            };
+            if index[gi][gj] == usize::MAX {
+                index[gi][gj] = vertices.len();
+                vertices.push(samples[gi][gj]);
+            }
+            index[i][j] = index[gi][gj];
+        }
+    }
+
+    let mut faces = Vec::<Face>::new();
+    for i in 0..u_steps {
+        for j in 0..v_steps {
+            let mut face = vec![index[i][j], index[i + 1][j], index[i + 1][j + 1], index[i][j + 1]];
+            // corners welded together leave a triangle, or nothing at all
+            face.dedup();
+            if face.len() > 1 && face.first() == face.last() {
+                face.pop();
+            }
+            if face.len() >= 3 {
+                faces.push(face);
+            }
+        }
+    }
+
+    Mesh::with_faces(Vec3::zeroes(), vertices, faces)
+        .or_else(|e| error(format!("made a bad mesh: {}", e)))
+}
+
+// The surfaces below with sizes and steps that look about right spinning on the page.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Surface {
+    UvSphere,
+    Torus,
+    Cylinder,
+    Cone,
+    Mobius,
+    KleinBottle,
+    // rings rippling out from the middle of a height field
+    Ripple,
+}
+
+impl Surface {
+    pub const ALL: [Surface; 7] = [
+        Surface::UvSphere,
+        Surface::Torus,
+        Surface::Cylinder,
+        Surface::Cone,
+        Surface::Mobius,
+        Surface::KleinBottle,
+        Surface::Ripple,
+    ];
+
+    // by its snake_case name, like in scene files
+    pub fn from_name(name: &str) -> Option<Surface> {
+        Surface::ALL.into_iter().find(|surface| format!("{:?}", surface).to_lowercase() == name.replace('_', ""))
+    }
+}
+
+impl Mesh {
+    pub fn mk_surface(surface: Surface) -> Result<Mesh, ParametricError> {
+        match surface {
+            Surface::UvSphere => Mesh::mk_uv_sphere(24, 12),
+            Surface::Torus => Mesh::mk_torus(0.7, 0.3, 32, 12),
+            Surface::Cylinder => Mesh::mk_cylinder(0.6, 1.4, 24, 4),
+            Surface::Cone => Mesh::mk_cone(0.8, 1.2, 24, 4),
+            Surface::Mobius => Mesh::mk_mobius(0.6, 48, 4),
+            Surface::KleinBottle => Mesh::mk_klein_bottle(1.5, 48, 16),
+            Surface::Ripple => Mesh::mk_height_field(|x, y| 0.1 * (3.0 * PI * x.hypot(y)).cos(), 24, 24),
+        }
+    }
+
+    // A unit sphere; u goes around the equator, v from pole to pole.
+    pub fn mk_uv_sphere(u_steps: usize, v_steps: usize) -> Result<Mesh, ParametricError> {
+        surface(
+            |u, v| {
+                let (theta, phi) = (2.0 * PI * u, PI * v);
+                Vec3::new([phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin()])
+            },
+            u_steps, v_steps, Seam::Closed, Seam::Open,
+        )
+    }
+
+    // A ring around the y axis; u goes around the ring, v around the tube.
+    pub fn mk_torus(major_radius: Element, minor_radius: Element, u_steps: usize, v_steps: usize) -> Result<Mesh, ParametricError> {
+        surface(
+            |u, v| {
+                let (theta, phi) = (2.0 * PI * u, 2.0 * PI * v);
+                let r = major_radius + minor_radius * phi.cos();
+                Vec3::new([r * theta.cos(), minor_radius * phi.sin(), r * theta.sin()])
+            },
+            u_steps, v_steps, Seam::Closed, Seam::Closed,
+        )
+    }
+
+    // An open tube along the y axis, centred on the origin; u goes around, v along.
+    pub fn mk_cylinder(radius: Element, height: Element, u_steps: usize, v_steps: usize) -> Result<Mesh, ParametricError> {
+        surface(
+            |u, v| {
+                let theta = 2.0 * PI * u;
+                Vec3::new([radius * theta.cos(), height * (v - 0.5), radius * theta.sin()])
+            },
+            u_steps, v_steps, Seam::Closed, Seam::Open,
+        )
+    }
+
+    // A cone with its base (left open) centred on the origin and its tip up the y axis; u goes
+    // around, v from the base to the tip.
+    pub fn mk_cone(radius: Element, height: Element, u_steps: usize, v_steps: usize) -> Result<Mesh, ParametricError> {
+        surface(
+            |u, v| {
+                let theta = 2.0 * PI * u;
+                let r = radius * (1.0 - v);
+                Vec3::new([r * theta.cos(), height * v, r * theta.sin()])
+            },
+            u_steps, v_steps, Seam::Closed, Seam::Open,
+        )
+    }
+
+    // A Möbius strip around a unit circle; u goes around, v across the `width` of the strip.
+    pub fn mk_mobius(width: Element, u_steps: usize, v_steps: usize) -> Result<Mesh, ParametricError> {
+        surface(
+            |u, v| {
+                let theta = 2.0 * PI * u;
+                let s = width * (v - 0.5);
+                let r = 1.0 + s * (theta / 2.0).cos();
+                Vec3::new([r * theta.cos(), s * (theta / 2.0).sin(), r * theta.sin()])
+            },
+            u_steps, v_steps, Seam::Twisted, Seam::Open,
+        )
+    }
+
+    // The "figure 8" immersion of a Klein bottle (it has to pass through itself in 3D): a figure 8
+    // swept around a circle of `radius`, turning half over on the way.
+    pub fn mk_klein_bottle(radius: Element, u_steps: usize, v_steps: usize) -> Result<Mesh, ParametricError> {
+        surface(
+            |u, v| {
+                let (theta, phi) = (2.0 * PI * u, 2.0 * PI * v);
+                let (half_sin, half_cos) = (theta / 2.0).sin_cos();
+                let r = radius + half_cos * phi.sin() - half_sin * (2.0 * phi).sin();
+                let y = half_sin * phi.sin() + half_cos * (2.0 * phi).sin();
+                Vec3::new([r * theta.cos(), y, r * theta.sin()])
+            },
+            u_steps, v_steps, Seam::Twisted, Seam::Closed,
+        )
+    }
+
+    // z = f(x, y) over the square from (-1, -1) to (1, 1). (there's no way to write down `f` in a
+    // scene file)
+    pub fn mk_height_field<F>(f: F, x_steps: usize, y_steps: usize) -> Result<Mesh, ParametricError>
+    where
+        F: Fn(Element, Element) -> Element,
+    {
+        surface(
+            |u, v| {
+                let (x, y) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+                Vec3::new([x, y, f(x, y)])
+            },
+            x_steps, y_steps, Seam::Open, Seam::Open,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (vertices, lines, faces)
+    fn counts(mesh: &Mesh) -> (usize, usize, usize) {
+        (mesh.vertices.len(), mesh.lines.len(), mesh.faces.len())
+    }
+
+    #[test]
+    fn closed_surfaces() {
+        let sphere = Mesh::mk_uv_sphere(8, 4).unwrap();
+        assert_eq!(counts(&sphere), (26, 56, 32));
+        assert_eq!(sphere.euler_characteristic(), 2);
+
+        let torus = Mesh::mk_torus(1.0, 0.25, 8, 4).unwrap();
+        assert_eq!(counts(&torus), (32, 64, 32));
+        assert_eq!(torus.euler_characteristic(), 0);
+
+        // can't be wound consistently, so never counts as closed or manifold here
+        let mut klein = Mesh::mk_klein_bottle(2.0, 8, 4).unwrap();
+        assert_eq!(counts(&klein), (32, 64, 32));
+        assert!(!klein.orient_faces());
+
+        for mesh in [sphere, torus] {
+            assert!(mesh.is_closed());
+            assert!(mesh.is_manifold());
+        }
+    }
+
+    #[test]
+    fn open_surfaces() {
+        let cylinder = Mesh::mk_cylinder(1.0, 2.0, 8, 2).unwrap();
+        assert_eq!(counts(&cylinder), (24, 40, 16));
+
+        let cone = Mesh::mk_cone(1.0, 2.0, 8, 2).unwrap();
+        assert_eq!(counts(&cone), (17, 32, 16));
+
+        let mut mobius = Mesh::mk_mobius(0.5, 8, 2).unwrap();
+        assert_eq!(counts(&mobius), (24, 40, 16));
+        assert!(!mobius.orient_faces());
+
+        let field = Mesh::mk_height_field(|x, y| x * y, 4, 4).unwrap();
+        assert_eq!(counts(&field), (25, 40, 16));
+
+        for mesh in [cylinder, cone, field] {
+            assert!(!mesh.is_closed());
+            assert!(mesh.is_manifold());
+        }
+    }
+
+    #[test]
+    fn bad_arguments() {
+        let flat = |u: Element, v: Element| Vec3::new([u, v, 0.0]);
+        assert!(surface(flat, 4, 4, Seam::Twisted, Seam::Twisted).is_err());
+        assert!(surface(flat, 2, 4, Seam::Closed, Seam::Open).is_err());
+        assert!(surface(flat, 4, 0, Seam::Open, Seam::Open).is_err());
+        assert!(Mesh::mk_torus(1.0, 0.25, 8, 2).is_err());
+        assert!(surface(flat, 1, 1, Seam::Open, Seam::Open).is_ok());
+    }
+
+    #[test]
+    fn surfaces_by_name() {
+        let names = ["uv_sphere", "torus", "cylinder", "cone", "mobius", "klein_bottle", "ripple"];
+        for (name, surface) in names.into_iter().zip(Surface::ALL) {
+            assert_eq!(Surface::from_name(name), Some(surface));
+            assert!(Mesh::mk_surface(surface).is_ok());
+        }
+        assert_eq!(Surface::from_name("teapot"), None);
+    }
+}
@@ -0,0 +1,390 @@
+// PLY (Stanford polygon format) import. We read the `vertex` element's x/y/z and the `face`
+// element's index lists, in any of the ascii, binary_little_endian or binary_big_endian encodings,
+// and skip over every other element and property. Faces with more than three corners are fanned
+// into triangles; the fan diagonals are coplanar so `soup` throws them away again.
+//
+// http://paulbourke.net/dataformats/ply/
+
+
+use crate::error::message_error;
+use crate::mesh::Mesh;
+use crate::point::{Element, Vec3};
+use crate::soup::TriangleSoup;
+
+message_error!(PlyError, "PLY");
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Result<Self, PlyError> {
+        Ok(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return error(format!("unknown property type {:?}", name)),
+        })
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Property {
+    Scalar(Scalar, String),
+    // count type, item type, name
+    List(Scalar, Scalar, String),
+}
+
+#[derive(Clone, Debug)]
+struct ElementDecl {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<ElementDecl>,
+}
+
+fn parse_header(text: &str) -> Result<Header, PlyError> {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("ply") {
+        return error("missing the `ply` magic number".to_string());
+    }
+
+    let mut format = None;
+    let mut elements = Vec::<ElementDecl>::new();
+    for (i, line) in lines.enumerate() {
+        // the magic number was line 1
+        let number = i + 2;
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        match words.as_slice() {
+            ["format", encoding, "1.0"] => {
+                format = Some(match *encoding {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::LittleEndian,
+                    "binary_big_endian" => Format::BigEndian,
+                    _ => return error(format!("line {}: unknown format {:?}", number, encoding)),
+                });
+            },
+            ["element", name, count] => {
+                let count = match count.parse::<usize>() {
+                    Ok(count) => count,
+                    Err(_) => return error(format!("line {}: element count {:?} is not a number", number, count)),
+                };
+                elements.push(ElementDecl {
+                    name: name.to_string(),
+                    count,
+                    properties: vec![],
+                });
+            },
+            ["property", "list", count, item, name] => {
+                let property = Property::List(Scalar::parse(count)?, Scalar::parse(item)?, name.to_string());
+                match elements.last_mut() {
+                    Some(element) => element.properties.push(property),
+                    None => return error(format!("line {}: property before any element", number)),
+                }
+            },
+            ["property", ty, name] => {
+                let property = Property::Scalar(Scalar::parse(ty)?, name.to_string());
+                match elements.last_mut() {
+                    Some(element) => element.properties.push(property),
+                    None => return error(format!("line {}: property before any element", number)),
+                }
+            },
+            ["comment", ..] | ["obj_info", ..] | [] => {},
+            ["end_header"] => break,
+            _ => return error(format!("line {}: unexpected header line {:?}", number, line)),
+        }
+    }
+
+    match format {
+        Some(format) => Ok(Header {
+            format,
+            elements,
+        }),
+        None => error("header has no `format` line".to_string()),
+    }
+}
+
+// Where the values after the header come from. ASCII bodies are just whitespace separated tokens
+// (we don't care which line they're on) while binary bodies are packed in declaration order.
+enum Body<'a> {
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary {
+        bytes: &'a [u8],
+        at: usize,
+        big_endian: bool,
+    },
+}
+
+impl Body<'_> {
+    fn read(&mut self, ty: Scalar) -> Result<Element, PlyError> {
+        match self {
+            Self::Ascii(words) => {
+                let word = match words.next() {
+                    Some(word) => word,
+                    None => return error("body ends early".to_string()),
+                };
+                match word.parse::<Element>() {
+                    Ok(x) => Ok(x),
+                    Err(_) => error(format!("value {:?} is not a number", word)),
+                }
+            },
+            Self::Binary { bytes, at, big_endian } => {
+                let size = ty.size();
+                if *at + size > bytes.len() {
+                    return error("body ends early".to_string());
+                }
+
+                let mut raw = [0u8; 8];
+                raw[..size].copy_from_slice(&bytes[*at..*at + size]);
+                if *big_endian {
+                    raw[..size].reverse();
+                }
+                *at += size;
+
+                // `raw` is now little-endian
+                Ok(match ty {
+                    Scalar::I8 => i8::from_le_bytes([raw[0]]) as Element,
+                    Scalar::U8 => raw[0] as Element,
+                    Scalar::I16 => i16::from_le_bytes([raw[0], raw[1]]) as Element,
+                    Scalar::U16 => u16::from_le_bytes([raw[0], raw[1]]) as Element,
+                    Scalar::I32 => i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as Element,
+                    Scalar::U32 => u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as Element,
+                    Scalar::F32 => f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as Element,
+                    Scalar::F64 => f64::from_le_bytes(raw),
+                })
+            },
+        }
+    }
+}
+
+fn index(value: Element, vertex_count: usize, face: usize) -> Result<usize, PlyError> {
+    if value < 0.0 || value.fract() != 0.0 || value as usize >= vertex_count {
+        return error(format!("face {} refers to vertex {} but there are {} vertices", face, value, vertex_count));
+    }
+
+    Ok(value as usize)
+}
+
+impl Mesh {
+    pub fn from_ply(bytes: &[u8], crease_angle: Element) -> Result<Mesh, PlyError> {
+        const END: &[u8] = b"end_header";
+        let end = match bytes.windows(END.len()).position(|window| window == END) {
+            Some(end) => end,
+            None => return error("header has no `end_header` line".to_string()),
+        };
+        // the body starts after the newline that ends `end_header`
+        let body_start = match bytes[end..].iter().position(|&b| b == b'\n') {
+            Some(newline) => end + newline + 1,
+            None => bytes.len(),
+        };
+        let header = match std::str::from_utf8(&bytes[..body_start]) {
+            Ok(text) => parse_header(text)?,
+            Err(_) => return error("header is not valid text".to_string()),
+        };
+
+        let mut body = match header.format {
+            Format::Ascii => match std::str::from_utf8(&bytes[body_start..]) {
+                Ok(text) => Body::Ascii(text.split_whitespace()),
+                Err(_) => return error("ASCII body is not valid text".to_string()),
+            },
+            Format::LittleEndian | Format::BigEndian => Body::Binary {
+                bytes: &bytes[body_start..],
+                at: 0,
+                big_endian: header.format == Format::BigEndian,
+            },
+        };
+
+        let mut soup = TriangleSoup::new();
+        let mut seen_vertices = false;
+        for element in header.elements.iter() {
+            let is_vertex = element.name == "vertex";
+            let is_face = element.name == "face";
+            if is_vertex {
+                for axis in ["x", "y", "z"] {
+                    if !element.properties.iter().any(|p| matches!(p, Property::Scalar(_, name) if name == axis)) {
+                        return error(format!("vertex element has no {} property", axis));
+                    }
+                }
+                seen_vertices = true;
+            }
+            if is_face && !seen_vertices {
+                return error("faces come before vertices".to_string());
+            }
+
+            for n in 0..element.count {
+                let mut coord = [0.0; 3];
+                for property in element.properties.iter() {
+                    match property {
+                        Property::Scalar(ty, name) => {
+                            let value = body.read(*ty)?;
+                            if is_vertex {
+                                match name.as_str() {
+                                    "x" => coord[0] = value,
+                                    "y" => coord[1] = value,
+                                    "z" => coord[2] = value,
+                                    _ => {},
+                                }
+                            }
+                        },
+                        Property::List(count_ty, item_ty, name) => {
+                            let count = body.read(*count_ty)?;
+                            if count < 0.0 || count.fract() != 0.0 {
+                                return error(format!("{} {} has a list of length {}", element.name, n, count));
+                            }
+                            let items = (0..count as usize)
+                                .map(|_| body.read(*item_ty))
+                                .collect::<Result<Vec<Element>, PlyError>>()?;
+
+                            let is_corners = name == "vertex_indices" || name == "vertex_index";
+                            if is_face && is_corners {
+                                if items.len() < 3 {
+                                    return error(format!("face {} has only {} corners", n, items.len()));
+                                }
+                                let corners = items.iter()
+                                    .map(|&i| index(i, soup.vertices.len(), n))
+                                    .collect::<Result<Vec<usize>, PlyError>>()?;
+                                for pair in corners[1..].windows(2) {
+                                    soup.triangles.push([corners[0], pair[0], pair[1]]);
+                                }
+                            }
+                        },
+                    }
+                }
+
+                if is_vertex {
+                    if !coord.iter().all(|x| x.is_finite()) {
+                        return error(format!("vertex {} has a non-finite coordinate", n));
+                    }
+                    soup.vertices.push(Vec3::new(coord));
+                }
+            }
+        }
+
+        if soup.triangles.is_empty() {
+            return error("no faces found".to_string());
+        }
+
+        soup.feature_edges(crease_angle)
+            .or_else(|e| error(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::soup::DEFAULT_CREASE_ANGLE;
+
+    // A cube with quad faces, a colour on every vertex and an `edge` element at the end, none of
+    // which we use.
+    fn header(format: &str) -> String {
+        let cube = Mesh::mk_cube();
+        format!(
+            "ply\nformat {} 1.0\ncomment made by hand\n\
+             element vertex {}\nproperty float x\nproperty float y\nproperty float z\nproperty uchar red\n\
+             element face {}\nproperty list uchar int vertex_indices\n\
+             element edge 1\nproperty int vertex1\nproperty int vertex2\nend_header\n",
+            format, cube.vertices.len(), cube.faces.len())
+    }
+
+    fn ascii() -> Vec<u8> {
+        let cube = Mesh::mk_cube();
+        let mut text = header("ascii");
+        for v in cube.vertices.iter() {
+            text.push_str(&format!("{} {} {} 255\n", v.x(), v.y(), v.z()));
+        }
+        for face in cube.faces.iter() {
+            let corners = face.iter().map(|i| i.to_string()).collect::<Vec<String>>();
+            text.push_str(&format!("{} {}\n", face.len(), corners.join(" ")));
+        }
+        text.push_str("0 1\n");
+        text.into_bytes()
+    }
+
+    fn binary(big_endian: bool) -> Vec<u8> {
+        let cube = Mesh::mk_cube();
+        let mut bytes = header(if big_endian { "binary_big_endian" } else { "binary_little_endian" }).into_bytes();
+        let int = |bytes: &mut Vec<u8>, x: i32| bytes.extend_from_slice(&if big_endian { x.to_be_bytes() } else { x.to_le_bytes() });
+        for v in cube.vertices.iter() {
+            for x in v.coord {
+                let x = x as f32;
+                bytes.extend_from_slice(&if big_endian { x.to_be_bytes() } else { x.to_le_bytes() });
+            }
+            bytes.push(255);
+        }
+        for face in cube.faces.iter() {
+            bytes.push(face.len() as u8);
+            for &i in face.iter() {
+                int(&mut bytes, i as i32);
+            }
+        }
+        int(&mut bytes, 0);
+        int(&mut bytes, 1);
+        bytes
+    }
+
+    #[test]
+    fn every_encoding() {
+        let cube = Mesh::mk_cube();
+        for bytes in [ascii(), binary(false), binary(true)] {
+            let mesh = Mesh::from_ply(&bytes, DEFAULT_CREASE_ANGLE).unwrap();
+            assert_eq!(mesh.vertices.len(), 8);
+            assert_eq!(mesh.lines.len(), 12);
+            assert!(mesh.vertices.iter().all(|v| cube.vertices.contains(v)));
+        }
+    }
+
+    #[test]
+    fn bad_files() {
+        let message = |text: &str| Mesh::from_ply(text.as_bytes(), DEFAULT_CREASE_ANGLE).unwrap_err().message;
+
+        assert_eq!(message("PLY\nend_header\n"), "missing the `ply` magic number");
+        assert_eq!(message("ply\nformat ascii 1.0\n"), "header has no `end_header` line");
+        assert_eq!(message("ply\nelement vertex 0\nend_header\n"), "header has no `format` line");
+        assert_eq!(message("ply\nformat utf8 1.0\nend_header\n"), "line 2: unknown format \"utf8\"");
+        assert_eq!(message("ply\nformat ascii 1.0\nproperty float x\nend_header\n"), "line 3: property before any element");
+        assert_eq!(message("ply\nformat ascii 1.0\nelement vertex lots\nend_header\n"), "line 3: element count \"lots\" is not a number");
+
+        let vertices = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+                        element face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0 1 0 0 0 1 0\n";
+        assert_eq!(message(&format!("{}3 0 1 3\n", vertices)), "face 0 refers to vertex 3 but there are 3 vertices");
+        assert_eq!(message(&format!("{}2 0 1\n", vertices)), "face 0 has only 2 corners");
+        assert_eq!(message(&format!("{}3 0 1\n", vertices)), "body ends early");
+        assert_eq!(message(&format!("{}3 0 1 two\n", vertices)), "value \"two\" is not a number");
+
+        assert_eq!(message("ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\n0\n"), "vertex element has no y property");
+    }
+}
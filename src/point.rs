@@ -3,6 +3,10 @@
 use std::ops::Sub;
 use std::ops::Mul;
 use std::ops::Add;
+use std::ops::Div;
+use std::ops::Neg;
+use std::ops::{AddAssign, DivAssign, MulAssign, SubAssign};
+use std::ops::{Index, IndexMut};
 
 pub type Element = f64;
 
@@ -40,15 +44,7 @@ impl<const N: usize> Point<N> {
     }
 
     pub fn scale(&self, scalar: Element) -> Self {
-        let calculated = self.coord.iter()
-            .map(|x| {
-                x * scalar
-            })
-            .collect::<Vec<Element>>()
-            .try_into()
-            .expect("iterating over a fixed-size array should yield a fixed-size array");
-
-        Self::new(calculated)
+        Self::new(self.coord.map(|x| x * scalar))
     }
 
     pub fn normal(&self) -> Self {
@@ -60,6 +56,17 @@ impl<const N: usize> Point<N> {
         self.scale(1.0 / magnitude)
     }
 
+    pub fn distance(&self, other: &Self) -> Element {
+        (*self - *other).magnitude()
+    }
+
+    // Whether every coordinate is within `epsilon` of the other's.
+    pub fn approx_eq(&self, other: &Self, epsilon: Element) -> bool {
+        self.coord.iter()
+            .zip(other.coord.iter())
+            .all(|(a, b)| (a - b).abs() <= epsilon)
+    }
+
     pub fn dot(&self, other: &Self) -> Element {
         let calculated = self.coord.iter()
             .zip(other.coord.iter())
@@ -72,16 +79,23 @@ impl<const N: usize> Point<N> {
 
     pub fn cross(&self, other: &Self) -> Self {
         let max = N;
-        let calculated = (0..max)
-            .map(|i| {
-                (self.coord[(i + 1) % max] * other.coord[(i + 2) % max]) +
-                (-self.coord[(i + 2) % max] * other.coord[(i + 1) % max])
-            })
-            .collect::<Vec<Element>>()
-            .try_into()
-            .expect("iterating over a fixed-size array should yield a fixed-size array");
+        Self::new(std::array::from_fn(|i| {
+            (self.coord[(i + 1) % max] * other.coord[(i + 2) % max]) +
+            (-self.coord[(i + 2) % max] * other.coord[(i + 1) % max])
+        }))
+    }
+
+    // The point (as a row) times `m`. Unlike `Vec3::transform_coordinates` there's no extra w
+    // coordinate, so this can rotate and scale but not move or add perspective.
+    pub fn transform(&self, m: &Matrix<N>) -> Self {
+        let mut out = Self::zeroes();
+        for (x, row) in self.coord.iter().zip(m.raw.iter()) {
+            for (acc, y) in out.coord.iter_mut().zip(row.iter()) {
+                *acc += x * y;
+            }
+        }
 
-        Self::new(calculated)
+        out
     }
 }
 
@@ -141,6 +155,18 @@ impl Vec3 {
 
         Vec3::new([x/w, y/w, z/w])
     }
+
+    // Undo `Vec2::project`: where a point on the screen at `depth` (0 on the near plane, 1 on the
+    // far one) came from. Unprojecting a pixel at both depths gives the ray through it, for
+    // picking. `None` if `trans` squashes everything flat.
+    pub fn unproject(dims: &Vec2, screen: &Vec2, depth: Element, trans: &Mat4) -> Option<Vec3> {
+        let width = dims.x();
+        let height = dims.y();
+        let x = (screen.x() - width / 2.0) / width;
+        let y = -(screen.y() - height / 2.0) / height;
+
+        trans.inverse().map(|inverse| Vec3::transform_coordinates(&Vec3::new([x, y, depth]), &inverse))
+    }
 }
 
 impl Vec4 {
@@ -165,16 +191,7 @@ impl<const N: usize> Add<Point<N>> for Point<N> {
     type Output = Self;
 
     fn add(self, other: Self) -> Self::Output {
-        let calculated = self.coord.iter()
-            .zip(other.coord.iter())
-            .map(|(lhs, rhs)| {
-                lhs + rhs
-            })
-            .collect::<Vec<Element>>()
-            .try_into()
-            .expect("iterating over a fixed-size array should yield a fixed-size array");
-
-        Self::new(calculated)
+        Self::new(std::array::from_fn(|i| self.coord[i] + other.coord[i]))
     }
 }
 
@@ -182,16 +199,84 @@ impl<const N: usize> Sub<Point<N>> for Point<N> {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self::Output {
-        let calculated = self.coord.iter()
-            .zip(other.coord.iter())
-            .map(|(lhs, rhs)| {
-                lhs - rhs
-            })
-            .collect::<Vec<Element>>()
-            .try_into()
-            .expect("iterating over a fixed-size array should yield a fixed-size array");
+        Self::new(std::array::from_fn(|i| self.coord[i] - other.coord[i]))
+    }
+}
+
+impl<const N: usize> Neg for Point<N> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self.scale(-1.0)
+    }
+}
+
+impl<const N: usize> Mul<Element> for Point<N> {
+    type Output = Self;
+
+    fn mul(self, scalar: Element) -> Self::Output {
+        self.scale(scalar)
+    }
+}
+
+impl<const N: usize> Div<Element> for Point<N> {
+    type Output = Self;
+
+    fn div(self, scalar: Element) -> Self::Output {
+        self.scale(1.0 / scalar)
+    }
+}
+
+// The point as a row times `m`, the same as `transform`.
+impl<const N: usize> Mul<Matrix<N>> for Point<N> {
+    type Output = Self;
+
+    fn mul(self, m: Matrix<N>) -> Self::Output {
+        self.transform(&m)
+    }
+}
+
+impl<const N: usize> AddAssign<Point<N>> for Point<N> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const N: usize> SubAssign<Point<N>> for Point<N> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<const N: usize> MulAssign<Element> for Point<N> {
+    fn mul_assign(&mut self, scalar: Element) {
+        *self = self.scale(scalar);
+    }
+}
+
+impl<const N: usize> DivAssign<Element> for Point<N> {
+    fn div_assign(&mut self, scalar: Element) {
+        *self = self.scale(1.0 / scalar);
+    }
+}
+
+impl<const N: usize> MulAssign<Matrix<N>> for Point<N> {
+    fn mul_assign(&mut self, m: Matrix<N>) {
+        *self = self.transform(&m);
+    }
+}
+
+impl<const N: usize> Index<usize> for Point<N> {
+    type Output = Element;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.coord[i]
+    }
+}
 
-        Self::new(calculated)
+impl<const N: usize> IndexMut<usize> for Point<N> {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        &mut self.coord[i]
     }
 }
 
@@ -232,13 +317,124 @@ impl<const N: usize> Matrix<N> {
 
         z
     }
+
+    pub fn at(&self, row: usize, col: usize) -> Element {
+        self.raw[row][col]
+    }
+
+    pub fn row(&self, row: usize) -> Point<N> {
+        Point::new(self.raw[row])
+    }
+
+    pub fn column(&self, col: usize) -> Point<N> {
+        Point::new(std::array::from_fn(|row| self.raw[row][col]))
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self::new(std::array::from_fn(|row| std::array::from_fn(|col| self.raw[col][row])))
+    }
+
+    pub fn trace(&self) -> Element {
+        (0..N).map(|i| self.raw[i][i]).sum()
+    }
+
+    // By elimination down to a triangle, so it's fine for any size (cofactors blow up quickly).
+    pub fn determinant(&self) -> Element {
+        let mut m = self.raw;
+        let mut determinant = 1.0;
+        for col in 0..N {
+            let pivot = (col..N)
+                .max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))
+                .expect("there's at least the one row");
+            if m[pivot][col] == 0.0 {
+                return 0.0;
+            }
+            if pivot != col {
+                m.swap(pivot, col);
+                determinant = -determinant;
+            }
+            determinant *= m[col][col];
+            let pivot_row = m[col];
+            for row in m[(col + 1)..].iter_mut() {
+                let factor = row[col] / pivot_row[col];
+                for (x, p) in row.iter_mut().zip(pivot_row.iter()).skip(col) {
+                    *x -= factor * p;
+                }
+            }
+        }
+
+        determinant
+    }
+
+    // Gauss-Jordan with partial pivoting. `None` if the matrix is singular, or close enough to it
+    // (next to its biggest entry) that the answer would be mostly rounding error.
+    pub fn inverse(&self) -> Option<Self> {
+        let largest = self.raw.iter().flatten().fold(0.0, |acc: Element, x| acc.max(x.abs()));
+        let tiny = largest * N as Element * Element::EPSILON;
+
+        let mut m = self.raw;
+        let mut inverse = Self::identity().raw;
+        for col in 0..N {
+            let pivot = (col..N)
+                .max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))
+                .expect("there's at least the one row");
+            if m[pivot][col].abs() <= tiny {
+                return None;
+            }
+            m.swap(pivot, col);
+            inverse.swap(pivot, col);
+
+            let scale = 1.0 / m[col][col];
+            for k in 0..N {
+                m[col][k] *= scale;
+                inverse[col][k] *= scale;
+            }
+            for row in (0..N).filter(|&row| row != col) {
+                let factor = m[row][col];
+                if factor == 0.0 {
+                    continue;
+                }
+                for k in 0..N {
+                    m[row][k] -= factor * m[col][k];
+                    inverse[row][k] -= factor * inverse[col][k];
+                }
+            }
+        }
+
+        Some(Self::new(inverse))
+    }
+
+    pub fn scale(&self, scalar: Element) -> Self {
+        Self::new(self.raw.map(|row| row.map(|x| x * scalar)))
+    }
+
+    // Whether every entry is within `epsilon` of the other's.
+    pub fn approx_eq(&self, other: &Self, epsilon: Element) -> bool {
+        self.raw.iter()
+            .flatten()
+            .zip(other.raw.iter().flatten())
+            .all(|(a, b)| (a - b).abs() <= epsilon)
+    }
+
+    // Turn `angle` radians in the plane of axes `a` and `b`, from `a` towards `b`. In 3D that's
+    // the usual rotation about the third axis, in 4D there are six planes to pick from.
+    pub fn rotation_in_plane(a: usize, b: usize, angle: Element) -> Self {
+        let mut m = Self::identity();
+        let (sin, cos) = angle.sin_cos();
+        m.raw[a][a] = cos;
+        m.raw[a][b] = sin;
+        m.raw[b][a] = -sin;
+        m.raw[b][b] = cos;
+
+        m
+    }
 }
 
 impl Mat4 {
     // https://learn.microsoft.com/en-us/previous-versions/windows/desktop/bb153147(v=vs.85)
     // Matrix is 4x4
     pub fn look_at_lh(camera_position: Vec3, camera_target: Vec3, camera_up_vector: Vec3) -> Mat4 {
-        let zaxis = (camera_target.clone() - camera_position.clone()).normal();
+        let zaxis = (camera_target - camera_position).normal();
         let xaxis = camera_up_vector.cross(&zaxis).normal();
         let yaxis = zaxis.cross(&xaxis);
 
@@ -301,6 +497,37 @@ impl Mat4 {
         rotation_z*rotation_x*rotation_y
     }
 
+    // The inverse of `rotation_yaw_pitch_roll`, returned as (yaw, pitch, roll). Only the upper 3x3
+    // is looked at and it had better be a pure rotation. Working the product out by hand:
+    //
+    //  [ ca*cc + sa*sb*sc,     sa*cb,  -ca*sc + sa*sb*cc ]
+    //  [ -sa*cc + ca*sb*sc,    ca*cb,  sa*sc + ca*sb*cc  ]
+    //  [ cb*sc,                -sb,    cb*cc             ]
+    //
+    // with a = yaw, b = roll and c = pitch.
+    pub fn yaw_pitch_roll(&self) -> (Element, Element, Element) {
+        let m = &self.raw;
+        let roll = (-m[2][1]).clamp(-1.0, 1.0).asin();
+        if roll.cos().abs() > 1e-9 {
+            let yaw = m[0][1].atan2(m[1][1]);
+            let pitch = m[2][0].atan2(m[2][2]);
+            (yaw, pitch, roll)
+        } else {
+            // gimbal lock: yaw and pitch spin about the same axis so pin pitch at zero
+            let yaw = (-m[1][0]).atan2(m[0][0]);
+            (yaw, 0.0, roll)
+        }
+    }
+
+    pub fn scaling(x: Element, y: Element, z: Element) -> Mat4 {
+        Mat4::new([
+              [x, 0.0, 0.0, 0.0],
+              [0.0, y, 0.0, 0.0],
+              [0.0, 0.0, z, 0.0],
+              [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
     pub fn translation(x: Element, y: Element, z: Element) -> Mat4 {
         Mat4::new([
               [1.0, 0.0, 0.0, 0.0],
@@ -314,28 +541,696 @@ impl Mat4 {
 impl<const N: usize> Mul<Matrix<N>> for Matrix<N> {
     type Output = Self;
 
+    // Row i of the product is row i of `self` times `other`: a sum of whole rows of `other`,
+    // which the compiler can do a few lanes at a time.
+    fn mul(self, other: Self) -> Self::Output {
+        let mut out = Self::zeroes();
+        for (out_row, row) in out.raw.iter_mut().zip(self.raw.iter()) {
+            for (x, other_row) in row.iter().zip(other.raw.iter()) {
+                for (acc, y) in out_row.iter_mut().zip(other_row.iter()) {
+                    *acc += x * y;
+                }
+            }
+        }
+
+        out
+    }
+}
+
+impl<const N: usize> Add<Matrix<N>> for Matrix<N> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        Self::new(std::array::from_fn(|row| std::array::from_fn(|col| self.raw[row][col] + other.raw[row][col])))
+    }
+}
+
+impl<const N: usize> Sub<Matrix<N>> for Matrix<N> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        Self::new(std::array::from_fn(|row| std::array::from_fn(|col| self.raw[row][col] - other.raw[row][col])))
+    }
+}
+
+impl<const N: usize> Neg for Matrix<N> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self.scale(-1.0)
+    }
+}
+
+impl<const N: usize> Mul<Element> for Matrix<N> {
+    type Output = Self;
+
+    fn mul(self, scalar: Element) -> Self::Output {
+        self.scale(scalar)
+    }
+}
+
+impl<const N: usize> Div<Element> for Matrix<N> {
+    type Output = Self;
+
+    fn div(self, scalar: Element) -> Self::Output {
+        self.scale(1.0 / scalar)
+    }
+}
+
+// `m` times the point as a column, which is the point as a row times `m.transpose()`. Everything
+// else here uses rows, so this is only for formulas written the other way round.
+impl<const N: usize> Mul<Point<N>> for Matrix<N> {
+    type Output = Point<N>;
+
+    fn mul(self, v: Point<N>) -> Self::Output {
+        Point::new(std::array::from_fn(|row| self.row(row).dot(&v)))
+    }
+}
+
+impl<const N: usize> AddAssign<Matrix<N>> for Matrix<N> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const N: usize> SubAssign<Matrix<N>> for Matrix<N> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<const N: usize> MulAssign<Matrix<N>> for Matrix<N> {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl<const N: usize> MulAssign<Element> for Matrix<N> {
+    fn mul_assign(&mut self, scalar: Element) {
+        *self = self.scale(scalar);
+    }
+}
+
+impl<const N: usize> DivAssign<Element> for Matrix<N> {
+    fn div_assign(&mut self, scalar: Element) {
+        *self = self.scale(1.0 / scalar);
+    }
+}
+
+// `m[(row, col)]`
+impl<const N: usize> Index<(usize, usize)> for Matrix<N> {
+    type Output = Element;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        &self.raw[row][col]
+    }
+}
+
+impl<const N: usize> IndexMut<(usize, usize)> for Matrix<N> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+        &mut self.raw[row][col]
+    }
+}
+
+// A rotation as a unit quaternion: turning by `angle` about `axis` is
+// (axis * sin(angle / 2), cos(angle / 2)). Unlike Euler angles they don't gimbal lock and turning
+// about any axis is just as easy as about x, y or z.
+//
+// `a * b` is the usual Hamilton product, which turns by `b` and then by `a`. Our matrices work on
+// row vectors so as matrices that's `b.to_mat4() * a.to_mat4()`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quaternion {
+    pub x: Element,
+    pub y: Element,
+    pub z: Element,
+    pub w: Element,
+}
+
+impl Quaternion {
+    pub fn new(x: Element, y: Element, z: Element, w: Element) -> Self {
+        Self { x, y, z, w }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    // A zero `axis` doesn't turn at all.
+    pub fn from_axis_angle(axis: &Vec3, angle: Element) -> Self {
+        if axis.magnitude() == 0.0 {
+            return Self::identity();
+        }
+        let axis = axis.normal();
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Self::new(axis.x() * sin, axis.y() * sin, axis.z() * sin, cos)
+    }
+
+    // The inverse of `from_axis_angle`, with the angle from 0 to 2π. No turn at all comes back
+    // as the x axis.
+    pub fn axis_angle(&self) -> (Vec3, Element) {
+        let q = self.normal();
+        let angle = 2.0 * q.w.clamp(-1.0, 1.0).acos();
+        let axis = Vec3::new([q.x, q.y, q.z]);
+        if axis.magnitude() == 0.0 {
+            return (Vec3::new([1.0, 0.0, 0.0]), 0.0);
+        }
+
+        (axis.normal(), angle)
+    }
+
+    // The same turn as `Mat4::rotation_yaw_pitch_roll`.
+    pub fn from_yaw_pitch_roll(yaw: Element, pitch: Element, roll: Element) -> Self {
+        Self::from_mat4(&Mat4::rotation_yaw_pitch_roll(yaw, pitch, roll))
+    }
+
+    // (yaw, pitch, roll), see `Mat4::yaw_pitch_roll`.
+    pub fn yaw_pitch_roll(&self) -> (Element, Element, Element) {
+        self.to_mat4().yaw_pitch_roll()
+    }
+
+    pub fn magnitude(&self) -> Element {
+        self.dot(self).sqrt()
+    }
+
+    // Rounding creeps in as turns pile up, so every so often they need pulling back to unit length.
+    pub fn normal(&self) -> Self {
+        let magnitude = self.magnitude();
+        if magnitude == 0.0 {
+            return Self::identity();
+        }
+
+        self.scale(1.0 / magnitude)
+    }
+
+    // The opposite turn (for a unit quaternion).
+    pub fn conjugate(&self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn dot(&self, other: &Self) -> Element {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    fn scale(&self, scalar: Element) -> Self {
+        Self::new(self.x * scalar, self.y * scalar, self.z * scalar, self.w * scalar)
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y, self.z + other.z, self.w + other.w)
+    }
+
+    pub fn rotate(&self, v: &Vec3) -> Vec3 {
+        let turned = *self * Self::new(v.x(), v.y(), v.z(), 0.0) * self.conjugate();
+        Vec3::new([turned.x, turned.y, turned.z])
+    }
+
+    // A row vector times this turns it the same way `rotate` does.
+    pub fn to_mat4(self) -> Mat4 {
+        let Self { x, y, z, w } = self.normal();
+        Mat4::new([
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y + z * w), 2.0 * (x * z - y * w), 0.0],
+            [2.0 * (x * y - z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z + x * w), 0.0],
+            [2.0 * (x * z + y * w), 2.0 * (y * z - x * w), 1.0 - 2.0 * (x * x + y * y), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Only the upper 3x3 is looked at and it had better be a pure rotation. (Shepperd's method:
+    // start from whichever of w, x, y and z is biggest so we never divide by something tiny.)
+    pub fn from_mat4(m: &Mat4) -> Self {
+        // `r` is the column vector form, the transpose of ours
+        let r = |row: usize, col: usize| m.raw[col][row];
+        let trace = r(0, 0) + r(1, 1) + r(2, 2);
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Self::new((r(2, 1) - r(1, 2)) / s, (r(0, 2) - r(2, 0)) / s, (r(1, 0) - r(0, 1)) / s, s / 4.0)
+        } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+            let s = (1.0 + r(0, 0) - r(1, 1) - r(2, 2)).sqrt() * 2.0;
+            Self::new(s / 4.0, (r(0, 1) + r(1, 0)) / s, (r(0, 2) + r(2, 0)) / s, (r(2, 1) - r(1, 2)) / s)
+        } else if r(1, 1) > r(2, 2) {
+            let s = (1.0 + r(1, 1) - r(0, 0) - r(2, 2)).sqrt() * 2.0;
+            Self::new((r(0, 1) + r(1, 0)) / s, s / 4.0, (r(1, 2) + r(2, 1)) / s, (r(0, 2) - r(2, 0)) / s)
+        } else {
+            let s = (1.0 + r(2, 2) - r(0, 0) - r(1, 1)).sqrt() * 2.0;
+            Self::new((r(0, 2) + r(2, 0)) / s, (r(1, 2) + r(2, 1)) / s, s / 4.0, (r(1, 0) - r(0, 1)) / s)
+        };
+
+        q.normal()
+    }
+
+    // Straight between the two and back onto unit length: cheap, and close to `slerp` when they're
+    // near each other, but it speeds up in the middle of big turns.
+    pub fn nlerp(&self, other: &Self, t: Element) -> Self {
+        // q and -q are the same turn; go whichever way is shorter
+        let other = if self.dot(other) < 0.0 { other.scale(-1.0) } else { *other };
+        self.scale(1.0 - t).add(&other.scale(t)).normal()
+    }
+
+    // From `self` (at 0) to `other` (at 1) at a steady speed the short way round.
+    pub fn slerp(&self, other: &Self, t: Element) -> Self {
+        let (a, mut b) = (self.normal(), other.normal());
+        let mut cos = a.dot(&b);
+        if cos < 0.0 {
+            b = b.scale(-1.0);
+            cos = -cos;
+        }
+        // nearly the same, where sin(angle) is too small to divide by
+        if cos > 0.9995 {
+            return a.nlerp(&b, t);
+        }
+
+        let angle = cos.acos();
+        let sin = angle.sin();
+        a.scale(((1.0 - t) * angle).sin() / sin)
+            .add(&b.scale((t * angle).sin() / sin))
+            .normal()
+    }
+}
+
+impl Mul<Quaternion> for Quaternion {
+    type Output = Self;
+
     fn mul(self, other: Self) -> Self::Output {
-        let calculated = (0..N)
-            .map(|i| -> [Element; N] {
-                let row = (0..N)
-                    .map(|j| -> Element {
-                        let inner = (0..N)
-                            .fold(0.0, |acc, k| {
-                                acc + (self.raw[i][k] * other.raw[k][j])
-                            });
-
-                        inner
-                    })
-                    .collect::<Vec<Element>>()
-                    .try_into()
-                    .expect("iterating over a fixed-size array should yield a fixed-sized array");
-
-                row
-            })
-            .collect::<Vec<Row::<N>>>()
-            .try_into()
-            .expect("iterating over a fixed-size array should yield a fixed-size array");
-
-        Self::new(calculated)
+        Self::new(
+            self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+            self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+        )
+    }
+}
+
+// serde can't derive for `[T; N]` with a generic `N` so we spell these out by hand. A point is a
+// plain tuple of its coordinates (`[x, y, z]` in JSON, `(x, y, z)` in RON) and a matrix is a
+// tuple of its rows.
+#[cfg(feature = "serde")]
+mod serialization {
+    use std::fmt;
+    use std::marker::PhantomData;
+
+    use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+    use serde::ser::{Serialize, SerializeTuple, Serializer};
+
+    use super::{Element, Matrix, Point};
+
+    impl<const N: usize> Serialize for Point<N> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut tuple = serializer.serialize_tuple(N)?;
+            for x in self.coord.iter() {
+                tuple.serialize_element(x)?;
+            }
+            tuple.end()
+        }
+    }
+
+    struct PointVisitor<const N: usize>;
+
+    impl<'de, const N: usize> Visitor<'de> for PointVisitor<N> {
+        type Value = Point<N>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a point with {} coordinates", N)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut coord = [0.0; N];
+            for (i, x) in coord.iter_mut().enumerate() {
+                *x = seq.next_element::<Element>()?
+                    .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+            }
+            if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                return Err(de::Error::invalid_length(N + 1, &self));
+            }
+
+            Ok(Point::new(coord))
+        }
+    }
+
+    impl<'de, const N: usize> Deserialize<'de> for Point<N> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_tuple(N, PointVisitor::<N>)
+        }
+    }
+
+    impl<const N: usize> Serialize for Matrix<N> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut tuple = serializer.serialize_tuple(N)?;
+            for row in self.raw.iter() {
+                tuple.serialize_element(&Point::new(*row))?;
+            }
+            tuple.end()
+        }
+    }
+
+    struct MatrixVisitor<const N: usize>(PhantomData<Matrix<N>>);
+
+    impl<'de, const N: usize> Visitor<'de> for MatrixVisitor<N> {
+        type Value = Matrix<N>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a {}x{} matrix given as {} rows", N, N, N)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut raw = [[0.0; N]; N];
+            for (i, row) in raw.iter_mut().enumerate() {
+                *row = seq.next_element::<Point<N>>()?
+                    .ok_or_else(|| de::Error::invalid_length(i, &self))?
+                    .coord;
+            }
+            if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                return Err(de::Error::invalid_length(N + 1, &self));
+            }
+
+            Ok(Matrix::new(raw))
+        }
+    }
+
+    impl<'de, const N: usize> Deserialize<'de> for Matrix<N> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_tuple(N, MatrixVisitor::<N>(PhantomData))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::f64::consts::PI;
+
+    const EPSILON: Element = 1e-9;
+
+    // something with a bit of everything in it: turned, stretched, moved and seen in perspective
+    fn busy() -> Mat4 {
+        Mat4::rotation_yaw_pitch_roll(0.3, -1.1, 2.0)
+            * Mat4::scaling(2.0, 0.5, 3.0)
+            * Mat4::translation(1.0, -2.0, 5.0)
+            * Mat4::perspective_fov_lh(0.78, 1.5, 0.01, 1.0)
+    }
+
+    #[test]
+    fn point_arithmetic() {
+        let a = Vec3::new([1.0, -2.0, 3.0]);
+        let b = Vec3::new([0.5, 4.0, -1.0]);
+
+        assert_eq!(-a, Vec3::new([-1.0, 2.0, -3.0]));
+        assert_eq!(a * 2.0, Vec3::new([2.0, -4.0, 6.0]));
+        assert_eq!(a / 2.0, Vec3::new([0.5, -1.0, 1.5]));
+        assert_eq!(a.distance(&b), (a - b).magnitude());
+
+        let mut c = a;
+        c += b;
+        assert_eq!(c, a + b);
+        c -= b;
+        assert_eq!(c, a);
+        c *= 4.0;
+        c /= 2.0;
+        assert_eq!(c, a.scale(2.0));
+    }
+
+    // the sums rewritten to stop allocating, against answers worked out by hand
+    #[test]
+    fn point_sums() {
+        let a = Vec3::new([1.0, -2.0, 3.0]);
+        let b = Vec3::new([0.5, 4.0, -1.0]);
+
+        assert_eq!(a + b, Vec3::new([1.5, 2.0, 2.0]));
+        assert_eq!(a - b, Vec3::new([0.5, -6.0, 4.0]));
+        assert_eq!(a.scale(-0.5), Vec3::new([-0.5, 1.0, -1.5]));
+        assert_eq!(a.dot(&b), -10.5);
+        assert_eq!(Vec2::new([3.0, 4.0]).magnitude(), 5.0);
+        assert!(Vec2::new([3.0, 4.0]).normal().approx_eq(&Vec2::new([0.6, 0.8]), EPSILON));
+        assert_eq!(Vec2::zeroes().normal(), Vec2::zeroes());
+
+        let (x, y, z) = (Vec3::new([1.0, 0.0, 0.0]), Vec3::new([0.0, 1.0, 0.0]), Vec3::new([0.0, 0.0, 1.0]));
+        assert_eq!(x.cross(&y), z);
+        assert_eq!(y.cross(&z), x);
+        assert_eq!(z.cross(&x), y);
+        assert_eq!(y.cross(&x), -z);
+        assert_eq!(Vec3::new([1.0, 2.0, 3.0]).cross(&Vec3::new([4.0, 5.0, 6.0])), Vec3::new([-3.0, 6.0, -3.0]));
+    }
+
+    #[test]
+    fn matrix_sums() {
+        let a = Matrix::<2>::new([[1.0, 2.0], [3.0, 4.0]]);
+        let b = Matrix::<2>::new([[5.0, 6.0], [7.0, 8.0]]);
+
+        assert_eq!(a * b, Matrix::<2>::new([[19.0, 22.0], [43.0, 50.0]]));
+        assert_eq!(b * a, Matrix::<2>::new([[23.0, 34.0], [31.0, 46.0]]));
+        assert_eq!(a + b, Matrix::<2>::new([[6.0, 8.0], [10.0, 12.0]]));
+        assert_eq!(a - b, Matrix::<2>::new([[-4.0, -4.0], [-4.0, -4.0]]));
+        assert_eq!(a.scale(2.0), Matrix::<2>::new([[2.0, 4.0], [6.0, 8.0]]));
+        assert_eq!(a * Matrix::<2>::identity(), a);
+
+        // as a row, so (1, 2) picks up 1 of the first row and 2 of the second
+        assert_eq!(Vec2::new([1.0, 2.0]).transform(&a), Vec2::new([7.0, 10.0]));
+        assert_eq!(a * Vec2::new([1.0, 2.0]), Vec2::new([5.0, 11.0]));
+    }
+
+    #[test]
+    fn transform_coordinates() {
+        let v = Vec3::new([1.0, 2.0, 3.0]);
+        assert_eq!(Vec3::transform_coordinates(&v, &Mat4::translation(1.0, -2.0, 5.0)), Vec3::new([2.0, 0.0, 8.0]));
+        assert_eq!(Vec3::transform_coordinates(&v, &Mat4::scaling(2.0, 0.5, 3.0)), Vec3::new([2.0, 1.0, 9.0]));
+
+        // the near plane ends up at depth 0 and the far one at 1, after dividing by w
+        let perspective = Mat4::perspective_fov_lh(0.78, 1.5, 0.01, 1.0);
+        let near = Vec3::transform_coordinates(&Vec3::new([0.0, 0.0, 0.01]), &perspective);
+        let far = Vec3::transform_coordinates(&Vec3::new([0.0, 0.0, 1.0]), &perspective);
+        assert!(near.z().abs() < EPSILON);
+        assert!((far.z() - 1.0).abs() < EPSILON);
+
+        // twice as far away is half as far from the middle of the screen
+        let close = Vec3::transform_coordinates(&Vec3::new([1.0, 1.0, 0.5]), &perspective);
+        let distant = Vec3::transform_coordinates(&Vec3::new([1.0, 1.0, 1.0]), &perspective);
+        assert!((close.x() - 2.0 * distant.x()).abs() < EPSILON);
+        assert!((close.y() - 2.0 * distant.y()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn point_indexing() {
+        let mut a = Vec4::new([1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(a[3], a.w());
+        a[1] = 7.0;
+        assert_eq!(a.y(), 7.0);
+    }
+
+    #[test]
+    fn approx_eq() {
+        let a = Vec2::new([1.0, 2.0]);
+        assert!(a.approx_eq(&Vec2::new([1.0 + 1e-12, 2.0]), EPSILON));
+        assert!(!a.approx_eq(&Vec2::new([1.0, 2.1]), EPSILON));
+
+        let m = busy();
+        assert!(m.approx_eq(&(m + Mat4::identity().scale(1e-12)), EPSILON));
+        assert!(!m.approx_eq(&(m + Mat4::identity()), EPSILON));
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let m = Mat3::new([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+        assert_eq!(m.row(1), Vec3::new([4.0, 5.0, 6.0]));
+        assert_eq!(m.column(1), Vec3::new([2.0, 5.0, 8.0]));
+        assert_eq!(m[(2, 0)], 7.0);
+        assert_eq!(m.trace(), 15.0);
+
+        let t = m.transpose();
+        assert_eq!(t.row(1), m.column(1));
+        assert_eq!(t.transpose(), m);
+
+        let mut n = m;
+        n[(0, 0)] = -1.0;
+        assert_eq!(n.at(0, 0), -1.0);
+    }
+
+    #[test]
+    fn matrix_arithmetic() {
+        let m = busy();
+        let n = Mat4::rotation_yaw_pitch_roll(1.0, 2.0, 3.0);
+
+        assert!((m + n - n).approx_eq(&m, EPSILON));
+        assert_eq!(-m, m * -1.0);
+        assert_eq!(m / 2.0, m.scale(0.5));
+        assert_eq!(m - m, Mat4::zeroes());
+
+        let mut o = m;
+        o *= n;
+        assert_eq!(o, m * n);
+        o += m;
+        assert_eq!(o, m * n + m);
+        o -= m;
+        o *= 2.0;
+        o /= 2.0;
+        assert!(o.approx_eq(&(m * n), EPSILON));
+    }
+
+    #[test]
+    fn matrix_times_point() {
+        let m = busy();
+        let v = Vec4::new([1.0, 2.0, -3.0, 1.0]);
+
+        assert_eq!(v * m, v.transform(&m));
+        assert!((m * v).approx_eq(&(v * m.transpose()), EPSILON));
+
+        let mut w = v;
+        w *= m;
+        assert_eq!(w, v * m);
+    }
+
+    #[test]
+    fn determinant() {
+        assert_eq!(Mat4::identity().determinant(), 1.0);
+        assert_eq!(Mat4::zeroes().determinant(), 0.0);
+        assert_eq!(Matrix::<2>::new([[1.0, 2.0], [3.0, 4.0]]).determinant(), -2.0);
+
+        // a row swap flips the sign
+        let m = Mat3::new([[0.0, 1.0, 2.0], [3.0, 4.0, 5.0], [6.0, 7.0, 9.0]]);
+        let swapped = Mat3::new([[3.0, 4.0, 5.0], [0.0, 1.0, 2.0], [6.0, 7.0, 9.0]]);
+        assert!((m.determinant() + 3.0).abs() < EPSILON);
+        assert!((swapped.determinant() - 3.0).abs() < EPSILON);
+
+        // rotations keep volume, scaling multiplies it
+        assert!((Mat4::rotation_yaw_pitch_roll(0.3, 0.2, 0.1).determinant() - 1.0).abs() < EPSILON);
+        assert!((Mat4::scaling(2.0, 3.0, 4.0).determinant() - 24.0).abs() < EPSILON);
+
+        // and it goes through products
+        let (a, b) = (busy(), Mat4::rotation_yaw_pitch_roll(1.0, 2.0, 3.0) * Mat4::scaling(1.0, 2.0, 3.0));
+        assert!(((a * b).determinant() - a.determinant() * b.determinant()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn inverse() {
+        let m = busy();
+        let inverse = m.inverse().expect("busy() isn't singular");
+        assert!((m * inverse).approx_eq(&Mat4::identity(), EPSILON));
+        assert!((inverse * m).approx_eq(&Mat4::identity(), EPSILON));
+        assert!((m.determinant() * inverse.determinant() - 1.0).abs() < EPSILON);
+
+        // rotations are undone by their transpose
+        let r = Mat4::rotation_yaw_pitch_roll(0.3, -1.1, 2.0);
+        assert!(r.inverse().unwrap().approx_eq(&r.transpose(), EPSILON));
+
+        // it needs a pivot swap to get started
+        let m = Mat3::new([[0.0, 1.0, 2.0], [3.0, 4.0, 5.0], [6.0, 7.0, 9.0]]);
+        assert!((m * m.inverse().unwrap()).approx_eq(&Mat3::identity(), EPSILON));
+
+        // and generic in size
+        let m = Matrix::<5>::new(std::array::from_fn(|i| std::array::from_fn(|j| 1.0 / (i + j + 1) as Element)));
+        assert!((m * m.inverse().unwrap()).approx_eq(&Matrix::identity(), 1e-6));
+    }
+
+    #[test]
+    fn singular() {
+        assert_eq!(Mat4::zeroes().inverse(), None);
+        assert_eq!(Mat4::scaling(1.0, 0.0, 1.0).inverse(), None);
+        let m = Mat3::new([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+        assert_eq!(m.inverse(), None);
+        assert!(m.determinant().abs() < EPSILON);
+    }
+
+    #[test]
+    fn unproject() {
+        let dims = Vec2::new([640.0, 480.0]);
+        let view = Mat4::look_at_lh(Vec3::new([0.0, 1.0, -3.0]), Vec3::zeroes(), Vec3::new([0.0, 1.0, 0.0]));
+        let trans = Mat4::rotation_yaw_pitch_roll(0.4, 0.2, 0.0) * view * Mat4::perspective_fov_lh(0.78, 640.0 / 480.0, 0.01, 1.0);
+
+        let v = Vec3::new([0.3, -0.2, 0.5]);
+        let screen = Vec2::project(&dims, &v, &trans);
+        let depth = Vec3::transform_coordinates(&v, &trans).z();
+        let back = Vec3::unproject(&dims, &screen, depth, &trans).expect("the transform isn't singular");
+        assert!(back.approx_eq(&v, 1e-6));
+
+        // the ray through the pixel passes through the point
+        let near = Vec3::unproject(&dims, &screen, 0.0, &trans).unwrap();
+        let far = Vec3::unproject(&dims, &screen, 1.0, &trans).unwrap();
+        let along = (far - near).normal();
+        let off = (v - near) - along.scale((v - near).dot(&along));
+        assert!(off.magnitude() < 1e-6);
+
+        assert_eq!(Vec3::unproject(&dims, &screen, 0.5, &Mat4::zeroes()), None);
+    }
+
+    // q and -q are the same turn
+    fn same_turn(a: &Quaternion, b: &Quaternion) -> bool {
+        (a.dot(b).abs() - 1.0).abs() < EPSILON
+    }
+
+    // a few turns, including half turns and ones with w < 0 so `from_mat4` takes every branch
+    fn turns() -> Vec<Quaternion> {
+        vec![
+            Quaternion::identity(),
+            Quaternion::from_axis_angle(&Vec3::new([1.0, 2.0, 3.0]), 0.7),
+            Quaternion::from_axis_angle(&Vec3::new([1.0, 0.0, 0.0]), PI),
+            Quaternion::from_axis_angle(&Vec3::new([0.0, 1.0, 0.0]), PI),
+            Quaternion::from_axis_angle(&Vec3::new([0.0, 0.0, 1.0]), PI),
+            Quaternion::from_axis_angle(&Vec3::new([-2.0, 0.5, 1.0]), 5.0),
+        ]
+    }
+
+    #[test]
+    fn quaternion_turns() {
+        // a quarter turn about z takes x to y
+        let quarter = Quaternion::from_axis_angle(&Vec3::new([0.0, 0.0, 2.0]), PI / 2.0);
+        assert!(quarter.rotate(&Vec3::new([1.0, 0.0, 0.0])).approx_eq(&Vec3::new([0.0, 1.0, 0.0]), EPSILON));
+
+        let (axis, angle) = Quaternion::from_axis_angle(&Vec3::new([0.0, 3.0, 4.0]), 2.5).axis_angle();
+        assert!(axis.approx_eq(&Vec3::new([0.0, 0.6, 0.8]), EPSILON));
+        assert!((angle - 2.5).abs() < EPSILON);
+        assert_eq!(Quaternion::identity().axis_angle(), (Vec3::new([1.0, 0.0, 0.0]), 0.0));
+        assert_eq!(Quaternion::from_axis_angle(&Vec3::zeroes(), 1.0).rotate(&Vec3::new([1.0, 2.0, 3.0])), Vec3::new([1.0, 2.0, 3.0]));
+
+        let v = Vec3::new([0.3, -1.2, 2.0]);
+        for a in turns() {
+            // the matrix turns the same way, keeps lengths and undoes with the conjugate
+            assert!(Vec3::transform_coordinates(&v, &a.to_mat4()).approx_eq(&a.rotate(&v), EPSILON));
+            assert!((a.rotate(&v).magnitude() - v.magnitude()).abs() < EPSILON);
+            assert!(same_turn(&(a * a.conjugate()), &Quaternion::identity()));
+            assert!(same_turn(&Quaternion::from_mat4(&a.to_mat4()), &a));
+
+            for b in turns() {
+                // b first, then a
+                assert!((a * b).rotate(&v).approx_eq(&a.rotate(&b.rotate(&v)), EPSILON));
+                assert!((a * b).to_mat4().approx_eq(&(b.to_mat4() * a.to_mat4()), EPSILON));
+            }
+        }
+
+        assert_eq!(Quaternion::new(0.0, 0.0, 0.0, 0.0).normal(), Quaternion::identity());
+        assert!((Quaternion::new(1.0, 2.0, 2.0, 4.0).normal().magnitude() - 1.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn quaternion_euler_angles() {
+        let (yaw, pitch, roll) = (0.4, -0.3, 1.2);
+        let q = Quaternion::from_yaw_pitch_roll(yaw, pitch, roll);
+        assert!(q.to_mat4().approx_eq(&Mat4::rotation_yaw_pitch_roll(yaw, pitch, roll), EPSILON));
+        let (y, p, r) = q.yaw_pitch_roll();
+        assert!((y - yaw).abs() < EPSILON && (p - pitch).abs() < EPSILON && (r - roll).abs() < EPSILON);
+    }
+
+    #[test]
+    fn quaternion_interpolation() {
+        let up = Vec3::new([0.0, 1.0, 0.0]);
+        let a = Quaternion::from_axis_angle(&up, 0.2);
+        let b = Quaternion::from_axis_angle(&up, 2.2);
+
+        for lerp in [Quaternion::slerp, Quaternion::nlerp] {
+            assert!(same_turn(&lerp(&a, &b, 0.0), &a));
+            assert!(same_turn(&lerp(&a, &b, 1.0), &b));
+            // halfway is halfway for both
+            assert!(same_turn(&lerp(&a, &b, 0.5), &Quaternion::from_axis_angle(&up, 1.2)));
+        }
+
+        // slerp goes at a steady speed, nlerp doesn't
+        let at = |q: Quaternion| q.axis_angle().1;
+        assert!((at(a.slerp(&b, 0.25)) - 0.7).abs() < EPSILON);
+        assert!((at(a.nlerp(&b, 0.25)) - 0.7).abs() > 1e-3);
+
+        // the short way round: 350 degrees one way is 10 the other
+        let c = Quaternion::from_axis_angle(&up, 0.2 - 20.0_f64.to_radians());
+        let middle = a.slerp(&c, 0.5);
+        assert!(same_turn(&middle, &Quaternion::from_axis_angle(&up, 0.2 - 10.0_f64.to_radians())));
+        assert!(same_turn(&a.slerp(&Quaternion::new(-a.x, -a.y, -a.z, -a.w), 0.5), &a));
     }
 }
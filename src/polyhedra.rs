@@ -0,0 +1,504 @@
+// The Platonic and Archimedean solids, prisms, antiprisms and bipyramids, all centred on the
+// origin with unit circumradius. Everything here is convex with its vertices on the unit sphere,
+// so we only ever write down vertices (the coordinates are the usual ones, see e.g. Wikipedia)
+// and let `convex_faces` work out the faces from which vertices are neighbours.
+
+use crate::mesh::{Adjacency, Face, Line, Mesh, MeshError};
+use crate::point::{Element, Point, Vec3};
+
+// golden ratio
+const PHI: Element = 1.618_033_988_749_895;
+// tribonacci constant, for the snub cube
+const TRIBONACCI: Element = 1.839_286_755_214_161;
+
+// Relative slack for "on the plane" and "the same length".
+const EPSILON: Element = 1e-9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Platonic {
+    Tetrahedron,
+    Cube,
+    Octahedron,
+    Dodecahedron,
+    Icosahedron,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Archimedean {
+    TruncatedTetrahedron,
+    Cuboctahedron,
+    TruncatedCube,
+    TruncatedOctahedron,
+    Rhombicuboctahedron,
+    TruncatedCuboctahedron,
+    SnubCube,
+    Icosidodecahedron,
+    TruncatedDodecahedron,
+    TruncatedIcosahedron,
+    Rhombicosidodecahedron,
+    TruncatedIcosidodecahedron,
+    SnubDodecahedron,
+}
+
+impl Platonic {
+    pub const ALL: [Platonic; 5] = [
+        Platonic::Tetrahedron,
+        Platonic::Cube,
+        Platonic::Octahedron,
+        Platonic::Dodecahedron,
+        Platonic::Icosahedron,
+    ];
+
+    // by its snake_case name, like in scene files
+    pub fn from_name(name: &str) -> Option<Platonic> {
+        Platonic::ALL.into_iter().find(|solid| format!("{:?}", solid).to_lowercase() == name.replace('_', ""))
+    }
+
+    fn vertices(self) -> Vec<[Element; 3]> {
+        match self {
+            // an even number of minus signs
+            Platonic::Tetrahedron => plus_signs([1.0, 1.0, 1.0], Some(false)),
+            Platonic::Cube => signs([1.0, 1.0, 1.0]),
+            Platonic::Octahedron => all(&[[1.0, 0.0, 0.0]]),
+            Platonic::Dodecahedron => {
+                let mut out = signs([1.0, 1.0, 1.0]);
+                out.extend(even(&[[0.0, 1.0 / PHI, PHI]]));
+                out
+            },
+            Platonic::Icosahedron => even(&[[0.0, 1.0, PHI]]),
+        }
+    }
+}
+
+impl Archimedean {
+    pub const ALL: [Archimedean; 13] = [
+        Archimedean::TruncatedTetrahedron,
+        Archimedean::Cuboctahedron,
+        Archimedean::TruncatedCube,
+        Archimedean::TruncatedOctahedron,
+        Archimedean::Rhombicuboctahedron,
+        Archimedean::TruncatedCuboctahedron,
+        Archimedean::SnubCube,
+        Archimedean::Icosidodecahedron,
+        Archimedean::TruncatedDodecahedron,
+        Archimedean::TruncatedIcosahedron,
+        Archimedean::Rhombicosidodecahedron,
+        Archimedean::TruncatedIcosidodecahedron,
+        Archimedean::SnubDodecahedron,
+    ];
+
+    // by its snake_case name, like in scene files
+    pub fn from_name(name: &str) -> Option<Archimedean> {
+        Archimedean::ALL.into_iter().find(|solid| format!("{:?}", solid).to_lowercase() == name.replace('_', ""))
+    }
+
+    fn vertices(self) -> Vec<[Element; 3]> {
+        let root2 = Element::sqrt(2.0);
+        match self {
+            // an even number of minus signs
+            Archimedean::TruncatedTetrahedron => permutations(&[[3.0, 1.0, 1.0]])
+                .into_iter()
+                .flat_map(|p| plus_signs(p, Some(false)))
+                .collect(),
+            Archimedean::Cuboctahedron => all(&[[1.0, 1.0, 0.0]]),
+            Archimedean::TruncatedCube => all(&[[root2 - 1.0, 1.0, 1.0]]),
+            Archimedean::TruncatedOctahedron => all(&[[0.0, 1.0, 2.0]]),
+            Archimedean::Rhombicuboctahedron => all(&[[1.0, 1.0, 1.0 + root2]]),
+            Archimedean::TruncatedCuboctahedron => all(&[[1.0, 1.0 + root2, 1.0 + 2.0 * root2]]),
+            Archimedean::SnubCube => {
+                // even permutations with an even number of plus signs, odd ones with an odd number
+                let p = [1.0, 1.0 / TRIBONACCI, TRIBONACCI];
+                let mut out = even_permutations(p)
+                    .into_iter()
+                    .flat_map(|p| plus_signs(p, Some(true)))
+                    .collect::<Vec<[Element; 3]>>();
+                out.extend(even_permutations([p[1], p[0], p[2]])
+                    .into_iter()
+                    .flat_map(|p| plus_signs(p, Some(false))));
+                out
+            },
+            Archimedean::Icosidodecahedron => {
+                let mut out = all(&[[0.0, 0.0, PHI]]);
+                out.extend(even(&[[0.5, PHI / 2.0, PHI * PHI / 2.0]]));
+                out
+            },
+            Archimedean::TruncatedDodecahedron => even(&[
+                [0.0, 1.0 / PHI, 2.0 + PHI],
+                [1.0 / PHI, PHI, 2.0 * PHI],
+                [PHI, 2.0, PHI + 1.0],
+            ]),
+            Archimedean::TruncatedIcosahedron => even(&[
+                [0.0, 1.0, 3.0 * PHI],
+                [1.0, 2.0 + PHI, 2.0 * PHI],
+                [PHI, 2.0, PHI * PHI * PHI],
+            ]),
+            Archimedean::Rhombicosidodecahedron => even(&[
+                [1.0, 1.0, PHI * PHI * PHI],
+                [PHI * PHI, PHI, 2.0 * PHI],
+                [2.0 + PHI, 0.0, PHI * PHI],
+            ]),
+            Archimedean::TruncatedIcosidodecahedron => even(&[
+                [1.0 / PHI, 1.0 / PHI, 3.0 + PHI],
+                [2.0 / PHI, PHI, 1.0 + 2.0 * PHI],
+                [1.0 / PHI, PHI * PHI, 3.0 * PHI - 1.0],
+                [2.0 * PHI - 1.0, 2.0, 2.0 + PHI],
+                [PHI, 3.0, 2.0 * PHI],
+            ]),
+            Archimedean::SnubDodecahedron => {
+                // One vertex is (2 alpha, 2, 2 beta) with xi the real root of xi^3 - 2 xi = phi;
+                // the rest are wherever the icosahedron's rotations take it. The sign rules for
+                // writing them all down directly are too easy to get wrong.
+                let root = (PHI - 5.0 / 27.0).sqrt() / 2.0;
+                let xi = (PHI / 2.0 + root).cbrt() + (PHI / 2.0 - root).cbrt();
+                let alpha = xi - 1.0 / xi;
+                let beta = xi * PHI + PHI * PHI + PHI / xi;
+                icosahedral_orbit(Vec3::new([2.0 * alpha, 2.0, 2.0 * beta]))
+                    .into_iter()
+                    .map(|v| v.coord)
+                    .collect()
+            },
+        }
+    }
+}
+
+// every way of flipping the signs of the non-zero coordinates
+fn signs(p: [Element; 3]) -> Vec<[Element; 3]> {
+    plus_signs(p, None)
+}
+
+// Like `signs` but, given `Some(even)`, only the ones with an even (or odd) number of plus signs.
+fn plus_signs(p: [Element; 3], even: Option<bool>) -> Vec<[Element; 3]> {
+    let mut out = vec![];
+    for flips in 0..8 {
+        let q: [Element; 3] = std::array::from_fn(|i| if flips & (1 << i) != 0 { -p[i] } else { p[i] });
+        if flips != 0 && (0..3).any(|i| flips & (1 << i) != 0 && p[i] == 0.0) {
+            // flipping a zero does nothing, we already have this one
+            continue;
+        }
+        let plus = q.iter().filter(|&&x| x > 0.0).count();
+        if even.is_none_or(|even| (plus % 2 == 0) == even) {
+            out.push(q);
+        }
+    }
+
+    out
+}
+
+fn even_permutations(p: [Element; 3]) -> Vec<[Element; 3]> {
+    vec![[p[0], p[1], p[2]], [p[1], p[2], p[0]], [p[2], p[0], p[1]]]
+}
+
+fn permutations(ps: &[[Element; 3]]) -> Vec<[Element; 3]> {
+    ps.iter()
+        .flat_map(|&p| {
+            let mut out = even_permutations(p);
+            out.extend(even_permutations([p[1], p[0], p[2]]));
+            out
+        })
+        .collect()
+}
+
+// all sign changes of the even permutations of each point
+fn even(ps: &[[Element; 3]]) -> Vec<[Element; 3]> {
+    ps.iter().flat_map(|&p| even_permutations(p)).flat_map(signs).collect()
+}
+
+// all sign changes of all permutations of each point
+fn all(ps: &[[Element; 3]]) -> Vec<[Element; 3]> {
+    permutations(ps).into_iter().flat_map(signs).collect()
+}
+
+// Everywhere the rotations of an icosahedron (with vertices at the even permutations of
+// (0, +-phi, +-1), the mirror image of `Platonic::Icosahedron`) take `point`. The group is generated by cycling the axes, turning half way
+// round an axis and a fifth of the way round a vertex.
+fn icosahedral_orbit(point: Vec3) -> Vec<Vec3> {
+    let axis = Vec3::new([0.0, PHI, 1.0]).normal();
+    let (sin, cos) = (2.0 * std::f64::consts::PI / 5.0).sin_cos();
+    let generators: [&dyn Fn(&Vec3) -> Vec3; 3] = [
+        &|v| Vec3::new([v.y(), v.z(), v.x()]),
+        &|v| Vec3::new([-v.x(), -v.y(), v.z()]),
+        // Rodrigues' rotation formula
+        &|v| v.scale(cos) + axis.cross(v).scale(sin) + axis.scale(axis.dot(v) * (1.0 - cos)),
+    ];
+
+    let mut orbit = vec![point];
+    let mut fresh = vec![point];
+    while let Some(v) = fresh.pop() {
+        for generator in generators.iter() {
+            let w = generator(&v);
+            if !orbit.iter().any(|u| (*u - w).magnitude() < EPSILON.sqrt() * point.magnitude()) {
+                orbit.push(w);
+                fresh.push(w);
+            }
+        }
+    }
+
+    orbit
+}
+
+// Drop repeats (permuting equal coordinates makes plenty) and scale onto the unit sphere.
+fn unit_vertices(points: Vec<[Element; 3]>) -> Vec<Vec3> {
+    let mut out = Vec::<Vec3>::new();
+    for point in points {
+        let point = Vec3::new(point);
+        if !out.iter().any(|v| (*v - point).magnitude() < EPSILON * point.magnitude()) {
+            out.push(point);
+        }
+    }
+
+    let radius = out.iter().map(Vec3::magnitude).fold(0.0, Element::max);
+    out.iter().map(|v| v.scale(1.0 / radius)).collect()
+}
+
+// Every pair of vertices that are as close as any two vertices get. For the uniform solids that's
+// exactly the edges.
+pub fn shortest_lines<const N: usize>(vertices: &[Point<N>]) -> Vec<Line> {
+    let mut shortest = Element::INFINITY;
+    for (i, a) in vertices.iter().enumerate() {
+        for b in vertices[i + 1..].iter() {
+            shortest = shortest.min((*a - *b).magnitude());
+        }
+    }
+
+    let mut out = vec![];
+    for (i, a) in vertices.iter().enumerate() {
+        for (j, b) in vertices.iter().enumerate().skip(i + 1) {
+            if (*a - *b).magnitude() <= shortest * (1.0 + EPSILON.sqrt()) {
+                out.push((i, j));
+            }
+        }
+    }
+
+    out
+}
+
+// The faces of a convex polyhedron containing the origin, given its edges. Each face is found by
+// walking around it: from the edge a-b the next corner is the neighbour c of b where the plane
+// through a, b and c has every vertex on or behind it and a-b-c turns anticlockwise seen from
+// outside, which is also the winding `face_normal` wants.
+pub fn convex_faces(vertices: &[Vec3], lines: &[Line]) -> Vec<Face> {
+    let adjacency = Adjacency::new(vertices.len(), lines);
+    let scale = vertices.iter().map(Vec3::magnitude).fold(0.0, Element::max);
+
+    let next_corner = |a: usize, b: usize| -> Option<usize> {
+        adjacency.neighbours(b).iter().copied().find(|&c| {
+            let normal = (vertices[b] - vertices[a]).cross(&(vertices[c] - vertices[a]));
+            let tolerance = EPSILON * scale * normal.magnitude();
+            c != a
+                && normal.dot(&vertices[a]) > tolerance
+                && vertices.iter().all(|v| normal.dot(&(*v - vertices[a])) <= tolerance)
+        })
+    };
+
+    let mut walked = std::collections::HashSet::<Line>::new();
+    let mut faces = vec![];
+    for &(from, to) in lines {
+        for start in [(from, to), (to, from)] {
+            if walked.contains(&start) {
+                continue;
+            }
+
+            let mut face = vec![start.0];
+            let (mut a, mut b) = start;
+            walked.insert(start);
+            while b != start.0 {
+                let Some(c) = next_corner(a, b) else {
+                    // not convex after all; leave the edge out of any face
+                    face.clear();
+                    break;
+                };
+                face.push(b);
+                walked.insert((b, c));
+                (a, b) = (b, c);
+                if face.len() > vertices.len() {
+                    face.clear();
+                    break;
+                }
+            }
+            if face.len() >= 3 {
+                faces.push(face);
+            }
+        }
+    }
+
+    faces
+}
+
+fn convex_mesh(vertices: Vec<Vec3>, lines: &[Line]) -> Mesh {
+    let faces = convex_faces(&vertices, lines);
+    Mesh::with_faces(Vec3::zeroes(), vertices, faces).expect("convex hulls are always valid meshes")
+}
+
+impl Mesh {
+    pub fn mk_platonic(solid: Platonic) -> Mesh {
+        let vertices = unit_vertices(solid.vertices());
+        let lines = shortest_lines(&vertices);
+        convex_mesh(vertices, &lines)
+    }
+
+    pub fn mk_archimedean(solid: Archimedean) -> Mesh {
+        let vertices = unit_vertices(solid.vertices());
+        let lines = shortest_lines(&vertices);
+        convex_mesh(vertices, &lines)
+    }
+
+    // Two regular `sides`-gons joined by squares.
+    pub fn mk_prism(sides: usize) -> Result<Mesh, MeshError> {
+        if sides < 3 {
+            return Err(MeshError::TooFewSides(sides));
+        }
+        // edge = 2 r sin(pi / n) = 2 h and r^2 + h^2 = 1
+        let half_edge = (std::f64::consts::PI / sides as Element).sin();
+        let radius = 1.0 / (1.0 + half_edge * half_edge).sqrt();
+        let height = radius * half_edge;
+
+        let mut vertices = ring(sides, radius, height, 0.0);
+        vertices.extend(ring(sides, radius, -height, 0.0));
+        let lines = (0..sides)
+            .flat_map(|i| [(i, (i + 1) % sides), (sides + i, sides + (i + 1) % sides), (i, sides + i)])
+            .collect::<Vec<Line>>();
+        Ok(convex_mesh(vertices, &lines))
+    }
+
+    // Two regular `sides`-gons, one twisted half a step, joined by equilateral triangles.
+    pub fn mk_antiprism(sides: usize) -> Result<Mesh, MeshError> {
+        if sides < 3 {
+            return Err(MeshError::TooFewSides(sides));
+        }
+        // with the rim at radius r the edge is 2 r sin(pi / n), and a slanted edge spans half a
+        // step around (2 r sin(pi / 2n)) and 2h up, so 4h^2 = 4r^2 (sin^2(pi / n) - sin^2(pi / 2n))
+        let step = std::f64::consts::PI / sides as Element;
+        let rise = (step.sin().powi(2) - (step / 2.0).sin().powi(2)).sqrt();
+        let radius = 1.0 / (1.0 + rise * rise).sqrt();
+        let height = radius * rise;
+
+        let mut vertices = ring(sides, radius, height, 0.0);
+        vertices.extend(ring(sides, radius, -height, step));
+        let lines = (0..sides)
+            .flat_map(|i| [
+                (i, (i + 1) % sides),
+                (sides + i, sides + (i + 1) % sides),
+                (i, sides + i),
+                ((i + 1) % sides, sides + i),
+            ])
+            .collect::<Vec<Line>>();
+        Ok(convex_mesh(vertices, &lines))
+    }
+
+    // A regular `sides`-gon around the equator with a point above and below, all on the unit
+    // sphere.
+    pub fn mk_bipyramid(sides: usize) -> Result<Mesh, MeshError> {
+        if sides < 3 {
+            return Err(MeshError::TooFewSides(sides));
+        }
+        let mut vertices = ring(sides, 1.0, 0.0, 0.0);
+        vertices.push(Vec3::new([0.0, 1.0, 0.0]));
+        vertices.push(Vec3::new([0.0, -1.0, 0.0]));
+        let lines = (0..sides)
+            .flat_map(|i| [(i, (i + 1) % sides), (i, sides), (i, sides + 1)])
+            .collect::<Vec<Line>>();
+        Ok(convex_mesh(vertices, &lines))
+    }
+}
+
+// `count` points evenly around a circle of `radius` at height `y`, starting `offset` radians in
+fn ring(count: usize, radius: Element, y: Element, offset: Element) -> Vec<Vec3> {
+    (0..count)
+        .map(|i| {
+            let angle = offset + 2.0 * std::f64::consts::PI * i as Element / count as Element;
+            Vec3::new([radius * angle.cos(), y, radius * angle.sin()])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // vertices, lines and faces, plus the usual checks every solid here has to pass
+    fn check(mesh: &Mesh, counts: (usize, usize, usize)) {
+        assert_eq!((mesh.vertices.len(), mesh.lines.len(), mesh.faces.len()), counts);
+        assert_eq!(mesh.euler_characteristic(), 2);
+        assert!(mesh.is_closed());
+        assert!(mesh.is_manifold());
+        assert!(mesh.vertices.iter().all(|v| (v.magnitude() - 1.0).abs() < 1e-9));
+        // convex around the origin, so every face faces away from it
+        for (face, normal) in mesh.faces.iter().zip(mesh.face_normals()) {
+            assert!(face.iter().all(|&v| normal.dot(&mesh.vertices[v]) > 0.0));
+        }
+    }
+
+    #[test]
+    fn platonic() {
+        let counts = [(4, 6, 4), (8, 12, 6), (6, 12, 8), (20, 30, 12), (12, 30, 20)];
+        for (solid, counts) in Platonic::ALL.into_iter().zip(counts) {
+            check(&Mesh::mk_platonic(solid), counts);
+        }
+    }
+
+    #[test]
+    fn archimedean() {
+        let counts = [
+            (12, 18, 8),
+            (12, 24, 14),
+            (24, 36, 14),
+            (24, 36, 14),
+            (24, 48, 26),
+            (48, 72, 26),
+            (24, 60, 38),
+            (30, 60, 32),
+            (60, 90, 32),
+            (60, 90, 32),
+            (60, 120, 62),
+            (120, 180, 62),
+            (60, 150, 92),
+        ];
+        for (solid, counts) in Archimedean::ALL.into_iter().zip(counts) {
+            check(&Mesh::mk_archimedean(solid), counts);
+        }
+
+        // 80 triangles and 12 pentagons
+        let snub = Mesh::mk_archimedean(Archimedean::SnubDodecahedron);
+        assert_eq!(snub.faces.iter().filter(|face| face.len() == 3).count(), 80);
+        assert_eq!(snub.faces.iter().filter(|face| face.len() == 5).count(), 12);
+    }
+
+    #[test]
+    fn prisms_antiprisms_and_bipyramids() {
+        for n in [3, 4, 5, 8, 17] {
+            check(&Mesh::mk_prism(n).unwrap(), (2 * n, 3 * n, n + 2));
+            check(&Mesh::mk_antiprism(n).unwrap(), (2 * n, 4 * n, 2 * n + 2));
+            check(&Mesh::mk_bipyramid(n).unwrap(), (n + 2, 3 * n, 2 * n));
+        }
+
+        // every edge of the prisms and antiprisms is the same length
+        for mesh in [Mesh::mk_prism(7).unwrap(), Mesh::mk_antiprism(7).unwrap()] {
+            let lengths = mesh.lines.iter()
+                .map(|&(a, b)| mesh.vertices[a].distance(&mesh.vertices[b]))
+                .collect::<Vec<Element>>();
+            assert!(lengths.iter().all(|length| (length - lengths[0]).abs() < 1e-9));
+        }
+    }
+
+    #[test]
+    fn too_few_sides() {
+        for n in [0, 1, 2] {
+            assert_eq!(Mesh::mk_prism(n).unwrap_err(), MeshError::TooFewSides(n));
+            assert_eq!(Mesh::mk_antiprism(n).unwrap_err(), MeshError::TooFewSides(n));
+            assert_eq!(Mesh::mk_bipyramid(n).unwrap_err(), MeshError::TooFewSides(n));
+        }
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Platonic::from_name("icosahedron"), Some(Platonic::Icosahedron));
+        assert_eq!(Archimedean::from_name("snub_dodecahedron"), Some(Archimedean::SnubDodecahedron));
+        assert_eq!(Archimedean::from_name("truncated_icosidodecahedron"), Some(Archimedean::TruncatedIcosidodecahedron));
+        assert_eq!(Platonic::from_name("cuboctahedron"), None);
+        assert_eq!(Archimedean::from_name("snub"), None);
+    }
+}
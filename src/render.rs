@@ -1,13 +1,23 @@
+use std::f64::consts::PI;
+
+use crate::point::Element;
 use crate::point::Vec2;
 use crate::point::Vec3;
 use crate::point::Mat4;
 use crate::Mesh;
 use crate::camera::Camera;
+use crate::graph;
+use crate::graph::Node;
+use crate::hidden;
+use crate::hyper;
+use crate::hyper::{Hyper, PolytopeError};
+use crate::morph;
+use crate::morph::{Morph, MorphError};
+use crate::solid;
 
 use crate::instance::Simulation;
 use crate::instance::SystemContext;
 
-use wasm_bindgen::prelude::*; // XXX this makes me sad
 // A macro to provide `println!(..)`-style syntax for `console.log` logging.
 /*
 macro_rules! log {
@@ -17,56 +27,196 @@ macro_rules! log {
 }
 */
 
+// How the illusion gets painted. Colors are anything the canvas understands but we stick to
+// "#rrggbb" so scene files can be checked before we hand them off.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Style {
+    pub line_color: String,
+    pub bubble_color: String,
+    pub line_width: Element,
+    pub bubble_radius: Element,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hidden_lines: HiddenLines,
+    // fill in the faces too, `None` for wireframes only
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub solid: Option<Shading>,
+}
+
+// Flat shading for filled faces: each face gets `face_color` scaled by how squarely it faces a
+// far off light shining in the (world space) direction `light`, and never darker than `ambient`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Shading {
+    pub face_color: String,
+    pub light: Vec3,
+    pub ambient: Element,
+}
+
+impl Default for Shading {
+    fn default() -> Self {
+        Self {
+            face_color: format!("#{:0>6x}", 0xf0ead6), // yellowish-white/eggshell
+            light: Vec3::new([-1.0, -2.0, -1.0]),
+            ambient: 0.2,
+        }
+    }
+}
+
+// What to do with the parts of lines that are behind a face (of any mesh in the scene). Meshes
+// without faces can't hide anything. `Show` skips the work entirely and draws everything the same.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum HiddenLines {
+    #[default]
+    Show,
+    Skip,
+    Dash,
+    Fade,
+}
+
+impl HiddenLines {
+    pub const ALL: [HiddenLines; 4] = [HiddenLines::Show, HiddenLines::Skip, HiddenLines::Dash, HiddenLines::Fade];
+
+    // by its snake_case name, like in scene files
+    pub fn from_name(name: &str) -> Option<HiddenLines> {
+        HiddenLines::ALL.into_iter().find(|hidden| format!("{:?}", hidden).to_lowercase() == name)
+    }
+}
+
+// how see-through a faded hidden line is
+const FADE_ALPHA: Element = 0.25;
+// dash and gap length, in line widths
+const DASH_LENGTH: Element = 2.0;
+
+impl Default for Style {
+    fn default() -> Self {
+        /*
+        let colors = [
+            0xff00ff, // pink?
+            0xff0000, // red
+            0x00ff00, // lime green
+            0x00bfff, // deep-sky blue
+            0xf0ead6, // yellowish-white/eggshell
+            0x6f4e37, // coffee
+            0xb00b1e,
+            0xc0ffee,
+        ];
+        */
+        Self {
+            line_color: format!("#{:0>6x}", 0xff00ff), // pink?
+            bubble_color: format!("#{:0>6x}", 0x00bfff), // deep-sky blue
+            /*
+            line_color: format!("#{:0>6x}", 0xffffff),
+            bubble_color: format!("#{:0>6x}", 0x000000),
+            */
+            line_width: 10.0,
+            bubble_radius: 100.0,
+            hidden_lines: HiddenLines::Show,
+            solid: None,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Scene {
     camera: Camera,
     meshes: Vec<Mesh>,
+    style: Style,
+    // each drawn through the shadow mesh at that index in `meshes`
+    #[cfg_attr(feature = "serde", serde(default))]
+    polytopes: Vec<(Hyper, usize)>,
+    // each moving the mesh at that index in `meshes`
+    #[cfg_attr(feature = "serde", serde(default))]
+    morphs: Vec<(Morph, usize)>,
+    // the roots of the scene graph; meshes none of them use are drawn on their own
+    #[cfg_attr(feature = "serde", serde(default))]
+    graph: Vec<Node>,
+    // each mesh's faces sorted for drawing solid, rebuilt when the mesh changes shape
+    #[cfg_attr(feature = "serde", serde(skip))]
+    solids: solid::Solids,
 }
 
 impl Scene {
     pub fn new(origin: Vec3, poly: Mesh) -> Self {
+        Self::from_parts(Camera::new(origin), vec![poly], Style::default())
+    }
+
+    pub fn from_parts(camera: Camera, meshes: Vec<Mesh>, style: Style) -> Self {
         Self {
-            camera: Camera::new(origin),
-            meshes: vec![poly],
+            camera,
+            meshes,
+            style,
+            polytopes: vec![],
+            morphs: vec![],
+            graph: vec![],
+            solids: solid::Solids::default(),
         }
     }
+
+    // Adds the polytope's shadow to the meshes and keeps it up to date as the polytope turns. Its
+    // fields are public, so it gets checked first.
+    pub fn add_polytope(&mut self, polytope: impl Into<Hyper>) -> Result<(), PolytopeError> {
+        let polytope = polytope.into();
+        polytope.validate()?;
+        self.meshes.push(polytope.to_mesh());
+        self.polytopes.push((polytope, self.meshes.len() - 1));
+        Ok(())
+    }
+
+    // Runs `morph` on the mesh at index `mesh`, which should be the one it starts from. Its
+    // frames are public, so it gets checked first.
+    pub fn add_morph(&mut self, mesh: usize, morph: Morph) -> Result<(), MorphError> {
+        morph.validate()?;
+        self.morphs.push((morph, mesh));
+        Ok(())
+    }
+
+    // Adds a mesh to the scene (without drawing it on its own once a node uses it), for nodes to
+    // refer to by the index this hands back.
+    pub fn add_mesh(&mut self, mesh: Mesh) -> usize {
+        self.meshes.push(mesh);
+        self.meshes.len() - 1
+    }
+
+    pub fn add_node(&mut self, node: Node) {
+        self.graph.push(node);
+    }
 }
 
 // maybe break this up into input(), step(), and render()?
 impl Simulation for Scene {
     fn go(&mut self, ctx: &SystemContext, dims: Vec2) {
         // no input to grab
+        hyper::tic(&mut self.polytopes, &mut self.meshes);
+        morph::tic(&mut self.morphs, &mut self.meshes);
+        graph::tic(&mut self.graph);
         tic(&dims, &self.camera, &mut self.meshes);
-        render(&ctx.canvas_ctx, &dims, &self.camera, &mut self.meshes);
+        render(&ctx.canvas_ctx, &dims, &self.camera, &self.style, &mut self.meshes, &self.graph, &mut self.solids);
     }
 }
 
-pub fn tic(_dims: &Vec2, _camera: &Camera, meshes: &mut Vec<Mesh>) {
+pub fn tic(_dims: &Vec2, _camera: &Camera, meshes: &mut [Mesh]) {
     for mesh in meshes.iter_mut() {
-        mesh.rotation.coord[0] += 0.005;
-        mesh.rotation.coord[1] += 0.005;
-        /*
-        mesh.rotation.coord[2] += 0.0005;
-        */
+        mesh.rotation = (mesh.spin * mesh.rotation).normal();
     }
 }
 
-pub fn render(canvas_ctx: &web_sys::CanvasRenderingContext2d, dims: &Vec2, camera: &Camera, meshes: &mut Vec<Mesh>) {
-    let colors = [
-        JsValue::from_str(&format!("#{:0>6x}", 0xff00ff)), // pink?
-        JsValue::from_str(&format!("#{:0>6x}", 0xff0000)), // red
-        JsValue::from_str(&format!("#{:0>6x}", 0x00ff00)), // lime green
-        JsValue::from_str(&format!("#{:0>6x}", 0x00bfff)), // deep-sky blue
-        JsValue::from_str(&format!("#{:0>6x}", 0xf0ead6)), // yellowish-white/eggshell
-        JsValue::from_str(&format!("#{:0>6x}", 0x6f4e37)), // coffee
-        JsValue::from_str(&format!("#{:0>6x}", 0xb00b1e)),
-        JsValue::from_str(&format!("#{:0>6x}", 0xc0ffee)),
-    ];
-    let line_color = &colors[0];
-    let bubble_color = &colors[3];
-    /*
-    let line_color = &JsValue::from_str(&format!("#{:0>6x}", 0xffffff));
-    let bubble_color = &JsValue::from_str(&format!("#{:0>6x}", 0x000000));
-    */
+// Everything a frame draws, already on the screen. Polygons are back to front with how brightly
+// each is lit.
+pub struct Frame {
+    pub points: Vec<Vec2>,
+    // (from, to, visible)
+    pub lines: Vec<(Vec2, Vec2, bool)>,
+    pub polygons: Vec<(Vec<Vec2>, Element)>,
+}
+
+// All the sums for a frame and none of the drawing, so it can run (and be timed) without a canvas.
+// `solids` only gets used (and brought up to date) when drawing solid.
+pub fn frame(dims: &Vec2, camera: &Camera, style: &Style, meshes: &[Mesh], graph: &[Node], solids: &mut solid::Solids) -> Frame {
+    // each mesh to draw and where (the graph can draw a mesh more than once)
+    let placements = graph::placements(meshes, graph);
 
     // calculate view_matrix from camera
     let view_matrix = Mat4::look_at_lh(camera.origin, camera.target, camera.up);
@@ -76,68 +226,134 @@ pub fn render(canvas_ctx: &web_sys::CanvasRenderingContext2d, dims: &Vec2, camer
     let height = dims.y();
     let projection_matrix = Mat4::perspective_fov_lh(0.78, width / height, 0.01, 1.0);
 
+    // every face in the scene, in view space, if we care about hidden lines
+    let occluders = match style.hidden_lines {
+        HiddenLines::Show => vec![],
+        _ => placements.iter()
+            .enumerate()
+            .flat_map(|(owner, (mesh, world_matrix))| hidden::occluders(&meshes[*mesh], &(*world_matrix * view_matrix), owner))
+            .collect::<Vec<hidden::Occluder>>(),
+    };
+
+    // every face in the scene, in view space and in the order to paint them
+    let polygons = match &style.solid {
+        None => vec![],
+        Some(shading) => {
+            // a direction, so leave out the view's translation
+            let light = Vec3::transform_coordinates(&shading.light, &view_matrix)
+                - Vec3::transform_coordinates(&Vec3::zeroes(), &view_matrix);
+            solids.update(meshes);
+            let placed = placements.iter()
+                .filter_map(|(mesh, world_matrix)| Some((solids.get(*mesh)?, *world_matrix * view_matrix)))
+                .collect::<Vec<(&solid::Solid, Mat4)>>();
+            solid::paint_order(&placed, &light, shading.ambient)
+        },
+    };
+
+    let polygons = polygons.iter()
+        .map(|polygon| (polygon.corners
+            .iter()
+            .map(|corner| Vec2::project(dims, corner, &projection_matrix))
+            .collect::<Vec<Vec2>>(), polygon.shade))
+        .collect();
+
     let mut points = Vec::<Vec2>::new();
-    let mut lines = Vec::<(Vec2, Vec2)>::new();
-    for mesh in meshes.iter_mut() {
-        // calculate the world_matrix by multiplying the rotation of the mesh with its position
-        let world_matrix = Mat4::rotation_yaw_pitch_roll(
-                            mesh.rotation.y(),
-                            mesh.rotation.x(),
-                            mesh.rotation.z()) *
-                                Mat4::translation(
-                                    mesh.origin.x(),
-                                    mesh.origin.y(),
-                                    mesh.origin.z());
-
-        let transform_matrix = world_matrix * view_matrix.clone() * projection_matrix.clone();
+    let mut lines = Vec::<(Vec2, Vec2, bool)>::new();
+    for (owner, (mesh, world_matrix)) in placements.iter().enumerate() {
+        let mesh = &meshes[*mesh];
+        let world_matrix = *world_matrix;
+
+        let world_view_matrix = world_matrix * view_matrix;
+        let transform_matrix = world_view_matrix * projection_matrix;
 
         for vertex in &mesh.vertices {
-            let projected_coord = Vec2::project(&dims, vertex, &transform_matrix);
+            let projected_coord = Vec2::project(dims, vertex, &transform_matrix);
             points.push(projected_coord);
         }
 
-        for (a, b) in mesh.lines
+        // `Mesh::new` already checked these but the fields are public; skip a bad line rather
+        // than take the whole page down
+        for (&(i, j), a, b) in mesh.lines
             .iter()
-            .map(|(a,b)| (&mesh.vertices[*a], &mesh.vertices[*b]))
+            .filter_map(|line| Some((line, mesh.vertices.get(line.0)?, mesh.vertices.get(line.1)?)))
         {
-            let projected_coord_a = Vec2::project(&dims, a, &transform_matrix);
-            let projected_coord_b = Vec2::project(&dims, b, &transform_matrix);
-            lines.push((projected_coord_a, projected_coord_b));
+            if occluders.is_empty() {
+                let projected_coord_a = Vec2::project(dims, a, &transform_matrix);
+                let projected_coord_b = Vec2::project(dims, b, &transform_matrix);
+                lines.push((projected_coord_a, projected_coord_b, true));
+                continue;
+            }
+
+            let view_a = Vec3::transform_coordinates(a, &world_view_matrix);
+            let view_b = Vec3::transform_coordinates(b, &world_view_matrix);
+            let along = view_b - view_a;
+            for (from, to, visible) in hidden::spans(&view_a, &view_b, (owner, i, j), &occluders) {
+                let projected_coord_a = Vec2::project(dims, &(view_a + along.scale(from)), &projection_matrix);
+                let projected_coord_b = Vec2::project(dims, &(view_a + along.scale(to)), &projection_matrix);
+                lines.push((projected_coord_a, projected_coord_b, visible));
+            }
         }
     }
 
+    Frame {
+        points,
+        lines,
+        polygons,
+    }
+}
+
+pub fn render(canvas_ctx: &web_sys::CanvasRenderingContext2d, dims: &Vec2, camera: &Camera, style: &Style, meshes: &mut [Mesh], graph: &[Node], solids: &mut solid::Solids) {
+    let Frame { points, lines, polygons } = frame(dims, camera, style, meshes, graph, solids);
+
     canvas_ctx.save();
 
     /*
-    canvas_ctx.set_fill_style(line_color);
+    canvas_ctx.set_fill_style_str(&style.line_color);
     canvas_ctx.fill_rect(0.0, 0.0, dims.x(), dims.y());
     */
 
     // Make vertex bubble mask
     canvas_ctx.begin_path();
-    canvas_ctx.set_fill_style(bubble_color);
+    canvas_ctx.set_fill_style_str(&style.bubble_color);
     for point in points {
-        draw_point(canvas_ctx, &point);
+        draw_point(canvas_ctx, &point, style.bubble_radius);
     }
     canvas_ctx.fill();
     canvas_ctx.close_path();
     canvas_ctx.clip();
 
-    for (a, b) in lines.iter() {
-        draw_line(line_color, canvas_ctx, a, b);
+    if let Some(shading) = &style.solid {
+        for (corners, brightness) in polygons.iter() {
+            draw_polygon(&shade(&shading.face_color, *brightness), canvas_ctx, corners);
+        }
+    }
+
+    for (a, b, visible) in lines.iter() {
+        match (visible, style.hidden_lines) {
+            (true, _) | (false, HiddenLines::Show) =>
+                draw_line(&style.line_color, style.line_width, canvas_ctx, a, b),
+            (false, HiddenLines::Skip) => {},
+            (false, HiddenLines::Dash) =>
+                draw_dashed_line(&style.line_color, style.line_width, canvas_ctx, a, b),
+            (false, HiddenLines::Fade) => {
+                canvas_ctx.set_global_alpha(FADE_ALPHA);
+                draw_line(&style.line_color, style.line_width, canvas_ctx, a, b);
+                canvas_ctx.set_global_alpha(1.0);
+            },
+        }
     }
 
     canvas_ctx.restore();
 }
 
-pub fn draw_point(canvas_ctx: &web_sys::CanvasRenderingContext2d, coord: &Vec2) {
+pub fn draw_point(canvas_ctx: &web_sys::CanvasRenderingContext2d, coord: &Vec2, radius: Element) {
     canvas_ctx.move_to(coord.x(), coord.y());
-    canvas_ctx.arc(coord.x(), coord.y(), 100.0, 0.0, 2.0 * 3.14159).expect("better be defined");
+    canvas_ctx.arc(coord.x(), coord.y(), radius, 0.0, 2.0 * PI).expect("better be defined");
 }
 
-pub fn draw_line(color: &JsValue, canvas_ctx: &web_sys::CanvasRenderingContext2d, coord_a: &Vec2, coord_b: &Vec2) {
-    canvas_ctx.set_stroke_style(color);
-    canvas_ctx.set_line_width(10.0);
+pub fn draw_line(color: &str, width: Element, canvas_ctx: &web_sys::CanvasRenderingContext2d, coord_a: &Vec2, coord_b: &Vec2) {
+    canvas_ctx.set_stroke_style_str(color);
+    canvas_ctx.set_line_width(width);
 
     canvas_ctx.begin_path();
     canvas_ctx.move_to(coord_a.x(), coord_a.y());
@@ -146,3 +362,58 @@ pub fn draw_line(color: &JsValue, canvas_ctx: &web_sys::CanvasRenderingContext2d
 
     canvas_ctx.stroke();
 }
+
+pub fn draw_polygon(color: &str, canvas_ctx: &web_sys::CanvasRenderingContext2d, corners: &[Vec2]) {
+    let Some((first, rest)) = corners.split_first() else {
+        return;
+    };
+
+    canvas_ctx.set_fill_style_str(color);
+    // stroking the edges in the same color covers the hairline gaps the canvas leaves between
+    // neighbouring polygons
+    canvas_ctx.set_stroke_style_str(color);
+    canvas_ctx.set_line_width(1.0);
+
+    canvas_ctx.begin_path();
+    canvas_ctx.move_to(first.x(), first.y());
+    for corner in rest {
+        canvas_ctx.line_to(corner.x(), corner.y());
+    }
+    canvas_ctx.close_path();
+
+    canvas_ctx.fill();
+    canvas_ctx.stroke();
+}
+
+// "#rrggbb" darkened by `amount` (0 is black, 1 leaves it be); anything else is returned as is
+pub fn shade(color: &str, amount: Element) -> String {
+    let rgb = color.strip_prefix('#')
+        .filter(|hex| hex.len() == 6)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+    let Some(rgb) = rgb else {
+        return color.to_string();
+    };
+
+    let amount = amount.clamp(0.0, 1.0);
+    let channel = |shift: u32| ((((rgb >> shift) & 0xff) as Element) * amount).round() as u32;
+    format!("#{:0>2x}{:0>2x}{:0>2x}", channel(16), channel(8), channel(0))
+}
+
+pub fn draw_dashed_line(color: &str, width: Element, canvas_ctx: &web_sys::CanvasRenderingContext2d, coord_a: &Vec2, coord_b: &Vec2) {
+    let along = *coord_b - *coord_a;
+    let length = along.magnitude();
+    let dash = width * DASH_LENGTH;
+    if length == 0.0 || dash <= 0.0 {
+        return;
+    }
+
+    // every other dash-length stretch, starting with a dash
+    let mut at = 0.0;
+    while at < length {
+        let end = (at + dash).min(length);
+        draw_line(color, width, canvas_ctx,
+            &(*coord_a + along.scale(at / length)),
+            &(*coord_a + along.scale(end / length)));
+        at += 2.0 * dash;
+    }
+}
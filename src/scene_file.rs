@@ -0,0 +1,638 @@
+//! Scene description files.
+//!
+//! Rather than hand-coding every scene in `main.rs` we can describe one in JSON or RON and load it
+//! with `SceneFile::from_json`/`SceneFile::from_ron` followed by `SceneFile::into_scene` (which
+//! validates everything before building a `Scene`). A scene looks like this (JSON):
+//!
+//! ```text
+//! {
+//!     "camera": { "origin": [0.0, 0.0, 10.0], "target": [0.0, 0.0, 0.0], "up": [0.0, 1.0, 0.0] },
+//!     "style": {
+//!         "line_color": "#ff00ff",
+//!         "bubble_color": "#00bfff",
+//!         "line_width": 10.0,
+//!         "bubble_radius": 100.0
+//!     },
+//!     "meshes": [
+//!         { "shape": "cube", "spin": [0.005, 0.005, 0.0] },
+//!         {
+//!             "shape": { "custom": {
+//!                 "vertices": [[-1.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+//!                 "lines": [[0, 1], [1, 2], [2, 0]]
+//!             } },
+//!             "origin": [3.0, 0.0, 0.0],
+//!             "rotation": [0.0, 0.0, 0.0]
+//!         }
+//!     ]
+//! }
+//! ```
+//!
+//! and the same thing in RON:
+//!
+//! ```text
+//! (
+//!     camera: (origin: (0.0, 0.0, 10.0), target: (0.0, 0.0, 0.0), up: (0.0, 1.0, 0.0)),
+//!     style: (line_color: "#ff00ff", bubble_color: "#00bfff", line_width: 10.0, bubble_radius: 100.0),
+//!     meshes: [
+//!         (shape: cube, spin: (0.005, 0.005, 0.0)),
+//!         (
+//!             shape: custom(
+//!                 vertices: [(-1.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)],
+//!                 lines: [(0, 1), (1, 2), (2, 0)],
+//!             ),
+//!             origin: (3.0, 0.0, 0.0),
+//!             rotation: (0.0, 0.0, 0.0),
+//!         ),
+//!     ],
+//! )
+//! ```
+//!
+//! The page draws one with `?scene=` (see `main.rs`): the name of a file in `scenes/` (they're
+//! built in) or a whole scene written out, JSON if it starts with `{` and RON if it starts with
+//! `(`.
+//!
+//! Fields:
+//!
+//! - `camera` (optional): where we look from (`origin`), what we look at (`target`, defaults to
+//!   the world origin) and which way is `up` (defaults to +y). Defaults to `Camera::new` at
+//!   `(0, 0, 10)`.
+//! - `style` (optional): `line_color` and `bubble_color` are `#rrggbb` strings, `line_width` and
+//!   `bubble_radius` are in pixels and `hidden_lines` (`show`, `skip`, `dash` or `fade`) says what
+//!   to do with lines behind faces. `solid`, if given, fills in faces: `face_color` (`#rrggbb`) is
+//!   lit by a far off light shining along `light` with `ambient` (0 to 1) the least it gets; each
+//!   defaults to `Shading::default`. Any field left out takes its value from `Style::default`.
+//! - `meshes` (required, at least one): a `shape` from the list below and, optionally, `origin`,
+//!   `rotation` (yaw/pitch/roll style Euler angles, radians), `spin` (how far it turns every
+//!   frame, the same sort of angles) and `subdivide` to smooth a shape with faces (`scheme` is
+//!   `catmull_clark` or `loop`, `levels` at most 5).
+//!
+//! Shapes, as written in JSON (RON drops the quotes and braces, e.g. `prism(6)` or
+//! `torus(major_radius: 1.0, ..)`). Sizes have to be positive. Anything that could build a huge
+//! mesh out of a few characters has a cap; the parametric surfaces (sphere to Klein bottle) take
+//! at most 256 steps each way.
+//!
+//! - `"cube"`, `"tetra_cube"`
+//! - `{"platonic": "icosahedron"}`: `tetrahedron`, `cube`, `octahedron`, `dodecahedron` or
+//!   `icosahedron`
+//! - `{"archimedean": "snub_cube"}`: any of the thirteen, by name (see `polyhedra.rs`)
+//! - `{"prism": 6}`, `{"antiprism": 6}`, `{"bipyramid": 6}`: 3 to 1000 sides
+//! - `{"uv_sphere": {"u_steps", "v_steps"}}`: `u_steps` from 3, `v_steps` from 1
+//! - `{"torus": {"major_radius", "minor_radius", "u_steps", "v_steps"}}`: `u_steps` from 3, `v_steps` from 3
+//! - `{"cylinder": {"radius", "height", "u_steps", "v_steps"}}`: `u_steps` from 3, `v_steps` from 1
+//! - `{"cone": {"radius", "height", "u_steps", "v_steps"}}`: `u_steps` from 3, `v_steps` from 1
+//! - `{"mobius": {"width", "u_steps", "v_steps"}}`: `u_steps` from 3, `v_steps` from 1
+//! - `{"klein_bottle": {"radius", "u_steps", "v_steps"}}`: `u_steps` from 3, `v_steps` from 3
+//! - `{"fractal": {"kind", "depth"}}`: `menger_sponge` to depth 3, `sierpinski_tetrahedron` 7,
+//!   `octahedron_flake` 5 or `koch_curve` 7
+//! - `{"conway": "tkD"}`: Conway notation (see `conway.rs`), at most 10000 lines in the result
+//!   and 1000 sides to a seed
+//! - `{"l_system": {"preset", "iterations"}}`: `plant` to 5 iterations, `bush` 5, `tree` 6,
+//!   `hilbert` 3, `dragon` 12 or `terdragon` 7 (see `lsystem.rs`)
+//! - `{"text": {"text", "depth"}}`: words in the vector font (see `text.rs`) pulled out `depth`
+//!   deep, 0 for flat lines
+//! - `{"hull": [[x, y, z], ..]}`: the convex hull of the points
+//! - `{"custom": {"vertices", "lines", "faces"}}`: `vertices` and pairs of indices into them for
+//!   `lines`; `faces` (lists of indices, optional) get their edges added to the lines
+//! - `{"obj": "v 0 0 0\n.."}`: the text of a Wavefront OBJ file
+//!
+//! Unknown fields are rejected so typos don't silently turn into defaults.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::camera::Camera;
+use crate::conway;
+use crate::fractal::Fractal;
+use crate::lsystem::Preset;
+use crate::mesh::{Face, Line, Mesh};
+use crate::point::{Element, Quaternion, Vec3};
+use crate::polyhedra::{Archimedean, Platonic};
+use crate::render::{HiddenLines, Scene, Shading, Style};
+use crate::subdivide;
+use crate::subdivide::Scheme;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneFile {
+    #[serde(default)]
+    pub camera: CameraDesc,
+    #[serde(default)]
+    pub style: StyleDesc,
+    pub meshes: Vec<MeshDesc>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraDesc {
+    pub origin: Vec3,
+    #[serde(default = "Vec3::zeroes")]
+    pub target: Vec3,
+    #[serde(default = "default_up")]
+    pub up: Vec3,
+}
+
+fn default_up() -> Vec3 {
+    Vec3::new([0.0, 1.0, 0.0])
+}
+
+impl Default for CameraDesc {
+    fn default() -> Self {
+        Self {
+            origin: Vec3::new([0.0, 0.0, 10.0]),
+            target: Vec3::zeroes(),
+            up: default_up(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StyleDesc {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bubble_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_width: Option<Element>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bubble_radius: Option<Element>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hidden_lines: Option<HiddenLines>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solid: Option<ShadingDesc>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShadingDesc {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub face_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub light: Option<Vec3>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ambient: Option<Element>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MeshDesc {
+    pub shape: Shape,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Vec3>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<Vec3>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spin: Option<Vec3>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subdivide: Option<SubdivideDesc>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubdivideDesc {
+    pub scheme: Scheme,
+    pub levels: usize,
+}
+
+
+// Each way round a parametric surface.
+const MAX_STEPS: usize = 256;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Shape {
+    Cube,
+    TetraCube,
+    Platonic(Platonic),
+    Archimedean(Archimedean),
+    // the number of sides of the polygon they're built on
+    Prism(usize),
+    Antiprism(usize),
+    Bipyramid(usize),
+    // parametric surfaces, see `parametric.rs`
+    UvSphere { u_steps: usize, v_steps: usize },
+    Torus { major_radius: Element, minor_radius: Element, u_steps: usize, v_steps: usize },
+    Cylinder { radius: Element, height: Element, u_steps: usize, v_steps: usize },
+    Cone { radius: Element, height: Element, u_steps: usize, v_steps: usize },
+    Mobius { width: Element, u_steps: usize, v_steps: usize },
+    KleinBottle { radius: Element, u_steps: usize, v_steps: usize },
+    Fractal { kind: Fractal, depth: usize },
+    // Conway polyhedron notation, e.g. "tkD"
+    Conway(String),
+    // see `lsystem.rs`
+    LSystem { preset: Preset, iterations: usize },
+    // words in the vector font, see `text.rs`
+    Text { text: String, depth: Element },
+    // the convex hull of a bunch of points
+    Hull(Vec<Vec3>),
+    Custom {
+        vertices: Vec<Vec3>,
+        #[serde(default)]
+        lines: Vec<Line>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        faces: Vec<Face>,
+    },
+    // the contents of a Wavefront OBJ file
+    Obj(String),
+}
+
+#[derive(Debug)]
+pub enum SceneFileError {
+    Json(serde_json::Error),
+    Ron(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "malformed JSON scene: {}", e),
+            Self::Ron(e) => write!(f, "malformed RON scene: {}", e),
+            Self::Invalid(msg) => write!(f, "invalid scene: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SceneFileError {}
+
+fn invalid<T>(msg: String) -> Result<T, SceneFileError> {
+    Err(SceneFileError::Invalid(msg))
+}
+
+fn check_finite(what: &str, v: &Vec3) -> Result<(), SceneFileError> {
+    if v.coord.iter().all(|x| x.is_finite()) {
+        Ok(())
+    } else {
+        invalid(format!("{} has a non-finite coordinate: {:?}", what, v.coord))
+    }
+}
+
+fn check_color(what: &str, color: &str) -> Result<(), SceneFileError> {
+    let hex = color.strip_prefix('#').unwrap_or("");
+    if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        invalid(format!("{} must look like \"#rrggbb\", got {:?}", what, color))
+    }
+}
+
+fn check_size(what: &str, size: Element) -> Result<(), SceneFileError> {
+    if size.is_finite() && size > 0.0 {
+        Ok(())
+    } else {
+        invalid(format!("{} must be a positive number, got {}", what, size))
+    }
+}
+
+// (steps, least) for u and v
+fn check_steps(index: usize, u: (usize, usize), v: (usize, usize)) -> Result<(), SceneFileError> {
+    for (name, (steps, least)) in [("u_steps", u), ("v_steps", v)] {
+        if steps < least {
+            return invalid(format!("mesh {} needs {} of at least {}, got {}", index, name, least, steps));
+        }
+        if steps > MAX_STEPS {
+            return invalid(format!("mesh {} can have {} of at most {}, got {}", index, name, MAX_STEPS, steps));
+        }
+    }
+
+    Ok(())
+}
+
+impl SceneFile {
+    // The scenes in scenes/, by file name without the extension.
+    pub fn builtin(name: &str) -> Option<Result<Self, SceneFileError>> {
+        match name {
+            "cube" => Some(Self::from_json(include_str!("../scenes/cube.json"))),
+            "pair" => Some(Self::from_ron(include_str!("../scenes/pair.ron"))),
+            _ => None,
+        }
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, SceneFileError> {
+        serde_json::from_str(text).map_err(SceneFileError::Json)
+    }
+
+    // Optional fields in RON are written bare rather than as `Some(..)`.
+    pub fn from_ron(text: &str) -> Result<Self, SceneFileError> {
+        ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+            .from_str(text)
+            .map_err(SceneFileError::Ron)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("scene descriptions are always representable")
+    }
+
+    pub fn to_ron(&self) -> String {
+        let config = ron::ser::PrettyConfig::default()
+            .extensions(ron::extensions::Extensions::IMPLICIT_SOME);
+        ron::ser::to_string_pretty(self, config)
+            .expect("scene descriptions are always representable")
+    }
+
+    pub fn into_scene(self) -> Result<Scene, SceneFileError> {
+        let camera = self.camera.into_camera()?;
+        let style = self.style.into_style()?;
+
+        if self.meshes.is_empty() {
+            return invalid("a scene needs at least one mesh".to_string());
+        }
+        let meshes = self.meshes
+            .into_iter()
+            .enumerate()
+            .map(|(i, desc)| desc.into_mesh(i))
+            .collect::<Result<Vec<Mesh>, SceneFileError>>()?;
+
+        Ok(Scene::from_parts(camera, meshes, style))
+    }
+}
+
+impl CameraDesc {
+    fn into_camera(self) -> Result<Camera, SceneFileError> {
+        check_finite("camera origin", &self.origin)?;
+        check_finite("camera target", &self.target)?;
+        check_finite("camera up", &self.up)?;
+
+        let facing = self.target - self.origin;
+        if facing.magnitude() == 0.0 {
+            return invalid("camera origin and target are the same point".to_string());
+        }
+        if self.up.cross(&facing).magnitude() == 0.0 {
+            return invalid("camera up must not be zero or parallel to the view direction".to_string());
+        }
+
+        let mut camera = Camera::new(self.origin);
+        camera.target = self.target;
+        camera.up = self.up;
+        Ok(camera)
+    }
+}
+
+impl ShadingDesc {
+    fn into_shading(self) -> Result<Shading, SceneFileError> {
+        let mut shading = Shading::default();
+        if let Some(color) = self.face_color {
+            check_color("solid face_color", &color)?;
+            shading.face_color = color;
+        }
+        if let Some(light) = self.light {
+            check_finite("solid light", &light)?;
+            if light.magnitude() == 0.0 {
+                return invalid("solid light must have a direction".to_string());
+            }
+            shading.light = light;
+        }
+        if let Some(ambient) = self.ambient {
+            if !(0.0..=1.0).contains(&ambient) {
+                return invalid(format!("solid ambient must be between 0 and 1, got {}", ambient));
+            }
+            shading.ambient = ambient;
+        }
+
+        Ok(shading)
+    }
+}
+
+impl StyleDesc {
+    fn into_style(self) -> Result<Style, SceneFileError> {
+        let mut style = Style::default();
+        if let Some(color) = self.line_color {
+            check_color("line_color", &color)?;
+            style.line_color = color;
+        }
+        if let Some(color) = self.bubble_color {
+            check_color("bubble_color", &color)?;
+            style.bubble_color = color;
+        }
+        if let Some(width) = self.line_width {
+            check_size("line_width", width)?;
+            style.line_width = width;
+        }
+        if let Some(radius) = self.bubble_radius {
+            check_size("bubble_radius", radius)?;
+            style.bubble_radius = radius;
+        }
+        if let Some(hidden_lines) = self.hidden_lines {
+            style.hidden_lines = hidden_lines;
+        }
+        if let Some(solid) = self.solid {
+            style.solid = Some(solid.into_shading()?);
+        }
+
+        Ok(style)
+    }
+}
+
+impl MeshDesc {
+    fn into_mesh(self, index: usize) -> Result<Mesh, SceneFileError> {
+        let mut mesh = match self.shape {
+            Shape::Cube => Mesh::mk_cube(),
+            Shape::TetraCube => Mesh::mk_tetra_cube(),
+            Shape::Platonic(solid) => Mesh::mk_platonic(solid),
+            Shape::Archimedean(solid) => Mesh::mk_archimedean(solid),
+            Shape::Prism(sides) | Shape::Antiprism(sides) | Shape::Bipyramid(sides) if sides > conway::MAX_SIDES =>
+                return invalid(format!("mesh {} can have at most {} sides, got {}", index, conway::MAX_SIDES, sides)),
+            Shape::Prism(sides) => Mesh::mk_prism(sides)
+                .or_else(|e| invalid(format!("mesh {}: {}", index, e)))?,
+            Shape::Antiprism(sides) => Mesh::mk_antiprism(sides)
+                .or_else(|e| invalid(format!("mesh {}: {}", index, e)))?,
+            Shape::Bipyramid(sides) => Mesh::mk_bipyramid(sides)
+                .or_else(|e| invalid(format!("mesh {}: {}", index, e)))?,
+            Shape::UvSphere { u_steps, v_steps } => {
+                check_steps(index, (u_steps, 3), (v_steps, 1))?;
+                Mesh::mk_uv_sphere(u_steps, v_steps)
+                    .or_else(|e| invalid(format!("mesh {}: {}", index, e)))?
+            },
+            Shape::Torus { major_radius, minor_radius, u_steps, v_steps } => {
+                check_size(&format!("mesh {} major_radius", index), major_radius)?;
+                check_size(&format!("mesh {} minor_radius", index), minor_radius)?;
+                check_steps(index, (u_steps, 3), (v_steps, 3))?;
+                Mesh::mk_torus(major_radius, minor_radius, u_steps, v_steps)
+                    .or_else(|e| invalid(format!("mesh {}: {}", index, e)))?
+            },
+            Shape::Cylinder { radius, height, u_steps, v_steps } => {
+                check_size(&format!("mesh {} radius", index), radius)?;
+                check_size(&format!("mesh {} height", index), height)?;
+                check_steps(index, (u_steps, 3), (v_steps, 1))?;
+                Mesh::mk_cylinder(radius, height, u_steps, v_steps)
+                    .or_else(|e| invalid(format!("mesh {}: {}", index, e)))?
+            },
+            Shape::Cone { radius, height, u_steps, v_steps } => {
+                check_size(&format!("mesh {} radius", index), radius)?;
+                check_size(&format!("mesh {} height", index), height)?;
+                check_steps(index, (u_steps, 3), (v_steps, 1))?;
+                Mesh::mk_cone(radius, height, u_steps, v_steps)
+                    .or_else(|e| invalid(format!("mesh {}: {}", index, e)))?
+            },
+            Shape::Mobius { width, u_steps, v_steps } => {
+                check_size(&format!("mesh {} width", index), width)?;
+                check_steps(index, (u_steps, 3), (v_steps, 1))?;
+                Mesh::mk_mobius(width, u_steps, v_steps)
+                    .or_else(|e| invalid(format!("mesh {}: {}", index, e)))?
+            },
+            Shape::KleinBottle { radius, u_steps, v_steps } => {
+                check_size(&format!("mesh {} radius", index), radius)?;
+                check_steps(index, (u_steps, 3), (v_steps, 3))?;
+                Mesh::mk_klein_bottle(radius, u_steps, v_steps)
+                    .or_else(|e| invalid(format!("mesh {}: {}", index, e)))?
+            },
+            Shape::Fractal { kind, depth } => {
+                if depth > kind.max_depth() {
+                    return invalid(format!(
+                        "mesh {} is too deep, {:?} goes to depth {} at most, got {}",
+                        index, kind, kind.max_depth(), depth,
+                    ));
+                }
+                Mesh::mk_fractal(kind, depth)
+            },
+            Shape::Conway(notation) => Mesh::from_conway(&notation)
+                .or_else(|e| invalid(format!("mesh {} {}", index, e)))?,
+            Shape::LSystem { preset, iterations } => {
+                if iterations > preset.max_iterations() {
+                    return invalid(format!("mesh {} {:?} goes up to {} iterations, got {}", index, preset, preset.max_iterations(), iterations));
+                }
+                Mesh::mk_lsystem(preset, iterations)
+                    .or_else(|e| invalid(format!("mesh {} {}", index, e)))?
+            },
+            Shape::Text { text, depth } => {
+                if !depth.is_finite() || depth < 0.0 {
+                    return invalid(format!("mesh {} depth has to be zero or more, got {}", index, depth));
+                }
+                Mesh::mk_text(&text, depth)
+                    .or_else(|e| invalid(format!("mesh {}: {}", index, e)))?
+            },
+            Shape::Hull(points) => Mesh::convex_hull(&points)
+                .or_else(|e| invalid(format!("mesh {}: {}", index, e)))?,
+            Shape::Custom { vertices, lines, faces } => {
+                let mesh = if faces.is_empty() {
+                    Mesh::new(Vec3::zeroes(), vertices, lines)
+                } else {
+                    Mesh::with_faces(Vec3::zeroes(), vertices, faces)
+                        .and_then(|mut mesh| mesh.add_lines(lines).map(|_| mesh))
+                };
+                mesh.or_else(|e| invalid(format!("mesh {}: {}", index, e)))?
+            },
+            Shape::Obj(text) => Mesh::from_obj(&text)
+                .or_else(|e| invalid(format!("mesh {} OBJ {}", index, e)))?,
+        };
+
+        if let Some(SubdivideDesc { scheme, levels }) = self.subdivide {
+            if levels > subdivide::MAX_LEVELS {
+                return invalid(format!("mesh {} can be subdivided at most {} levels, got {}", index, subdivide::MAX_LEVELS, levels));
+            }
+            mesh = mesh.subdivide(scheme, levels)
+                .or_else(|e| invalid(format!("mesh {} {}", index, e)))?;
+        }
+
+        if let Some(origin) = self.origin {
+            check_finite(&format!("mesh {} origin", index), &origin)?;
+            mesh.origin = origin;
+        }
+        if let Some(rotation) = self.rotation {
+            check_finite(&format!("mesh {} rotation", index), &rotation)?;
+            mesh.rotation = Quaternion::from_yaw_pitch_roll(rotation.y(), rotation.x(), rotation.z());
+        }
+        if let Some(spin) = self.spin {
+            check_finite(&format!("mesh {} spin", index), &spin)?;
+            mesh.spin = Quaternion::from_yaw_pitch_roll(spin.y(), spin.x(), spin.z());
+        }
+
+        Ok(mesh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(json: &str) -> Result<Scene, SceneFileError> {
+        SceneFile::from_json(&format!("{{ \"meshes\": [{{ \"shape\": {} }}] }}", json))?.into_scene()
+    }
+
+    #[test]
+    fn builtin_scenes() {
+        let cube = SceneFile::builtin("cube").expect("there's a cube").expect("it parses");
+        assert_eq!(cube.meshes.len(), 1);
+        assert!(cube.into_scene().is_ok());
+
+        let pair = SceneFile::builtin("pair").expect("there's a pair").expect("it parses");
+        assert_eq!(pair.meshes.len(), 3);
+        assert_eq!(pair.camera.origin, Vec3::new([0.0, 0.0, 14.0]));
+        assert!(pair.into_scene().is_ok());
+
+        assert!(SceneFile::builtin("nothing").is_none());
+    }
+
+    #[test]
+    fn round_trips() {
+        let pair = SceneFile::builtin("pair").expect("there's a pair").expect("it parses");
+        let json = SceneFile::from_json(&pair.to_json()).expect("JSON reads back");
+        assert_eq!(json.to_json(), pair.to_json());
+        let ron = SceneFile::from_ron(&pair.to_ron()).expect("RON reads back");
+        assert_eq!(ron.to_json(), pair.to_json());
+    }
+
+    #[test]
+    fn every_shape() {
+        for json in [
+            "\"cube\"",
+            "\"tetra_cube\"",
+            "{ \"platonic\": \"icosahedron\" }",
+            "{ \"archimedean\": \"snub_cube\" }",
+            "{ \"prism\": 6 }",
+            "{ \"antiprism\": 6 }",
+            "{ \"bipyramid\": 6 }",
+            "{ \"uv_sphere\": { \"u_steps\": 8, \"v_steps\": 4 } }",
+            "{ \"torus\": { \"major_radius\": 1.0, \"minor_radius\": 0.4, \"u_steps\": 8, \"v_steps\": 4 } }",
+            "{ \"cylinder\": { \"radius\": 1.0, \"height\": 2.0, \"u_steps\": 8, \"v_steps\": 1 } }",
+            "{ \"cone\": { \"radius\": 1.0, \"height\": 2.0, \"u_steps\": 8, \"v_steps\": 1 } }",
+            "{ \"mobius\": { \"width\": 0.5, \"u_steps\": 8, \"v_steps\": 1 } }",
+            "{ \"klein_bottle\": { \"radius\": 1.0, \"u_steps\": 8, \"v_steps\": 4 } }",
+            "{ \"fractal\": { \"kind\": \"koch_curve\", \"depth\": 2 } }",
+            "{ \"conway\": \"tkD\" }",
+            "{ \"l_system\": { \"preset\": \"dragon\", \"iterations\": 4 } }",
+            "{ \"text\": { \"text\": \"hi\", \"depth\": 0.2 } }",
+            "{ \"hull\": [[0, 0, 0], [1, 0, 0], [0, 1, 0], [0, 0, 1]] }",
+            "{ \"custom\": { \"vertices\": [[0, 0, 0], [1, 0, 0], [0, 1, 0]], \"faces\": [[0, 1, 2]] } }",
+            "{ \"obj\": \"v 0 0 0\\nv 1 0 0\\nl 1 2\" }",
+        ] {
+            if let Err(e) = shape(json) {
+                panic!("{}: {}", json, e);
+            }
+        }
+    }
+
+    #[test]
+    fn caps() {
+        for json in [
+            "{ \"prism\": 2 }",
+            "{ \"antiprism\": 1001 }",
+            "{ \"uv_sphere\": { \"u_steps\": 2, \"v_steps\": 4 } }",
+            "{ \"torus\": { \"major_radius\": 1.0, \"minor_radius\": 0.4, \"u_steps\": 8, \"v_steps\": 257 } }",
+            "{ \"torus\": { \"major_radius\": -1.0, \"minor_radius\": 0.4, \"u_steps\": 8, \"v_steps\": 4 } }",
+            "{ \"fractal\": { \"kind\": \"menger_sponge\", \"depth\": 4 } }",
+            "{ \"conway\": \"kkkkkkD\" }",
+            "{ \"l_system\": { \"preset\": \"hilbert\", \"iterations\": 4 } }",
+            "{ \"text\": { \"text\": \"hi\", \"depth\": -1.0 } }",
+        ] {
+            assert!(matches!(shape(json), Err(SceneFileError::Invalid(_))), "{}", json);
+        }
+
+        let deep = "{ \"meshes\": [{ \"shape\": \"cube\", \"subdivide\": { \"scheme\": \"loop\", \"levels\": 6 } }] }";
+        assert!(matches!(SceneFile::from_json(deep).and_then(SceneFile::into_scene), Err(SceneFileError::Invalid(_))));
+    }
+
+    #[test]
+    fn bad_files() {
+        assert!(matches!(SceneFile::from_json("{ \"meshes\": [], \"colour\": 1 }"), Err(SceneFileError::Json(_))));
+        assert!(matches!(SceneFile::from_ron("(meshes: [(shape: cube)]"), Err(SceneFileError::Ron(_))));
+        assert!(matches!(SceneFile::from_json("{ \"meshes\": [] }").and_then(SceneFile::into_scene), Err(SceneFileError::Invalid(_))));
+
+        let style = "{ \"style\": { \"line_color\": \"pink\" }, \"meshes\": [{ \"shape\": \"cube\" }] }";
+        assert!(matches!(SceneFile::from_json(style).and_then(SceneFile::into_scene), Err(SceneFileError::Invalid(_))));
+        let camera = "{ \"camera\": { \"origin\": [0, 0, 0] }, \"meshes\": [{ \"shape\": \"cube\" }] }";
+        assert!(matches!(SceneFile::from_json(camera).and_then(SceneFile::into_scene), Err(SceneFileError::Invalid(_))));
+    }
+}
@@ -0,0 +1,482 @@
+// Solid (filled) faces. Like hidden.rs what we hand back is in view space with the eye at the
+// origin looking down +z. Faces are drawn back to front with the painter's algorithm; sorting by
+// depth alone gets overlapping and interlocking faces wrong so instead the faces go into a BSP
+// tree, which splits any face that straddles another's plane and so never has a cycle to untangle.
+//
+// The tree doesn't care where the eye is, only the walk through it does, so each mesh gets its
+// tree built once in model space (see `Solids`) and every frame just walks it from wherever the
+// eye is. Meshes drawn near each other still have to be sorted together, every frame.
+
+use std::cmp::Ordering;
+
+use crate::mesh::{Face, Mesh};
+use crate::point::{Element, Mat4, Vec3};
+use crate::topology::face_area_vector;
+
+// Anything closer than this to the eye gets cut off (matches the near plane in `render`).
+pub const NEAR: Element = 0.01;
+
+// How far off a plane still counts as on it, relative to how far away things are.
+const EPSILON: Element = 1e-9;
+
+// A flat, convex piece of a face in view space and how lit it is (0 is unlit, 1 is fully lit).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    pub corners: Vec<Vec3>,
+    pub shade: Element,
+}
+
+// How brightly a face with `normal` is lit by a light shining in the direction `light`. Faces are
+// lit on whichever side we're looking at so open meshes don't go black on the inside.
+pub fn lambert(normal: &Vec3, towards_eye: &Vec3, light: &Vec3, ambient: Element) -> Element {
+    let normal = if normal.dot(towards_eye) < 0.0 { normal.scale(-1.0) } else { *normal };
+    let diffuse = (-normal.dot(&light.normal())).max(0.0);
+    ambient + (1.0 - ambient) * diffuse
+}
+
+// Cut a convex polygon by the plane normal.x = offset into the parts behind and in front of it.
+fn split(corners: &[Vec3], normal: &Vec3, offset: Element) -> (Option<Vec<Vec3>>, Option<Vec<Vec3>>) {
+    let distances = corners.iter()
+        .map(|c| normal.dot(c) - offset)
+        .collect::<Vec<Element>>();
+
+    let mut back = vec![];
+    let mut front = vec![];
+    for i in 0..corners.len() {
+        let j = (i + 1) % corners.len();
+        let (a, b) = (corners[i], corners[j]);
+        let (da, db) = (distances[i], distances[j]);
+        if da <= 0.0 {
+            back.push(a);
+        }
+        if da >= 0.0 {
+            front.push(a);
+        }
+        if (da < 0.0 && db > 0.0) || (da > 0.0 && db < 0.0) {
+            let crossing = a + (b - a).scale(da / (da - db));
+            back.push(crossing);
+            front.push(crossing);
+        }
+    }
+
+    let keep = |part: Vec<Vec3>| if part.len() >= 3 { Some(part) } else { None };
+    (keep(back), keep(front))
+}
+
+// see `face_area_vector`
+fn area_vector(corners: &[Vec3]) -> Vec3 {
+    (0..corners.len())
+        .map(|i| corners[i].cross(&corners[(i + 1) % corners.len()]))
+        .fold(Vec3::zeroes(), |acc, x| acc + x)
+}
+
+// A flat convex piece and where it came from (a face, or a polygon we were handed).
+type Piece = (Vec<Vec3>, usize);
+
+struct Node {
+    normal: Vec3,
+    offset: Element,
+    // the pieces lying in the plane normal.x = offset
+    on: Vec<Piece>,
+    back: Option<usize>,
+    front: Option<usize>,
+}
+
+// A BSP tree. Pieces with no area can't be seen and are left out.
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+// How many candidates to try splitting with at each node.
+const CANDIDATES: usize = 8;
+
+// Which side of the plane normal.x = offset all the corners of `piece` are on (`Equal` for in
+// it); `None` if the plane cuts it.
+fn side(normal: &Vec3, offset: Element, tolerance: Element, piece: &[Vec3]) -> Option<Ordering> {
+    let (mut behind, mut ahead) = (true, true);
+    for corner in piece {
+        let d = normal.dot(corner) - offset;
+        behind &= d <= tolerance;
+        ahead &= d >= -tolerance;
+    }
+    match (behind, ahead) {
+        (true, true) => Some(Ordering::Equal),
+        (true, false) => Some(Ordering::Less),
+        (false, true) => Some(Ordering::Greater),
+        (false, false) => None,
+    }
+}
+
+// The plane through a piece, with how far off it still counts as on it. `None` for no area.
+fn plane(corners: &[Vec3]) -> Option<(Vec3, Element, Element)> {
+    let normal = area_vector(corners).normal();
+    if normal.coord.iter().any(|x| !x.is_finite()) {
+        return None;
+    }
+    let scale = corners.iter().map(Vec3::magnitude).fold(0.0, Element::max);
+    Some((normal, normal.dot(&corners[0]), EPSILON * scale.max(1.0)))
+}
+
+impl Tree {
+    // Built with our own stack, since a convex mesh makes the tree as deep as it has faces.
+    fn new(pieces: Vec<Piece>) -> Self {
+        let pieces = pieces.into_iter()
+            .filter(|(corners, _)| plane(corners).is_some())
+            .collect::<Vec<Piece>>();
+
+        let mut nodes = Vec::<Node>::new();
+        // the pieces for a subtree and where to hook it on (parent, front?)
+        let mut work = vec![(pieces, None::<(usize, bool)>)];
+        while let Some((mut pieces, parent)) = work.pop() {
+            if pieces.is_empty() {
+                continue;
+            }
+
+            // Splitting makes more pieces (and more splitting further down), so try a few
+            // spread out through the list and go with whichever cuts the fewest.
+            let step = pieces.len().div_ceil(CANDIDATES);
+            let best = (0..pieces.len()).step_by(step)
+                .min_by_key(|&i| {
+                    let (normal, offset, tolerance) = plane(&pieces[i].0).expect("no slivers left");
+                    pieces.iter()
+                        .filter(|(corners, _)| side(&normal, offset, tolerance, corners).is_none())
+                        .count()
+                })
+                .expect("there's at least the one piece");
+            let splitter = pieces.swap_remove(best);
+            let (normal, offset, tolerance) = plane(&splitter.0).expect("no slivers left");
+
+            let mut back = vec![];
+            let mut on = vec![splitter];
+            let mut front = vec![];
+            for (corners, from) in pieces {
+                match side(&normal, offset, tolerance, &corners) {
+                    Some(Ordering::Equal) => on.push((corners, from)),
+                    Some(Ordering::Less) => back.push((corners, from)),
+                    Some(Ordering::Greater) => front.push((corners, from)),
+                    None => {
+                        let (b, f) = split(&corners, &normal, offset);
+                        // a cut can leave a sliver too thin to have a plane of its own
+                        back.extend(b.filter(|b| plane(b).is_some()).map(|b| (b, from)));
+                        front.extend(f.filter(|f| plane(f).is_some()).map(|f| (f, from)));
+                    },
+                }
+            }
+
+            let index = nodes.len();
+            nodes.push(Node { normal, offset, on, back: None, front: None });
+            match parent {
+                Some((parent, true)) => nodes[parent].front = Some(index),
+                Some((parent, false)) => nodes[parent].back = Some(index),
+                None => {},
+            }
+            work.push((back, Some((index, false))));
+            work.push((front, Some((index, true))));
+        }
+
+        Self { nodes }
+    }
+
+    // The pieces in the order to paint them for an eye at `eye`: the far side of each plane, then
+    // the plane, then the near side.
+    fn walk(&self, eye: &Vec3) -> Vec<&Piece> {
+        enum Work {
+            Walk(usize),
+            Draw(usize),
+        }
+
+        let mut out = vec![];
+        let mut work = if self.nodes.is_empty() { vec![] } else { vec![Work::Walk(0)] };
+        while let Some(item) = work.pop() {
+            let index = match item {
+                Work::Draw(index) => {
+                    out.extend(self.nodes[index].on.iter());
+                    continue;
+                },
+                Work::Walk(index) => index,
+            };
+
+            let node = &self.nodes[index];
+            let (far, near) = if node.normal.dot(eye) > node.offset {
+                (node.back, node.front)
+            } else {
+                (node.front, node.back)
+            };
+            // work is a stack so what goes first gets pushed last
+            work.extend(near.map(Work::Walk));
+            work.push(Work::Draw(index));
+            work.extend(far.map(Work::Walk));
+        }
+
+        out
+    }
+}
+
+// Order `polygons` so that drawing them in turn paints nearer ones over farther ones, splitting
+// them as needed.
+pub fn back_to_front(polygons: Vec<Polygon>) -> Vec<Polygon> {
+    let tree = Tree::new(polygons.iter()
+        .enumerate()
+        .map(|(i, polygon)| (polygon.corners.clone(), i))
+        .collect());
+
+    tree.walk(&Vec3::zeroes())
+        .into_iter()
+        .map(|(corners, i)| Polygon { corners: corners.clone(), shade: polygons[*i].shade })
+        .collect()
+}
+
+// A mesh's faces (fanned into triangles) in a BSP tree in model space, along with what they were
+// when it was built so we can tell when it's out of date.
+pub struct Solid {
+    vertices: Vec<Vec3>,
+    faces: Vec<Face>,
+    tree: Tree,
+}
+
+impl Solid {
+    pub fn new(mesh: &Mesh) -> Self {
+        let pieces = mesh.faces.iter()
+            .enumerate()
+            .flat_map(|(f, face)| (1..face.len() - 1)
+                .map(move |i| (vec![mesh.vertices[face[0]], mesh.vertices[face[i]], mesh.vertices[face[i + 1]]], f)))
+            .collect();
+
+        Self {
+            vertices: mesh.vertices.clone(),
+            faces: mesh.faces.clone(),
+            tree: Tree::new(pieces),
+        }
+    }
+
+    fn is_for(&self, mesh: &Mesh) -> bool {
+        self.vertices == mesh.vertices && self.faces == mesh.faces
+    }
+
+    // The faces in view space and back to front, shaded by a light shining in the (view space)
+    // direction `light`. Anything too close to the eye is cut off.
+    pub fn polygons(&self, world_view: &Mat4, light: &Vec3, ambient: Element) -> Vec<Polygon> {
+        let Some(eye) = world_view.inverse().map(|inverse| Vec3::transform_coordinates(&Vec3::zeroes(), &inverse)) else {
+            // squashed flat, nothing to see
+            return vec![];
+        };
+
+        let view = self.vertices.iter()
+            .map(|v| Vec3::transform_coordinates(v, world_view))
+            .collect::<Vec<Vec3>>();
+        let shades = self.faces.iter()
+            .map(|face| lambert(&face_area_vector(&view, face).normal(), &view[face[0]].scale(-1.0), light, ambient))
+            .collect::<Vec<Element>>();
+
+        self.tree.walk(&eye)
+            .into_iter()
+            .filter_map(|(corners, f)| {
+                let corners = corners.iter()
+                    .map(|c| Vec3::transform_coordinates(c, world_view))
+                    .collect::<Vec<Vec3>>();
+                let (_, front) = split(&corners, &Vec3::new([0.0, 0.0, 1.0]), NEAR);
+                Some(Polygon { corners: front?, shade: shades[*f] })
+            })
+            .collect()
+    }
+}
+
+// One `Solid` for each mesh in a scene, kept up to date as meshes change shape.
+#[derive(Default)]
+pub struct Solids {
+    solids: Vec<Option<Solid>>,
+}
+
+impl Solids {
+    // Rebuilds the trees for any mesh that changed since last time. Meshes without faces don't
+    // get one.
+    pub fn update(&mut self, meshes: &[Mesh]) {
+        self.solids.resize_with(meshes.len(), || None);
+        for (solid, mesh) in self.solids.iter_mut().zip(meshes.iter()) {
+            if mesh.faces.is_empty() {
+                *solid = None;
+            } else if !solid.as_ref().is_some_and(|solid| solid.is_for(mesh)) {
+                *solid = Some(Solid::new(mesh));
+            }
+        }
+    }
+
+    pub fn get(&self, mesh: usize) -> Option<&Solid> {
+        self.solids.get(mesh)?.as_ref()
+    }
+}
+
+// A ball around everything in `points`; not the smallest but near enough.
+fn ball(points: impl Iterator<Item = Vec3> + Clone) -> Option<(Vec3, Element)> {
+    let (lo, hi) = points.clone().fold(None, |acc: Option<(Vec3, Vec3)>, p| Some(match acc {
+        None => (p, p),
+        Some((lo, hi)) => (
+            Vec3::new(std::array::from_fn(|i| lo.coord[i].min(p.coord[i]))),
+            Vec3::new(std::array::from_fn(|i| hi.coord[i].max(p.coord[i]))),
+        ),
+    }))?;
+    let centre = (lo + hi).scale(0.5);
+    let radius = points.map(|p| (p - centre).magnitude()).fold(0.0, Element::max);
+    Some((centre, radius))
+}
+
+// The smallest ball around two balls.
+fn both(a: (Vec3, Element), b: (Vec3, Element)) -> (Vec3, Element) {
+    let ((ca, ra), (cb, rb)) = (a, b);
+    let apart = (cb - ca).magnitude();
+    if apart + rb <= ra {
+        return a;
+    }
+    if apart + ra <= rb {
+        return b;
+    }
+    let radius = (apart + ra + rb) / 2.0;
+    (ca + (cb - ca).scale((radius - ra) / apart), radius)
+}
+
+// Every placed solid's faces in view space, in the order to paint them. Each solid sorts its own
+// faces with its tree; solids whose bounding balls overlap get sorted together from scratch,
+// and the rest are painted a ball at a time. Balls that don't overlap can't get in front of each
+// other in a cycle, and sorting them by the eye's power (distance to the centre squared less the
+// radius squared) puts them in the right order.
+pub fn paint_order(placed: &[(&Solid, Mat4)], light: &Vec3, ambient: Element) -> Vec<Polygon> {
+    // (ball, polygons, how many solids are in it)
+    let mut groups = placed.iter()
+        .filter_map(|(solid, world_view)| {
+            let polygons = solid.polygons(world_view, light, ambient);
+            let ball = ball(polygons.iter().flat_map(|polygon| polygon.corners.iter().copied()))?;
+            Some((ball, polygons, 1))
+        })
+        .collect::<Vec<((Vec3, Element), Vec<Polygon>, usize)>>();
+
+    // merge overlapping balls until there are none
+    let mut i = 0;
+    while i < groups.len() {
+        let overlaps = (i + 1..groups.len()).find(|&j| {
+            let ((ci, ri), (cj, rj)) = (groups[i].0, groups[j].0);
+            (cj - ci).magnitude() < ri + rj
+        });
+        match overlaps {
+            Some(j) => {
+                let (ball, polygons, count) = groups.swap_remove(j);
+                groups[i].0 = both(groups[i].0, ball);
+                groups[i].1.extend(polygons);
+                groups[i].2 += count;
+                // the bigger ball might overlap ones already checked
+                i = 0;
+            },
+            None => i += 1,
+        }
+    }
+
+    let power = |(centre, radius): (Vec3, Element)| centre.dot(&centre) - radius * radius;
+    groups.sort_by(|lhs, rhs| power(rhs.0).total_cmp(&power(lhs.0)));
+    groups.into_iter()
+        .flat_map(|(_, polygons, count)| if count > 1 { back_to_front(polygons) } else { polygons })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(z: Element, shade: Element) -> Polygon {
+        Polygon {
+            corners: vec![
+                Vec3::new([-1.0, -1.0, z]),
+                Vec3::new([1.0, -1.0, z]),
+                Vec3::new([1.0, 1.0, z]),
+                Vec3::new([-1.0, 1.0, z]),
+            ],
+            shade,
+        }
+    }
+
+    #[test]
+    fn far_before_near() {
+        let sorted = back_to_front(vec![square(5.0, 0.1), square(9.0, 0.2), square(7.0, 0.3)]);
+        let shades = sorted.iter().map(|polygon| polygon.shade).collect::<Vec<Element>>();
+        assert_eq!(shades, vec![0.2, 0.3, 0.1]);
+    }
+
+    #[test]
+    fn straddling_faces_get_split() {
+        // a square tipped back through the middle of one facing the eye
+        let tipped = Polygon {
+            corners: vec![
+                Vec3::new([-1.0, -1.0, 4.0]),
+                Vec3::new([1.0, -1.0, 4.0]),
+                Vec3::new([1.0, 1.0, 6.0]),
+                Vec3::new([-1.0, 1.0, 6.0]),
+            ],
+            shade: 0.9,
+        };
+        let sorted = back_to_front(vec![square(5.0, 0.1), tipped]);
+        let shades = sorted.iter().map(|polygon| polygon.shade).collect::<Vec<Element>>();
+        assert_eq!(shades, vec![0.9, 0.1, 0.9]);
+        assert!(sorted[0].corners.iter().all(|c| c.z() >= 5.0));
+        assert!(sorted[2].corners.iter().all(|c| c.z() <= 5.0));
+    }
+
+    #[test]
+    fn one_tree_from_every_side() {
+        let cube = Mesh::mk_cube();
+        let solid = Solid::new(&cube);
+        let light = Vec3::new([0.0, 0.0, 1.0]);
+
+        // the faces nearest the eye come last wherever it's looking from
+        let (y, z) = (Vec3::new([0.0, 1.0, 0.0]), Vec3::new([0.0, 0.0, 1.0]));
+        for (eye, up) in [([0.0, 0.0, 5.0], y), ([0.0, 0.0, -5.0], y), ([5.0, 0.0, 0.0], y), ([0.0, -5.0, 0.0], z)] {
+            let look = Mat4::look_at_lh(Vec3::new(eye), Vec3::zeroes(), up);
+            let polygons = solid.polygons(&look, &light, 0.2);
+            assert_eq!(polygons.len(), 12);
+            for polygon in &polygons[10..] {
+                assert!(polygon.corners.iter().all(|c| (c.z() - 4.0).abs() < 1e-9), "{:?}", eye);
+            }
+        }
+    }
+
+    #[test]
+    fn trees_follow_their_meshes() {
+        let line = Mesh::new(Vec3::zeroes(), vec![Vec3::zeroes(), Vec3::new([1.0, 1.0, 1.0])], vec![(0, 1)]).expect("a fine line");
+        let mut meshes = vec![Mesh::mk_cube(), line];
+        let mut solids = Solids::default();
+        solids.update(&meshes);
+        assert!(solids.get(0).is_some_and(|solid| solid.is_for(&meshes[0])));
+        assert!(solids.get(1).is_none());
+        assert!(solids.get(2).is_none());
+
+        meshes[0].vertices[0] = Vec3::new([-2.0, 2.0, 2.0]);
+        assert!(!solids.get(0).is_some_and(|solid| solid.is_for(&meshes[0])));
+        solids.update(&meshes);
+        assert!(solids.get(0).is_some_and(|solid| solid.is_for(&meshes[0])));
+    }
+
+    #[test]
+    fn far_meshes_first() {
+        let cube = Solid::new(&Mesh::mk_cube());
+        let light = Vec3::new([0.0, 0.0, 1.0]);
+        let near = Mat4::translation(1.0, 0.0, 5.0);
+        let far = Mat4::translation(-1.0, 0.0, 10.0);
+
+        let polygons = paint_order(&[(&cube, near), (&cube, far)], &light, 0.2);
+        assert_eq!(polygons.len(), 24);
+        assert!(polygons[..12].iter().all(|polygon| polygon.corners.iter().all(|c| c.z() >= 9.0)));
+        assert!(polygons[12..].iter().all(|polygon| polygon.corners.iter().all(|c| c.z() <= 6.0)));
+
+        // overlapping, so sorted together (and nothing's lost)
+        let touching = Mat4::translation(1.5, 0.0, 6.0);
+        let polygons = paint_order(&[(&cube, near), (&cube, touching)], &light, 0.2);
+        assert!(polygons.len() >= 24);
+        assert!(polygons.last().expect("something to draw").corners.iter().all(|c| (c.z() - 4.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn cut_off_at_the_eye() {
+        let cube = Solid::new(&Mesh::mk_cube());
+        let light = Vec3::new([0.0, 0.0, 1.0]);
+        let polygons = cube.polygons(&Mat4::identity(), &light, 0.2);
+        assert!(!polygons.is_empty());
+        assert!(polygons.iter().flat_map(|polygon| polygon.corners.iter()).all(|c| c.z() >= NEAR - 1e-12));
+    }
+}
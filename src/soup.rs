@@ -0,0 +1,235 @@
+// Triangle soups (what STL and PLY give us) turned into wireframes. Drawing every triangle edge of
+// a scanned or CAD model is just noise, so we only keep the "feature" edges: boundaries, edges
+// shared by more than two triangles and creases where the neighbouring faces meet at an angle
+// sharper than some threshold. Coplanar fan diagonals and gently curved patches vanish.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use crate::mesh::{Line, Mesh, MeshError};
+use crate::point::{Element, Vec3};
+
+// 30 degrees between face normals is where most CAD tools start calling an edge "sharp"
+pub const DEFAULT_CREASE_ANGLE: Element = PI / 6.0;
+
+// Vertices closer than this fraction of the model's bounding box diagonal are the same vertex.
+const MERGE_TOLERANCE: Element = 1e-7;
+
+#[derive(Clone, Debug, Default)]
+pub struct TriangleSoup {
+    pub vertices: Vec<Vec3>,
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl TriangleSoup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // For formats (like STL) where every triangle carries its own copy of each corner.
+    pub fn push_triangle(&mut self, corners: [Vec3; 3]) {
+        let base = self.vertices.len();
+        self.vertices.extend_from_slice(&corners);
+        self.triangles.push([base, base + 1, base + 2]);
+    }
+
+    // Collapse coincident vertices, returning where every old vertex ended up along with the
+    // surviving vertices. We bucket on a grid the size of the tolerance and look in the
+    // neighbouring cells too so points straddling a cell boundary still find each other.
+    fn merge_vertices(&self) -> (Vec<usize>, Vec<Vec3>) {
+        let (min, max) = self.vertices.iter()
+            .fold((self.vertices[0], self.vertices[0]), |(min, max), v| {
+                let mut lo = min;
+                let mut hi = max;
+                for i in 0..3 {
+                    lo.coord[i] = lo.coord[i].min(v.coord[i]);
+                    hi.coord[i] = hi.coord[i].max(v.coord[i]);
+                }
+                (lo, hi)
+            });
+        let diagonal = (max - min).magnitude();
+        let tolerance = if diagonal > 0.0 { diagonal * MERGE_TOLERANCE } else { 1.0 };
+
+        let cell = |v: &Vec3| -> [i64; 3] {
+            [
+                (v.x() / tolerance).floor() as i64,
+                (v.y() / tolerance).floor() as i64,
+                (v.z() / tolerance).floor() as i64,
+            ]
+        };
+
+        let mut grid = HashMap::<[i64; 3], Vec<usize>>::new();
+        let mut merged = Vec::<Vec3>::new();
+        let mut remap = Vec::<usize>::with_capacity(self.vertices.len());
+        for v in self.vertices.iter() {
+            let [cx, cy, cz] = cell(v);
+            let mut found = None;
+            'search: for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        if let Some(candidates) = grid.get(&[cx + dx, cy + dy, cz + dz]) {
+                            if let Some(&i) = candidates.iter().find(|&&i| (merged[i] - *v).magnitude() <= tolerance) {
+                                found = Some(i);
+                                break 'search;
+                            }
+                        }
+                    }
+                }
+            }
+
+            let index = found.unwrap_or_else(|| {
+                merged.push(*v);
+                grid.entry([cx, cy, cz]).or_default().push(merged.len() - 1);
+                merged.len() - 1
+            });
+            remap.push(index);
+        }
+
+        (remap, merged)
+    }
+
+    // Build a wireframe out of the feature edges. `crease_angle` is in radians; an edge between
+    // two triangles is kept when their normals differ by more than it. Vertices that don't end up
+    // on any kept edge are dropped so they don't get a bubble of their own.
+    pub fn feature_edges(&self, crease_angle: Element) -> Result<Mesh, MeshError> {
+        if self.vertices.is_empty() {
+            return Err(MeshError::Empty);
+        }
+
+        let (remap, vertices) = self.merge_vertices();
+
+        let mut normals = Vec::<Vec3>::new();
+        // undirected edge -> the triangles (by index into `normals`) that use it and whether they
+        // run along it from its lower vertex to its higher one
+        let mut edges = BTreeMap::<Line, Vec<(usize, bool)>>::new();
+        for triangle in self.triangles.iter() {
+            let [a, b, c] = triangle.map(|i| remap[i]);
+            if a == b || b == c || c == a {
+                continue;
+            }
+
+            let normal = (vertices[b] - vertices[a]).cross(&(vertices[c] - vertices[a]));
+            if normal.magnitude() == 0.0 {
+                // all three corners sit on a line; there's no surface to speak of
+                continue;
+            }
+            normals.push(normal.normal());
+
+            let face = normals.len() - 1;
+            for (p, q) in [(a, b), (b, c), (c, a)] {
+                edges.entry((p.min(q), p.max(q))).or_default().push((face, p < q));
+            }
+        }
+
+        // Soups aren't always wound consistently. Neighbours that are run along their shared edge
+        // in opposite directions; when both go the same way one of them is inside out, so its
+        // normal gets turned around before comparing.
+        let cos_crease = crease_angle.cos();
+        let kept = edges.into_iter()
+            .filter(|(_, faces)| match faces.as_slice() {
+                [(lhs, lhs_forwards), (rhs, rhs_forwards)] => {
+                    let dot = normals[*lhs].dot(&normals[*rhs]);
+                    let dot = if lhs_forwards == rhs_forwards { -dot } else { dot };
+                    dot < cos_crease
+                },
+                // boundary or non-manifold
+                _ => true,
+            })
+            .map(|(edge, _)| edge)
+            .collect::<Vec<Line>>();
+
+        let mut compact = vec![None; vertices.len()];
+        let mut used = Vec::<Vec3>::new();
+        let mut index = |i: usize| -> usize {
+            *compact[i].get_or_insert_with(|| {
+                used.push(vertices[i]);
+                used.len() - 1
+            })
+        };
+        let lines = kept.into_iter()
+            .map(|(a, b)| (index(a), index(b)))
+            .collect::<Vec<Line>>();
+
+        Mesh::new(Vec3::zeroes(), used, lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // two triangles sharing the edge from (0,0,0) to (1,0,0), the second one tipped up out of the
+    // z = 0 plane by `fold` radians
+    fn hinge(fold: Element) -> TriangleSoup {
+        let mut soup = TriangleSoup::new();
+        soup.push_triangle([Vec3::new([0.0, 0.0, 0.0]), Vec3::new([1.0, 0.0, 0.0]), Vec3::new([0.5, 1.0, 0.0])]);
+        soup.push_triangle([Vec3::new([1.0, 0.0, 0.0]), Vec3::new([0.0, 0.0, 0.0]), Vec3::new([0.5, -fold.cos(), fold.sin()])]);
+        soup
+    }
+
+    #[test]
+    fn merges_copies_of_a_vertex() {
+        let mut soup = hinge(0.0);
+        // a third triangle whose corners are only a hair off the others
+        soup.push_triangle([Vec3::new([0.5, 1.0, 1e-12]), Vec3::new([1.0, 1e-12, 0.0]), Vec3::new([2.0, 1.0, 0.0])]);
+
+        let (remap, merged) = soup.merge_vertices();
+        assert_eq!(merged.len(), 5);
+        assert_eq!(remap[6], remap[2]);
+        assert_eq!(remap[7], remap[1]);
+    }
+
+    #[test]
+    fn flat_edges_vanish() {
+        // the shared edge is gone, the four around the outside are boundaries
+        let flat = hinge(0.0).feature_edges(DEFAULT_CREASE_ANGLE).unwrap();
+        assert_eq!(flat.vertices.len(), 4);
+        assert_eq!(flat.lines.len(), 4);
+
+        // folding by less than the crease angle still counts as flat
+        let gentle = hinge(PI / 12.0).feature_edges(DEFAULT_CREASE_ANGLE).unwrap();
+        assert_eq!(gentle.lines.len(), 4);
+    }
+
+    #[test]
+    fn creases_stay() {
+        let sharp = hinge(PI / 3.0).feature_edges(DEFAULT_CREASE_ANGLE).unwrap();
+        assert_eq!(sharp.lines.len(), 5);
+
+        // and a small enough threshold calls any fold a crease
+        let gentle = hinge(PI / 12.0).feature_edges(PI / 24.0).unwrap();
+        assert_eq!(gentle.lines.len(), 5);
+    }
+
+    #[test]
+    fn flipped_triangles() {
+        // the same hinges with the second triangle wound the other way round
+        let flipped = |fold: Element| {
+            let mut soup = TriangleSoup::new();
+            soup.push_triangle([Vec3::new([0.0, 0.0, 0.0]), Vec3::new([1.0, 0.0, 0.0]), Vec3::new([0.5, 1.0, 0.0])]);
+            soup.push_triangle([Vec3::new([0.0, 0.0, 0.0]), Vec3::new([1.0, 0.0, 0.0]), Vec3::new([0.5, -fold.cos(), fold.sin()])]);
+            soup
+        };
+        assert_eq!(flipped(0.0).feature_edges(DEFAULT_CREASE_ANGLE).unwrap().lines.len(), 4);
+        assert_eq!(flipped(PI / 12.0).feature_edges(DEFAULT_CREASE_ANGLE).unwrap().lines.len(), 4);
+        assert_eq!(flipped(PI / 3.0).feature_edges(DEFAULT_CREASE_ANGLE).unwrap().lines.len(), 5);
+    }
+
+    #[test]
+    fn fins_and_slivers() {
+        // a third triangle on the hinge makes it non-manifold, which is always kept
+        let mut soup = hinge(0.0);
+        soup.push_triangle([Vec3::new([0.0, 0.0, 0.0]), Vec3::new([1.0, 0.0, 0.0]), Vec3::new([0.5, 0.0, 1.0])]);
+        assert_eq!(soup.feature_edges(DEFAULT_CREASE_ANGLE).unwrap().lines.len(), 7);
+
+        // triangles with no area add nothing, not even their vertices
+        let mut soup = hinge(0.0);
+        soup.push_triangle([Vec3::new([5.0, 0.0, 0.0]), Vec3::new([6.0, 0.0, 0.0]), Vec3::new([7.0, 0.0, 0.0])]);
+        let mesh = soup.feature_edges(DEFAULT_CREASE_ANGLE).unwrap();
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.lines.len(), 4);
+
+        assert_eq!(TriangleSoup::new().feature_edges(DEFAULT_CREASE_ANGLE).unwrap_err(), MeshError::Empty);
+    }
+}
@@ -0,0 +1,190 @@
+// STL import, both the ASCII and binary flavours. STL is nothing but a list of triangles so all
+// the interesting work happens in `soup`.
+//
+// https://en.wikipedia.org/wiki/STL_(file_format)
+
+
+use crate::error::message_error;
+use crate::mesh::Mesh;
+use crate::point::{Element, Vec3};
+use crate::soup::TriangleSoup;
+
+message_error!(StlError, "STL");
+
+// 80 byte header, a little-endian u32 triangle count and then 50 bytes per triangle
+const BINARY_HEADER: usize = 84;
+const BINARY_TRIANGLE: usize = 50;
+
+fn parse_binary(bytes: &[u8]) -> Result<TriangleSoup, StlError> {
+    if bytes.len() < BINARY_HEADER {
+        return error(format!("file is {} bytes, too short for a binary STL header", bytes.len()));
+    }
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    let expected = count
+        .checked_mul(BINARY_TRIANGLE)
+        .and_then(|body| body.checked_add(BINARY_HEADER));
+    if expected.is_none_or(|expected| bytes.len() < expected) {
+        return error(format!("header promises {} triangles but the file is only {} bytes", count, bytes.len()));
+    }
+
+    let float = |at: usize| -> Element {
+        f32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as Element
+    };
+
+    let mut soup = TriangleSoup::new();
+    for i in 0..count {
+        // skip the 12 byte facet normal, we work out our own
+        let base = BINARY_HEADER + i * BINARY_TRIANGLE + 12;
+        let mut corners = [Vec3::zeroes(); 3];
+        for (j, corner) in corners.iter_mut().enumerate() {
+            let at = base + j * 12;
+            *corner = Vec3::new([float(at), float(at + 4), float(at + 8)]);
+            if !corner.coord.iter().all(|x| x.is_finite()) {
+                return error(format!("triangle {} has a non-finite vertex", i));
+            }
+        }
+        soup.push_triangle(corners);
+    }
+
+    Ok(soup)
+}
+
+fn parse_ascii(text: &str) -> Result<TriangleSoup, StlError> {
+    let mut soup = TriangleSoup::new();
+    let mut corners = Vec::<Vec3>::new();
+    for (i, line) in text.lines().enumerate() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("vertex") => {
+                let coord = words
+                    .map(|word| word.parse::<Element>())
+                    .collect::<Result<Vec<Element>, _>>();
+                match coord.as_deref() {
+                    Ok(&[x, y, z]) if x.is_finite() && y.is_finite() && z.is_finite() => {
+                        corners.push(Vec3::new([x, y, z]))
+                    },
+                    _ => return error(format!("line {}: expected `vertex x y z`", i + 1)),
+                }
+            },
+            Some("endloop") => {
+                let corners = std::mem::take(&mut corners);
+                match corners.as_slice() {
+                    &[a, b, c] => soup.push_triangle([a, b, c]),
+                    _ => return error(format!("line {}: facet has {} vertices instead of 3", i + 1, corners.len())),
+                }
+            },
+            // solid, facet, outer loop, endfacet, endsolid; nothing in them we need
+            _ => {},
+        }
+    }
+    if !corners.is_empty() {
+        return error("file ends in the middle of a facet".to_string());
+    }
+
+    Ok(soup)
+}
+
+impl Mesh {
+    // Binary STL files are allowed to start with "solid" too, so we only believe a file is ASCII
+    // if it says so *and* its size doesn't line up with the binary layout.
+    pub fn from_stl(bytes: &[u8], crease_angle: Element) -> Result<Mesh, StlError> {
+        let looks_binary = bytes.len() >= BINARY_HEADER && {
+            let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+            count.checked_mul(BINARY_TRIANGLE).and_then(|body| body.checked_add(BINARY_HEADER)) == Some(bytes.len())
+        };
+        let soup = if bytes.trim_ascii_start().starts_with(b"solid") && !looks_binary {
+            let text = match std::str::from_utf8(bytes) {
+                Ok(text) => text,
+                Err(_) => return error("ASCII STL is not valid UTF-8".to_string()),
+            };
+            parse_ascii(text)?
+        } else {
+            parse_binary(bytes)?
+        };
+
+        if soup.triangles.is_empty() {
+            return error("no triangles found".to_string());
+        }
+
+        soup.feature_edges(crease_angle)
+            .or_else(|e| error(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::soup::DEFAULT_CREASE_ANGLE;
+
+    // the cube's quads fanned into the 12 triangles an exporter would write
+    fn cube() -> Vec<[Vec3; 3]> {
+        let cube = Mesh::mk_cube();
+        cube.faces.iter()
+            .flat_map(|face| face[1..].windows(2).map(|pair| [face[0], pair[0], pair[1]]).collect::<Vec<_>>())
+            .map(|corners| corners.map(|i| cube.vertices[i]))
+            .collect()
+    }
+
+    fn ascii(triangles: &[[Vec3; 3]]) -> String {
+        let mut text = "solid cube\n".to_string();
+        for triangle in triangles {
+            text.push_str("  facet normal 0 0 0\n    outer loop\n");
+            for v in triangle {
+                text.push_str(&format!("      vertex {} {} {}\n", v.x(), v.y(), v.z()));
+            }
+            text.push_str("    endloop\n  endfacet\n");
+        }
+        text.push_str("endsolid cube\n");
+        text
+    }
+
+    fn binary(triangles: &[[Vec3; 3]]) -> Vec<u8> {
+        // starting the header with "solid" is allowed, and some exporters really do
+        let mut bytes = b"solid but actually binary".to_vec();
+        bytes.resize(80, 0);
+        bytes.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+        for triangle in triangles {
+            bytes.extend_from_slice(&[0; 12]);
+            for v in triangle {
+                for x in v.coord {
+                    bytes.extend_from_slice(&(x as f32).to_le_bytes());
+                }
+            }
+            bytes.extend_from_slice(&[0; 2]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn ascii_and_binary_agree() {
+        let from_ascii = Mesh::from_stl(ascii(&cube()).as_bytes(), DEFAULT_CREASE_ANGLE).unwrap();
+        let from_binary = Mesh::from_stl(&binary(&cube()), DEFAULT_CREASE_ANGLE).unwrap();
+
+        // 36 corners merge back into 8 and the fan diagonals drop out
+        for mesh in [&from_ascii, &from_binary] {
+            assert_eq!(mesh.vertices.len(), 8);
+            assert_eq!(mesh.lines.len(), 12);
+        }
+        assert_eq!(from_ascii.vertices, from_binary.vertices);
+        assert_eq!(from_ascii.lines, from_binary.lines);
+    }
+
+    #[test]
+    fn bad_files() {
+        let message = |bytes: &[u8]| Mesh::from_stl(bytes, DEFAULT_CREASE_ANGLE).unwrap_err().message;
+
+        assert_eq!(message(b"solid x\nfacet normal 0 0 0\nouter loop\nvertex 0 0\n"), "line 4: expected `vertex x y z`");
+        assert_eq!(message(b"solid x\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nendloop\n"), "line 5: facet has 2 vertices instead of 3");
+        assert_eq!(message(b"solid x\nouter loop\nvertex 0 0 0\n"), "file ends in the middle of a facet");
+        assert_eq!(message(b"solid x\nendsolid x\n"), "no triangles found");
+
+        // once the size stops matching, a header starting with "solid" reads as (broken) ASCII
+        let mut truncated = binary(&cube());
+        truncated.truncate(truncated.len() - 1);
+        assert_eq!(message(&truncated), "ASCII STL is not valid UTF-8");
+        truncated[..5].copy_from_slice(b"model");
+        assert_eq!(message(&truncated), format!("header promises 12 triangles but the file is only {} bytes", truncated.len()));
+        assert_eq!(message(&[0; 10]), "file is 10 bytes, too short for a binary STL header");
+    }
+}
@@ -0,0 +1,382 @@
+// Subdivision surfaces. Each level splits every face up and moves every vertex towards a weighted
+// average of its neighbours, so after a few levels a cube is nearly a sphere.
+//
+// - Catmull-Clark turns any polygon into quads (one per corner) through a new vertex in the middle
+//   of the face and one on each edge.
+// - Loop turns every triangle into four through a new vertex on each edge. Faces that aren't
+//   triangles are split into a fan around their middle first.
+//
+// Open surfaces keep their edges: a vertex on the boundary only listens to its two neighbours
+// along it and the corners where more than two boundary edges meet stay where they are. Lines that
+// aren't on any face (and vertices only they use) are carried over untouched.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use crate::error::message_error;
+use crate::mesh::{canonical, Face, Line, Mesh};
+use crate::point::{Element, Vec3};
+
+message_error!(SubdivisionError, "subdivision");
+
+// Most levels anything outside of code asks for; each has about four times the faces of the last.
+pub const MAX_LEVELS: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Scheme {
+    CatmullClark,
+    Loop,
+}
+
+impl Scheme {
+    pub const ALL: [Scheme; 2] = [Scheme::CatmullClark, Scheme::Loop];
+
+    // by its snake_case name, like in scene files
+    pub fn from_name(name: &str) -> Option<Scheme> {
+        Scheme::ALL.into_iter().find(|scheme| format!("{:?}", scheme).to_lowercase() == name.replace('_', ""))
+    }
+}
+
+impl Mesh {
+    // `levels` rounds of `scheme`. Every level multiplies the faces by about four, so don't go
+    // wild. The mesh keeps its origin, rotation and spin.
+    pub fn subdivide(&self, scheme: Scheme, levels: usize) -> Result<Mesh, SubdivisionError> {
+        if self.faces.is_empty() {
+            return error("the mesh has no faces to work on".to_string());
+        }
+
+        let mut vertices = self.vertices.clone();
+        let mut faces = self.faces.clone();
+        for _ in 0..levels {
+            let edges = Edges::new(&faces)?;
+            (vertices, faces) = match scheme {
+                Scheme::CatmullClark => catmull_clark(&vertices, &faces, &edges),
+                Scheme::Loop => {
+                    let (vertices, faces) = triangulate(&vertices, &faces);
+                    let edges = Edges::new(&faces)?;
+                    loop_subdivide(&vertices, &faces, &edges)
+                },
+            };
+        }
+
+        // every level keeps the old vertices at the front, so the loose lines still line up
+        let face_lines = self.face_lines();
+        let loose = self.lines.iter()
+            .filter(|line| !face_lines.contains(line))
+            .copied()
+            .collect::<Vec<Line>>();
+
+        let mut mesh = Mesh::with_faces(self.origin, vertices, faces)
+            .or_else(|e| error(format!("made a bad mesh: {}", e)))?;
+        mesh.add_lines(loose)
+            .or_else(|e| error(format!("made a bad mesh: {}", e)))?;
+        mesh.rotation = self.rotation;
+        mesh.spin = self.spin;
+
+        Ok(mesh)
+    }
+}
+
+// Which faces are on each side of every edge and which vertices each vertex is joined to.
+struct Edges {
+    // in order of first appearance so the new vertices come out the same every time
+    order: Vec<Line>,
+    faces: HashMap<Line, Vec<usize>>,
+    neighbours: HashMap<usize, Vec<usize>>,
+}
+
+impl Edges {
+    fn new(faces: &[Face]) -> Result<Self, SubdivisionError> {
+        let mut order = vec![];
+        let mut sides = HashMap::<Line, Vec<usize>>::new();
+        let mut neighbours = HashMap::<usize, Vec<usize>>::new();
+        for (f, face) in faces.iter().enumerate() {
+            for i in 0..face.len() {
+                let line = canonical((face[i], face[(i + 1) % face.len()]));
+                let on = sides.entry(line).or_default();
+                if on.is_empty() {
+                    order.push(line);
+                    neighbours.entry(line.0).or_default().push(line.1);
+                    neighbours.entry(line.1).or_default().push(line.0);
+                }
+                on.push(f);
+                if on.len() > 2 {
+                    return error(format!("more than two faces meet at the edge {:?}", line));
+                }
+            }
+        }
+
+        Ok(Self {
+            order,
+            faces: sides,
+            neighbours,
+        })
+    }
+
+    fn is_boundary(&self, line: &Line) -> bool {
+        self.faces[line].len() == 1
+    }
+
+    // The neighbours of `vertex` along boundary edges (none for a vertex inside the surface).
+    fn boundary_neighbours(&self, vertex: usize) -> Vec<usize> {
+        self.neighbours.get(&vertex)
+            .map(|around| around.iter()
+                .filter(|&&other| self.is_boundary(&canonical((vertex, other))))
+                .copied()
+                .collect())
+            .unwrap_or_default()
+    }
+
+    // Where a vertex on the boundary goes (or `None` if it isn't on one). Both schemes use the
+    // same rule there, which is what keeps the boundary a nice curve.
+    fn boundary_vertex(&self, vertices: &[Vec3], vertex: usize) -> Option<Vec3> {
+        match self.boundary_neighbours(vertex).as_slice() {
+            [] => None,
+            &[a, b] => Some(vertices[vertex].scale(0.75) + (vertices[a] + vertices[b]).scale(0.125)),
+            // a corner
+            _ => Some(vertices[vertex]),
+        }
+    }
+}
+
+fn centroid(vertices: &[Vec3], face: &Face) -> Vec3 {
+    face.iter()
+        .fold(Vec3::zeroes(), |acc, &v| acc + vertices[v])
+        .scale(1.0 / face.len() as Element)
+}
+
+// New vertices come after the old ones: one per edge then one per face.
+fn catmull_clark(vertices: &[Vec3], faces: &[Face], edges: &Edges) -> (Vec<Vec3>, Vec<Face>) {
+    let face_points = faces.iter()
+        .map(|face| centroid(vertices, face))
+        .collect::<Vec<Vec3>>();
+    let midpoint = |line: &Line| (vertices[line.0] + vertices[line.1]).scale(0.5);
+
+    let edge_index = edges.order.iter()
+        .enumerate()
+        .map(|(i, line)| (*line, vertices.len() + i))
+        .collect::<HashMap<Line, usize>>();
+    let edge_points = edges.order.iter()
+        .map(|line| match edges.faces[line].as_slice() {
+            &[f, g] => (vertices[line.0] + vertices[line.1] + face_points[f] + face_points[g]).scale(0.25),
+            _ => midpoint(line),
+        })
+        .collect::<Vec<Vec3>>();
+
+    // the faces around each vertex
+    let mut around = HashMap::<usize, Vec<usize>>::new();
+    for (f, face) in faces.iter().enumerate() {
+        for &v in face.iter() {
+            around.entry(v).or_default().push(f);
+        }
+    }
+
+    let mut out = (0..vertices.len())
+        .map(|v| {
+            if let Some(moved) = edges.boundary_vertex(vertices, v) {
+                return moved;
+            }
+            let (Some(near), Some(next_to)) = (around.get(&v), edges.neighbours.get(&v)) else {
+                // only on loose lines
+                return vertices[v];
+            };
+            let n = next_to.len() as Element;
+            if n < 3.0 {
+                return vertices[v];
+            }
+            // (Q + 2R + (n - 3)P) / n with Q the average face point and R the average edge middle
+            let q = near.iter().fold(Vec3::zeroes(), |acc, &f| acc + face_points[f]).scale(1.0 / near.len() as Element);
+            let r = next_to.iter()
+                .fold(Vec3::zeroes(), |acc, &other| acc + midpoint(&canonical((v, other))))
+                .scale(1.0 / n);
+            (q + r.scale(2.0) + vertices[v].scale(n - 3.0)).scale(1.0 / n)
+        })
+        .collect::<Vec<Vec3>>();
+    out.extend(edge_points);
+    let face_base = out.len();
+    out.extend(face_points);
+
+    let mut new_faces = vec![];
+    for (f, face) in faces.iter().enumerate() {
+        let n = face.len();
+        for i in 0..n {
+            let (prev, here, next) = (face[(i + n - 1) % n], face[i], face[(i + 1) % n]);
+            new_faces.push(vec![
+                here,
+                edge_index[&canonical((here, next))],
+                face_base + f,
+                edge_index[&canonical((prev, here))],
+            ]);
+        }
+    }
+
+    (out, new_faces)
+}
+
+// Split faces with more than three corners into a fan of triangles around a new vertex in their
+// middle, so Loop has something to work with.
+fn triangulate(vertices: &[Vec3], faces: &[Face]) -> (Vec<Vec3>, Vec<Face>) {
+    let mut vertices = vertices.to_vec();
+    let mut out = vec![];
+    for face in faces.iter() {
+        if face.len() == 3 {
+            out.push(face.clone());
+            continue;
+        }
+        let centre = vertices.len();
+        vertices.push(centroid(&vertices, face));
+        for i in 0..face.len() {
+            out.push(vec![face[i], face[(i + 1) % face.len()], centre]);
+        }
+    }
+
+    (vertices, out)
+}
+
+// New vertices, one per edge, come after the old ones. Every face has to be a triangle.
+fn loop_subdivide(vertices: &[Vec3], faces: &[Face], edges: &Edges) -> (Vec<Vec3>, Vec<Face>) {
+    // the corner of `face` that isn't on `line`
+    let opposite = |face: usize, line: &Line| {
+        *faces[face].iter()
+            .find(|&&v| v != line.0 && v != line.1)
+            .expect("triangles have a third corner")
+    };
+
+    let edge_index = edges.order.iter()
+        .enumerate()
+        .map(|(i, line)| (*line, vertices.len() + i))
+        .collect::<HashMap<Line, usize>>();
+    let edge_points = edges.order.iter()
+        .map(|line| {
+            let (a, b) = (vertices[line.0], vertices[line.1]);
+            match edges.faces[line].as_slice() {
+                &[f, g] => (a + b).scale(0.375) + (vertices[opposite(f, line)] + vertices[opposite(g, line)]).scale(0.125),
+                _ => (a + b).scale(0.5),
+            }
+        })
+        .collect::<Vec<Vec3>>();
+
+    let mut out = (0..vertices.len())
+        .map(|v| {
+            if let Some(moved) = edges.boundary_vertex(vertices, v) {
+                return moved;
+            }
+            let Some(next_to) = edges.neighbours.get(&v) else {
+                return vertices[v];
+            };
+            let n = next_to.len() as Element;
+            // Loop's original weights
+            let beta = (0.625 - (0.375 + 0.25 * (2.0 * PI / n).cos()).powi(2)) / n;
+            let sum = next_to.iter().fold(Vec3::zeroes(), |acc, &other| acc + vertices[other]);
+            vertices[v].scale(1.0 - n * beta) + sum.scale(beta)
+        })
+        .collect::<Vec<Vec3>>();
+    out.extend(edge_points);
+
+    let mut new_faces = vec![];
+    for face in faces.iter() {
+        let [a, b, c] = [face[0], face[1], face[2]];
+        let ab = edge_index[&canonical((a, b))];
+        let bc = edge_index[&canonical((b, c))];
+        let ca = edge_index[&canonical((c, a))];
+        new_faces.extend([vec![a, ab, ca], vec![b, bc, ab], vec![c, ca, bc], vec![ab, bc, ca]]);
+    }
+
+    (out, new_faces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::polyhedra::Platonic;
+
+    fn counts(mesh: &Mesh) -> (usize, usize, usize) {
+        (mesh.vertices.len(), mesh.lines.len(), mesh.faces.len())
+    }
+
+    fn closed(mesh: &Mesh) -> bool {
+        mesh.is_closed() && mesh.is_manifold() && mesh.euler_characteristic() == 2
+    }
+
+    #[test]
+    fn catmull_clark() {
+        let cube = Mesh::mk_cube();
+        let once = cube.subdivide(Scheme::CatmullClark, 1).unwrap();
+        assert_eq!(counts(&once), (26, 48, 24));
+        assert!(once.faces.iter().all(|face| face.len() == 4));
+        assert!(closed(&once));
+        assert_eq!(counts(&cube.subdivide(Scheme::CatmullClark, 2).unwrap()), (98, 192, 96));
+        assert_eq!(counts(&cube.subdivide(Scheme::CatmullClark, 0).unwrap()), (8, 12, 6));
+
+        // the corners are pulled in towards a sphere: (Q + 2R + 0P) / 3 with Q = (1/3, 1/3, 1/3)
+        // and R = (2/3, 2/3, 2/3) for the corner at (1, 1, 1)
+        let corner = cube.vertices.iter().position(|v| *v == Vec3::new([1.0, 1.0, 1.0])).unwrap();
+        assert!(once.vertices[corner].approx_eq(&Vec3::new([5.0 / 9.0; 3]), 1e-12));
+        assert_eq!(once.rotation, cube.rotation);
+    }
+
+    #[test]
+    fn loop_scheme() {
+        let icosahedron = Mesh::mk_platonic(Platonic::Icosahedron);
+        let once = icosahedron.subdivide(Scheme::Loop, 1).unwrap();
+        assert_eq!(counts(&once), (42, 120, 80));
+        assert!(once.faces.iter().all(|face| face.len() == 3));
+        assert!(closed(&once));
+        assert_eq!(counts(&icosahedron.subdivide(Scheme::Loop, 2).unwrap()), (162, 480, 320));
+
+        // a cube's squares are fanned into 24 triangles around 6 new middles first
+        let cube = Mesh::mk_cube().subdivide(Scheme::Loop, 1).unwrap();
+        assert_eq!(counts(&cube), (14 + 36, 2 * 36 + 3 * 24, 96));
+        assert!(closed(&cube));
+    }
+
+    #[test]
+    fn open_surfaces_and_loose_lines() {
+        // a square stays flat and its outline stays a loop of boundary edges
+        let square = Mesh::with_faces(Vec3::zeroes(), vec![
+            Vec3::new([0.0, 0.0, 0.0]),
+            Vec3::new([1.0, 0.0, 0.0]),
+            Vec3::new([1.0, 1.0, 0.0]),
+            Vec3::new([0.0, 1.0, 0.0]),
+        ], vec![vec![0, 1, 2, 3]]).unwrap();
+        for scheme in [Scheme::CatmullClark, Scheme::Loop] {
+            let mesh = square.subdivide(scheme, 2).unwrap();
+            assert!(mesh.vertices.iter().all(|v| v.z() == 0.0));
+            assert!(!mesh.is_closed());
+            assert!(mesh.is_manifold());
+            assert_eq!(mesh.euler_characteristic(), 1);
+        }
+        assert_eq!(counts(&square.subdivide(Scheme::CatmullClark, 1).unwrap()), (9, 12, 4));
+
+        // a flagpole on the cube comes along unchanged
+        let mut flagged = Mesh::mk_cube();
+        let tip = Vec3::new([0.0, 3.0, 0.0]);
+        flagged.vertices.push(tip);
+        flagged.add_lines(vec![(0, 8)]).unwrap();
+        let mesh = flagged.subdivide(Scheme::CatmullClark, 2).unwrap();
+        assert_eq!(mesh.vertices[8], tip);
+        assert!(mesh.lines.contains(&(0, 8)));
+        assert_eq!(mesh.lines.len(), 192 + 1);
+    }
+
+    #[test]
+    fn bad_meshes() {
+        let wire = Mesh::new(Vec3::zeroes(), vec![Vec3::zeroes(), Vec3::new([1.0, 0.0, 0.0])], vec![(0, 1)]).unwrap();
+        assert_eq!(wire.subdivide(Scheme::Loop, 1).unwrap_err().message, "the mesh has no faces to work on");
+
+        // three triangles on one edge
+        let vertices = (0..5).map(|i| Vec3::new([i as Element, (i * i) as Element, (i % 2) as Element])).collect();
+        let fins = Mesh::with_faces(Vec3::zeroes(), vertices, vec![vec![0, 1, 2], vec![1, 0, 3], vec![0, 1, 4]]).unwrap();
+        assert_eq!(fins.subdivide(Scheme::CatmullClark, 1).unwrap_err().message, "more than two faces meet at the edge (0, 1)");
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Scheme::from_name("catmull_clark"), Some(Scheme::CatmullClark));
+        assert_eq!(Scheme::from_name("loop"), Some(Scheme::Loop));
+        assert_eq!(Scheme::from_name("doo_sabin"), None);
+    }
+}
@@ -0,0 +1,202 @@
+// Text as wireframes, in a tiny single-stroke vector font in the spirit of the Hershey fonts: every
+// glyph is a few pen strokes on a grid 4 units wide and 6 tall (capitals only, lower case is drawn
+// the same). Given some depth each stroke becomes a ribbon: a copy in front and one behind, joined
+// at every corner, with faces so hidden lines and solid shading work on words too.
+//
+// Where more than two strokes meet (the middle of a B, the foot of a Y) the ribbons are paired up
+// and each pair gets its own copy of the corner, so no edge is shared by more than two faces and
+// the surface stays manifold.
+//
+// Characters the font doesn't have come out as a box.
+
+use std::collections::HashMap;
+
+use crate::mesh::{Face, Line, Mesh, MeshError};
+use crate::point::{Element, Vec3};
+
+const GLYPH_WIDTH: i64 = 4;
+const GLYPH_HEIGHT: i64 = 6;
+// from the start of one glyph to the next, and one line to the next
+const ADVANCE: i64 = GLYPH_WIDTH + 2;
+const LINE_SPACING: i64 = GLYPH_HEIGHT + 3;
+
+const MISSING: &str = "00 06 46 40 00";
+
+// Strokes are separated by `|` and each is a list of points, a digit for x then one for y (up).
+fn glyph(c: char) -> &'static str {
+    match c.to_ascii_uppercase() {
+        'A' => "00 26 40|13 33",
+        'B' => "00 06 36 45 44 33 03|33 42 41 30 00",
+        'C' => "45 36 16 05 01 10 30 41",
+        'D' => "00 06 26 44 42 20 00",
+        'E' => "40 00 06 46|03 33",
+        'F' => "00 06 46|03 33",
+        'G' => "45 36 16 05 01 10 30 41 43 23",
+        'H' => "00 06|40 46|03 43",
+        'I' => "10 30|16 36|20 26",
+        'J' => "46 41 30 10 01 02",
+        'K' => "00 06|02 46|13 40",
+        'L' => "06 00 40",
+        'M' => "00 06 23 46 40",
+        'N' => "00 06 40 46",
+        'O' => "16 05 01 10 30 41 45 36 16",
+        'P' => "00 06 36 45 44 33 03",
+        'Q' => "16 05 01 10 30 41 45 36 16|22 40",
+        'R' => "00 06 36 45 44 33 03|23 40",
+        'S' => "45 36 16 05 04 13 33 42 41 30 10 01",
+        'T' => "06 46|20 26",
+        'U' => "06 01 10 30 41 46",
+        'V' => "06 20 46",
+        'W' => "06 10 23 30 46",
+        'X' => "00 46|06 40",
+        'Y' => "06 23 46|23 20",
+        'Z' => "06 46 00 40",
+        '0' => "16 05 01 10 30 41 45 36 16|01 45",
+        '1' => "15 26 20|10 30",
+        '2' => "05 16 36 45 44 00 40",
+        '3' => "05 16 36 45 44 33 13|33 42 41 30 10 01",
+        '4' => "30 36 02 42",
+        '5' => "46 06 04 34 43 41 30 10 01",
+        '6' => "45 36 16 05 01 10 30 41 42 33 13 02",
+        '7' => "06 46 20",
+        '8' => "13 04 05 16 36 45 44 33 13 02 01 10 30 41 42 33",
+        '9' => "01 10 30 41 45 36 16 05 04 13 33 44",
+        '.' => "20 21",
+        ',' => "21 10",
+        '!' => "26 22|20 21",
+        '?' => "05 16 36 45 44 33 23 22|20 21",
+        '-' => "13 33",
+        '\'' => "26 25",
+        ':' => "22 23|24 25",
+        ' ' => "",
+        _ => MISSING,
+    }
+}
+
+// Each stroke of `c` as grid points.
+fn strokes(c: char) -> Vec<Vec<(i64, i64)>> {
+    glyph(c)
+        .split('|')
+        .filter(|stroke| !stroke.is_empty())
+        .map(|stroke| stroke
+            .split_whitespace()
+            .map(|point| {
+                let digits = point.as_bytes();
+                ((digits[0] - b'0') as i64, (digits[1] - b'0') as i64)
+            })
+            .collect())
+        .collect()
+}
+
+impl Mesh {
+    // `text` with capitals 1 tall and the whole block centred on the origin. With a `depth` of 0
+    // it's flat lines, otherwise ribbons `depth` thick. Fails if there's nothing to draw.
+    pub fn mk_text(text: &str, depth: Element) -> Result<Mesh, MeshError> {
+        // every stroke, on the grid for the whole block of text
+        let mut all = vec![];
+        for (row, line) in text.lines().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                let (x, y) = (column as i64 * ADVANCE, -(row as i64) * LINE_SPACING);
+                all.extend(strokes(c)
+                    .into_iter()
+                    .map(|stroke| stroke.into_iter().map(|(gx, gy)| (x + gx, y + gy)).collect::<Vec<(i64, i64)>>()));
+            }
+        }
+
+        if all.is_empty() {
+            return Err(MeshError::Empty);
+        }
+
+        let (mut min, mut max) = ((i64::MAX, i64::MAX), (i64::MIN, i64::MIN));
+        for &(x, y) in all.iter().flatten() {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        let scale = 1.0 / GLYPH_HEIGHT as Element;
+        let centre = ((min.0 + max.0) as Element / 2.0, (min.1 + max.1) as Element / 2.0);
+
+        // (x, y, which side, which copy) -> vertex
+        let mut index = HashMap::<(i64, i64, bool, usize), usize>::new();
+        let mut vertices = vec![];
+        let mut vertex = |x: i64, y: i64, back: bool, copy: usize| -> usize {
+            *index.entry((x, y, back, copy)).or_insert_with(|| {
+                let z = if back { depth / 2.0 } else { -depth / 2.0 };
+                vertices.push(Vec3::new([
+                    (x as Element - centre.0) * scale,
+                    (y as Element - centre.1) * scale,
+                    z,
+                ]));
+                vertices.len() - 1
+            })
+        };
+
+        if depth == 0.0 {
+            let mut lines = Vec::<Line>::new();
+            for stroke in all.iter() {
+                for pair in stroke.windows(2) {
+                    lines.push((vertex(pair[0].0, pair[0].1, false, 0), vertex(pair[1].0, pair[1].1, false, 0)));
+                }
+            }
+            return Mesh::new(Vec3::zeroes(), vertices, lines);
+        }
+
+        // Segment ends at each point so far; every two in a row share a copy. A stroke's segments
+        // come one after the other so its corners pair up with each other first.
+        let mut ends = HashMap::<(i64, i64), usize>::new();
+        let mut copy = |x: i64, y: i64| -> usize {
+            let seen = ends.entry((x, y)).or_insert(0);
+            *seen += 1;
+            (*seen - 1) / 2
+        };
+
+        let mut faces = Vec::<Face>::new();
+        for stroke in all.iter() {
+            for pair in stroke.windows(2) {
+                let ((ax, ay), (bx, by)) = (pair[0], pair[1]);
+                let (a, b) = (copy(ax, ay), copy(bx, by));
+                faces.push(vec![vertex(ax, ay, false, a), vertex(bx, by, false, b), vertex(bx, by, true, b), vertex(ax, ay, true, a)]);
+            }
+        }
+        Mesh::with_faces(Vec3::zeroes(), vertices, faces)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_lines() {
+        let mesh = Mesh::mk_text("L", 0.0).unwrap();
+        assert_eq!((mesh.vertices.len(), mesh.lines.len(), mesh.faces.len()), (3, 2, 0));
+        // capitals are 1 tall
+        let height = mesh.vertices.iter().map(|v| v.y()).fold(Element::MIN, Element::max)
+            - mesh.vertices.iter().map(|v| v.y()).fold(Element::MAX, Element::min);
+        assert!((height - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ribbons() {
+        let mesh = Mesh::mk_text("L", 0.2).unwrap();
+        assert_eq!((mesh.vertices.len(), mesh.lines.len(), mesh.faces.len()), (6, 7, 2));
+        assert!(mesh.is_manifold());
+    }
+
+    #[test]
+    fn junctions_stay_manifold() {
+        let everything = "ABCDEFGHIJKLMNOPQRSTUVWXYZ\n0123456789.,!?-':";
+        for text in ["B", "3", "8", "0", "Y", "APATE 2026", everything, "~"] {
+            let mesh = Mesh::mk_text(text, 0.2).unwrap();
+            assert!(mesh.is_manifold(), "{:?}", text);
+        }
+        for c in everything.chars().filter(|c| !c.is_whitespace()) {
+            assert!(Mesh::mk_text(&c.to_string(), 0.2).unwrap().is_manifold(), "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn nothing_to_draw() {
+        assert_eq!(Mesh::mk_text("", 0.0).unwrap_err(), MeshError::Empty);
+        assert_eq!(Mesh::mk_text("   \n ", 0.2).unwrap_err(), MeshError::Empty);
+    }
+}
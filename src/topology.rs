@@ -0,0 +1,275 @@
+// Surface topology for meshes that have faces. `HalfEdges` is the usual half-edge structure: every
+// face is a loop of half-edges and a half-edge's twin (if it has one) runs the other way along the
+// same edge in the neighbouring face. Faces are wound so that the sum of the cross products of
+// consecutive corners points out of the surface (see `face_normal`).
+
+use std::collections::HashMap;
+
+use crate::mesh::Face;
+use crate::point::Vec3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HalfEdge {
+    // the vertex this half-edge leaves from; it arrives at `next`'s origin
+    pub origin: usize,
+    pub face: usize,
+    pub next: usize,
+    pub prev: usize,
+    // `None` on a boundary
+    pub twin: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HalfEdges {
+    pub half_edges: Vec<HalfEdge>,
+    // one half-edge of every face
+    pub face_start: Vec<usize>,
+    // every half-edge leaving each vertex
+    pub outgoing: Vec<Vec<usize>>,
+    // Set when two faces walk the same edge in the same direction (the winding is inconsistent)
+    // or more than two faces share an edge. Twins are left unset for those edges.
+    pub tangled: bool,
+}
+
+// Twice the area vector of a polygon: the sum of the cross products of consecutive corners.
+// Works for non-planar and non-convex faces and for triangles is exactly (b - a) x (c - a).
+pub fn face_area_vector(vertices: &[Vec3], face: &Face) -> Vec3 {
+    (0..face.len())
+        .map(|i| vertices[face[i]].cross(&vertices[face[(i + 1) % face.len()]]))
+        .fold(Vec3::zeroes(), |acc, x| acc + x)
+}
+
+pub fn face_normal(vertices: &[Vec3], face: &Face) -> Vec3 {
+    face_area_vector(vertices, face).normal()
+}
+
+impl HalfEdges {
+    pub fn new(vertex_count: usize, faces: &[Face]) -> Self {
+        let mut half_edges = Vec::<HalfEdge>::new();
+        let mut face_start = Vec::with_capacity(faces.len());
+        let mut outgoing = vec![vec![]; vertex_count];
+        for (f, face) in faces.iter().enumerate() {
+            let base = half_edges.len();
+            let n = face.len();
+            face_start.push(base);
+            for (i, &origin) in face.iter().enumerate() {
+                half_edges.push(HalfEdge {
+                    origin,
+                    face: f,
+                    next: base + (i + 1) % n,
+                    prev: base + (i + n - 1) % n,
+                    twin: None,
+                });
+                outgoing[origin].push(base + i);
+            }
+        }
+
+        // (from, to) -> the half-edges walking it
+        let mut directed = HashMap::<(usize, usize), Vec<usize>>::new();
+        for (h, half_edge) in half_edges.iter().enumerate() {
+            let to = half_edges[half_edge.next].origin;
+            directed.entry((half_edge.origin, to)).or_default().push(h);
+        }
+
+        let mut tangled = false;
+        for (&(from, to), forward) in directed.iter() {
+            let backward = directed.get(&(to, from)).map(Vec::as_slice).unwrap_or(&[]);
+            match (forward.as_slice(), backward) {
+                (&[h], &[t]) => half_edges[h].twin = Some(t),
+                (&[_], &[]) => {},
+                _ => tangled = true,
+            }
+        }
+
+        Self {
+            half_edges,
+            face_start,
+            outgoing,
+            tangled,
+        }
+    }
+
+    pub fn destination(&self, h: usize) -> usize {
+        self.half_edges[self.half_edges[h].next].origin
+    }
+
+    pub fn is_closed(&self) -> bool {
+        !self.tangled && self.half_edges.iter().all(|h| h.twin.is_some())
+    }
+
+    // Each edge belongs to at most two consistently wound faces and the faces around every
+    // vertex form a single fan (a disc, or a half disc on the boundary) rather than, say, two
+    // cones touching at their tips.
+    pub fn is_manifold(&self) -> bool {
+        if self.tangled {
+            return false;
+        }
+
+        for out in self.outgoing.iter() {
+            if out.is_empty() {
+                continue;
+            }
+
+            // Turning around a vertex: from an outgoing half-edge, its `prev` comes in to the
+            // vertex and that one's twin leaves it again in the next face over. Without a twin
+            // there's no way to have arrived at a half-edge, so it starts the fan.
+            let starts = out.iter()
+                .filter(|&&h| self.half_edges[h].twin.is_none())
+                .collect::<Vec<&usize>>();
+            let start = match starts.as_slice() {
+                [] => out[0],
+                [start] => **start,
+                _ => return false,
+            };
+
+            let mut visited = 1;
+            let mut h = start;
+            while let Some(t) = self.half_edges[self.half_edges[h].prev].twin {
+                if t == start {
+                    break;
+                }
+                h = t;
+                visited += 1;
+                if visited > out.len() {
+                    return false;
+                }
+            }
+            if visited != out.len() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mesh::Mesh;
+
+    fn points(count: usize) -> Vec<Vec3> {
+        (0..count).map(|i| Vec3::new([i as f64, (i * i) as f64, (i % 3) as f64])).collect()
+    }
+
+    fn outward(mesh: &Mesh) -> bool {
+        mesh.faces.iter().all(|face| {
+            let centre = face.iter().fold(Vec3::zeroes(), |acc, &v| acc + mesh.vertices[v]);
+            face_area_vector(&mesh.vertices, face).dot(&centre) > 0.0
+        })
+    }
+
+    #[test]
+    fn areas() {
+        let square = vec![Vec3::new([0.0, 0.0, 0.0]), Vec3::new([2.0, 0.0, 0.0]), Vec3::new([2.0, 2.0, 0.0]), Vec3::new([0.0, 2.0, 0.0])];
+        assert_eq!(face_area_vector(&square, &vec![0, 1, 2, 3]), Vec3::new([0.0, 0.0, 8.0]));
+        assert_eq!(face_normal(&square, &vec![3, 2, 1, 0]), Vec3::new([0.0, 0.0, -1.0]));
+    }
+
+    #[test]
+    fn cube_half_edges() {
+        let cube = Mesh::mk_cube();
+        let half_edges = cube.half_edges();
+        assert_eq!(half_edges.half_edges.len(), 24);
+        assert_eq!(half_edges.face_start, vec![0, 4, 8, 12, 16, 20]);
+        assert!(half_edges.outgoing.iter().all(|out| out.len() == 3));
+        assert!(!half_edges.tangled);
+
+        for (h, half_edge) in half_edges.half_edges.iter().enumerate() {
+            let twin = half_edge.twin.unwrap();
+            assert_eq!(half_edges.half_edges[twin].twin, Some(h));
+            assert_eq!(half_edges.half_edges[twin].origin, half_edges.destination(h));
+            assert_ne!(half_edges.half_edges[twin].face, half_edge.face);
+            assert_eq!(half_edges.half_edges[half_edge.next].prev, h);
+        }
+
+        assert!(cube.is_closed());
+        assert!(cube.is_manifold());
+        assert!(outward(&cube));
+    }
+
+    #[test]
+    fn open_and_pinched() {
+        // a box without a lid has a boundary but is still a surface
+        let mut open = Mesh::mk_cube();
+        open.faces.pop();
+        assert!(!open.is_closed());
+        assert!(open.is_manifold());
+
+        // two triangles touching at a corner; no fan goes all the way round vertex 0
+        let bowtie = Mesh::with_faces(Vec3::zeroes(), points(5), vec![vec![0, 1, 2], vec![0, 3, 4]]).unwrap();
+        assert!(!bowtie.is_closed());
+        assert!(!bowtie.is_manifold());
+
+        // three triangles on one edge
+        let fins = HalfEdges::new(5, &[vec![0, 1, 2], vec![1, 0, 3], vec![1, 0, 4]]);
+        assert!(fins.tangled);
+        assert!(!fins.is_manifold());
+
+        // no faces, nothing to be closed
+        let wire = Mesh::new(Vec3::zeroes(), points(2), vec![(0, 1)]).unwrap();
+        assert!(!wire.is_closed());
+    }
+
+    #[test]
+    fn orienting() {
+        // one face the wrong way round tangles the half-edges until it's flipped back
+        let mut cube = Mesh::mk_cube();
+        cube.faces[2].reverse();
+        assert!(cube.half_edges().tangled);
+        assert!(cube.orient_faces());
+        assert!(cube.is_manifold());
+        assert!(outward(&cube));
+
+        // all of them wound inwards
+        let inside_out = Mesh::mk_cube().faces.into_iter()
+            .map(|mut face| {
+                face.reverse();
+                face
+            })
+            .collect::<Vec<Face>>();
+        let cube = Mesh::with_faces(Vec3::zeroes(), Mesh::mk_cube().vertices, inside_out).unwrap();
+        assert!(outward(&cube));
+
+        // a Moebius strip: four quads in a ring with the last one glued on with a half twist
+        let mut strip = Mesh::with_faces(Vec3::zeroes(), points(8), vec![
+            vec![0, 1, 5, 4],
+            vec![1, 2, 6, 5],
+            vec![2, 3, 7, 6],
+            vec![3, 4, 0, 7],
+        ]).unwrap();
+        assert!(!strip.orient_faces());
+    }
+
+    #[test]
+    fn solids_touching_along_an_edge() {
+        // two cubes sharing the edge from (1, 1, -1) to (1, 1, 1), each with a face turned inside
+        // out that has to be put right without looking at the other cube
+        let cube = Mesh::mk_cube();
+        let shift = Vec3::new([2.0, 2.0, 0.0]);
+        let mut vertices = cube.vertices.clone();
+        let mut faces = cube.faces.clone();
+        for v in cube.vertices.iter() {
+            let moved = *v + shift;
+            if !vertices.contains(&moved) {
+                vertices.push(moved);
+            }
+        }
+        for face in cube.faces.iter() {
+            faces.push(face.iter()
+                .map(|&v| vertices.iter().position(|&w| w == cube.vertices[v] + shift).unwrap())
+                .collect());
+        }
+        faces[1].reverse();
+        faces[9].reverse();
+
+        let mesh = Mesh::with_faces(Vec3::zeroes(), vertices, faces).unwrap();
+        assert_eq!(mesh.vertices.len(), 14);
+        for (f, face) in mesh.faces.iter().enumerate() {
+            let centre = if f < 6 { Vec3::zeroes() } else { shift };
+            let middle = face.iter().fold(Vec3::zeroes(), |acc, &v| acc + mesh.vertices[v] - centre);
+            assert!(face_area_vector(&mesh.vertices, face).dot(&middle) > 0.0);
+        }
+    }
+}